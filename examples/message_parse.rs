@@ -56,13 +56,10 @@ R0lGODlhAQABAIAAAAAAAP///yH5BAEAAAAALAAAAAABAAEAAAIBRAA7
     let message = MessageParser::default().parse(input).unwrap();
 
     // Parses addresses (including comments), lists and groups
-    assert_eq!(
-        message.from().unwrap().first().unwrap(),
-        &Addr::new(
-            "Art Vandelay (Vandelay Industries)".into(),
-            "art@vandelay.com"
-        )
-    );
+    let from = message.from().unwrap().first().unwrap();
+    assert_eq!(from.name(), Some("Art Vandelay"));
+    assert_eq!(from.address(), Some("art@vandelay.com"));
+    assert_eq!(from.comment(), Some("Vandelay Industries"));
 
     assert_eq!(
         message.to().unwrap().as_group().unwrap(),
@@ -9,7 +9,10 @@
  * except according to those terms.
  */
 
-use crate::{Addr, Address, Group};
+use alloc::{borrow::Cow, boxed::Box, format, string::String, string::ToString, vec, vec::Vec};
+use core::fmt::{self, Display, Write};
+
+use crate::{decoders::base64::base64_encode, Addr, Address, Group};
 
 impl<'x> Address<'x> {
     /// Returns the first address in the list, or the first address in the first group.
@@ -73,7 +76,7 @@ impl<'x> Address<'x> {
     }
 
     /// Returns an iterator over the addresses in the list, or the addresses in the groups.
-    pub fn iter<'y: 'x>(&'y self) -> Box<dyn DoubleEndedIterator<Item = &Addr<'x>> + 'x> {
+    pub fn iter(&self) -> Box<dyn DoubleEndedIterator<Item = &Addr<'x>> + '_> {
         match self {
             Address::List(list) => Box::new(list.iter()),
             Address::Group(group) => {
@@ -82,22 +85,23 @@ impl<'x> Address<'x> {
         }
     }
 
-    /// Returns whether the list contains the given address.
+    /// Returns an iterator over the e-mail addresses in the list or groups,
+    /// skipping entries without an address.
+    pub fn addresses(&self) -> impl DoubleEndedIterator<Item = &str> + '_ {
+        self.iter().filter_map(|addr| addr.address.as_deref())
+    }
+
+    /// Returns whether the list or any of the groups contains the given e-mail
+    /// address. Addresses are compared ignoring ASCII case, including their local
+    /// part, which RFC5321 allows to be case-sensitive but which mail systems
+    /// treat as case-insensitive in practice.
     pub fn contains(&self, addr: &str) -> bool {
-        match self {
-            Address::List(list) => list.iter().any(|a| {
-                a.address
-                    .as_ref()
-                    .map_or(false, |a| a.eq_ignore_ascii_case(addr))
-            }),
-            Address::Group(group) => group.iter().any(|group| {
-                group.addresses.iter().any(|a| {
-                    a.address
-                        .as_ref()
-                        .map_or(false, |a| a.eq_ignore_ascii_case(addr))
-                })
-            }),
-        }
+        self.addresses().any(|a| a.eq_ignore_ascii_case(addr))
+    }
+
+    /// Returns the address formatted as an RFC5322 header value.
+    pub fn to_header_value(&self) -> String {
+        self.to_string()
     }
 
     pub fn into_owned(self) -> Address<'static> {
@@ -112,6 +116,24 @@ impl<'x> Address<'x> {
     }
 }
 
+impl<'x> IntoIterator for Address<'x> {
+    type Item = Addr<'x>;
+    type IntoIter = alloc::vec::IntoIter<Addr<'x>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.into_list().into_iter()
+    }
+}
+
+impl<'y, 'x> IntoIterator for &'y Address<'x> {
+    type Item = &'y Addr<'x>;
+    type IntoIter = Box<dyn DoubleEndedIterator<Item = &'y Addr<'x>> + 'y>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
 impl<'x> Group<'x> {
     pub fn new(name: &'x str, addresses: Vec<Addr<'x>>) -> Self {
         Self {
@@ -133,6 +155,7 @@ impl<'x> Addr<'x> {
         Self {
             name: name.map(|name| name.into()),
             address: Some(address.into()),
+            comment: None,
         }
     }
 
@@ -140,6 +163,7 @@ impl<'x> Addr<'x> {
         Addr {
             name: self.name.map(|s| s.into_owned().into()),
             address: self.address.map(|s| s.into_owned().into()),
+            comment: self.comment.map(|s| s.into_owned().into()),
         }
     }
 
@@ -150,4 +174,379 @@ impl<'x> Addr<'x> {
     pub fn address(&self) -> Option<&str> {
         self.address.as_deref()
     }
+
+    /// Returns the text of the address comments, joined by spaces.
+    pub fn comment(&self) -> Option<&str> {
+        self.comment.as_deref()
+    }
+
+    /// Returns the local part of the address, or `None` if the address is malformed.
+    pub fn local_part(&self) -> Option<&str> {
+        self.split_address().map(|(local_part, _)| local_part)
+    }
+
+    /// Returns the domain part of the address, or `None` if the address is malformed.
+    pub fn domain(&self) -> Option<&str> {
+        self.split_address().map(|(_, domain)| domain)
+    }
+
+    /// Returns the address with any `+tag` subaddress removed from the local part.
+    pub fn plus_address_base(&self) -> Option<Cow<'_, str>> {
+        let (local_part, domain) = self.split_address()?;
+        if local_part.starts_with('"') {
+            return self.address().map(Cow::Borrowed);
+        }
+        match local_part.split_once('+') {
+            Some((base, _)) if !base.is_empty() => Some(format!("{base}@{domain}").into()),
+            _ => self.address().map(Cow::Borrowed),
+        }
+    }
+
+    /// Splits the address at the last `@` outside a quoted local part.
+    fn split_address(&self) -> Option<(&str, &str)> {
+        let address = self.address()?;
+        let mut in_quote = false;
+        let mut is_escaped = false;
+        let mut split_pos = None;
+
+        for (pos, ch) in address.char_indices() {
+            match ch {
+                _ if is_escaped => is_escaped = false,
+                '\\' if in_quote => is_escaped = true,
+                '"' => in_quote = !in_quote,
+                '@' if !in_quote => split_pos = Some(pos),
+                _ => (),
+            }
+        }
+
+        let (local_part, domain) = address.split_at(split_pos.filter(|_| !in_quote)?);
+        let domain = &domain[1..];
+        (!local_part.is_empty() && !domain.is_empty()).then_some((local_part, domain))
+    }
+
+    /// Returns the domain part of the address with any Punycode labels
+    /// decoded to Unicode. Invalid labels are returned unchanged.
+    #[cfg(feature = "domain")]
+    pub fn domain_unicode(&self) -> Option<Cow<'_, str>> {
+        self.domain()
+            .map(crate::decoders::punycode::domain_to_unicode)
+    }
+
+    /// Returns `true` if the domain part is an internationalized domain name,
+    /// either in Punycode or in Unicode form.
+    #[cfg(feature = "domain")]
+    pub fn is_idn(&self) -> bool {
+        self.domain().is_some_and(|domain| {
+            !domain.is_ascii()
+                || domain
+                    .split('.')
+                    .any(crate::decoders::punycode::is_ace_label)
+        })
+    }
+}
+
+impl<'x> Display for Address<'x> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Address::List(list) => write_list(f, list),
+            Address::Group(groups) => {
+                for (pos, group) in groups.iter().enumerate() {
+                    if pos > 0 {
+                        f.write_str(", ")?;
+                    }
+                    group.fmt(f)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl<'x> Display for Group<'x> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(name) = &self.name {
+            write_phrase(f, name)?;
+            f.write_str(": ")?;
+            write_list(f, &self.addresses)?;
+            f.write_char(';')
+        } else {
+            write_list(f, &self.addresses)
+        }
+    }
+}
+
+impl<'x> Display for Addr<'x> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let comment = self
+            .comment
+            .as_deref()
+            .filter(|comment| !comment.is_empty());
+        match (self.name.as_deref(), self.address.as_deref()) {
+            // Without a display name the parser uses the comment as the name,
+            // write it back in its original place.
+            (Some(name), Some(address)) if comment == Some(name) => {
+                f.write_str(address)?;
+                return write_comment(f, name);
+            }
+            (Some(name), address) if !name.is_empty() => {
+                write_phrase(f, name)?;
+                write!(f, " <{}>", address.unwrap_or_default())?;
+            }
+            (_, Some(address)) => f.write_str(address)?,
+            _ => f.write_str("<>")?,
+        }
+        if let Some(comment) = comment {
+            write_comment(f, comment)?;
+        }
+        Ok(())
+    }
+}
+
+fn write_list(f: &mut fmt::Formatter<'_>, list: &[Addr<'_>]) -> fmt::Result {
+    for (pos, addr) in list.iter().enumerate() {
+        if pos > 0 {
+            f.write_str(", ")?;
+        }
+        addr.fmt(f)?;
+    }
+    Ok(())
+}
+
+fn write_phrase(f: &mut fmt::Formatter<'_>, phrase: &str) -> fmt::Result {
+    if !phrase.is_ascii() {
+        write_encoded_words(f, phrase)
+    } else if phrase.split(' ').all(|word| {
+        !word.is_empty()
+            && word
+                .bytes()
+                .all(|ch| ch.is_ascii_alphanumeric() || b"!#$%&'*+-/=?^_`{|}~".contains(&ch))
+    }) {
+        f.write_str(phrase)
+    } else {
+        f.write_char('"')?;
+        for ch in phrase.chars() {
+            match ch {
+                '"' | '\\' => {
+                    f.write_char('\\')?;
+                    f.write_char(ch)?;
+                }
+                '\r' | '\n' => (),
+                _ => f.write_char(ch)?,
+            }
+        }
+        f.write_char('"')
+    }
+}
+
+fn write_comment(f: &mut fmt::Formatter<'_>, comment: &str) -> fmt::Result {
+    f.write_str(" (")?;
+    if !comment.is_ascii() {
+        write_encoded_words(f, comment)?;
+    } else {
+        for ch in comment.chars() {
+            match ch {
+                '(' | ')' | '\\' => {
+                    f.write_char('\\')?;
+                    f.write_char(ch)?;
+                }
+                '\r' | '\n' => (),
+                _ => f.write_char(ch)?,
+            }
+        }
+    }
+    f.write_char(')')
+}
+
+/// Writes a text as space separated RFC2047 encoded-words of at most 75 characters
+pub(crate) fn write_encoded_words(f: &mut impl Write, text: &str) -> fmt::Result {
+    let mut chunk_start = 0;
+    for (pos, ch) in text.char_indices() {
+        if pos + ch.len_utf8() - chunk_start > 45 {
+            write_encoded_word(f, &text[chunk_start..pos], chunk_start > 0)?;
+            chunk_start = pos;
+        }
+    }
+    write_encoded_word(f, &text[chunk_start..], chunk_start > 0)
+}
+
+fn write_encoded_word(f: &mut impl Write, text: &str, add_space: bool) -> fmt::Result {
+    if add_space {
+        f.write_char(' ')?;
+    }
+    f.write_str("=?utf-8?b?")?;
+    for ch in base64_encode(text.as_bytes()) {
+        f.write_char(ch as char)?;
+    }
+    f.write_str("?=")
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{parsers::MessageStream, Addr, Address};
+
+    #[test]
+    fn address_to_header_value() {
+        for (input, expected) in [
+            ("john@example.com", "john@example.com"),
+            ("John Doe <john@example.com>", "John Doe <john@example.com>"),
+            (
+                "\"Doe, John\" <john@example.com>",
+                "\"Doe, John\" <john@example.com>",
+            ),
+            ("\"J. Doe\" <j@example.com>", "\"J. Doe\" <j@example.com>"),
+            (
+                "\"Say \\\"hi\\\" \\\\o/\" <hi@example.com>",
+                "\"Say \\\"hi\\\" \\\\o/\" <hi@example.com>",
+            ),
+            (
+                "=?utf-8?q?Jos=C3=A9?= <jose@example.com>, ann@example.com",
+                "=?utf-8?b?Sm9zw6k=?= <jose@example.com>, ann@example.com",
+            ),
+            (
+                "Friends: jerry@example.com, Kramer <k@example.com>;, Empty:;",
+                "Friends: jerry@example.com, Kramer <k@example.com>;, Empty: ;",
+            ),
+            (
+                "Art <art@example.com>, Friends: jerry@example.com;",
+                "Art <art@example.com>, Friends: jerry@example.com;",
+            ),
+            ("\"Undisclosed\" <>", "Undisclosed <>"),
+            ("jdoe@example.com (John Doe)", "jdoe@example.com (John Doe)"),
+            (
+                "John <john@example.com> (work)",
+                "John <john@example.com> (work)",
+            ),
+            (
+                "ann@example.com (Ann \\(home\\))",
+                "ann@example.com (Ann \\(home\\))",
+            ),
+        ] {
+            let input = format!("{input}\n");
+            let address = MessageStream::new(input.as_bytes())
+                .parse_address()
+                .into_address()
+                .unwrap();
+            let output = address.to_header_value();
+            assert_eq!(output, expected, "failed for {input:?}");
+
+            // Parsing the output must produce the same address
+            let output = format!("{output}\n");
+            assert_eq!(
+                MessageStream::new(output.as_bytes())
+                    .parse_address()
+                    .into_address()
+                    .unwrap(),
+                address,
+                "failed for {output:?}"
+            );
+        }
+
+        // Long non-ASCII names are split into multiple encoded-words
+        let address = Address::List(vec![Addr::new(
+            Some("Ünïcödé Ñämé wïth sëvéräl wörds ïn ït"),
+            "long@example.com",
+        )]);
+        let output = format!("{address}\n");
+        assert_eq!(output.matches("=?utf-8?b?").count(), 2);
+        assert!(output.split(' ').all(|word| word.len() <= 75));
+        assert_eq!(
+            MessageStream::new(output.as_bytes())
+                .parse_address()
+                .into_address()
+                .unwrap(),
+            address,
+        );
+    }
+
+    #[cfg(feature = "domain")]
+    #[test]
+    fn address_domains() {
+        for (address, domain, domain_unicode, is_idn) in [
+            (
+                "jdoe@example.com",
+                Some("example.com"),
+                Some("example.com"),
+                false,
+            ),
+            (
+                "jdoe@xn--bcher-kva.example",
+                Some("xn--bcher-kva.example"),
+                Some("bücher.example"),
+                true,
+            ),
+            ("jdoe@例え.jp", Some("例え.jp"), Some("例え.jp"), true),
+            (
+                "jdoe@xn--zz!.example",
+                Some("xn--zz!.example"),
+                Some("xn--zz!.example"),
+                true,
+            ),
+            ("jdoe", None, None, false),
+            ("jdoe@", None, None, false),
+        ] {
+            let addr = Addr::new(None, address);
+            assert_eq!(addr.domain(), domain, "failed for {address:?}");
+            assert_eq!(
+                addr.domain_unicode().as_deref(),
+                domain_unicode,
+                "failed for {address:?}"
+            );
+            assert_eq!(addr.is_idn(), is_idn, "failed for {address:?}");
+        }
+    }
+
+    #[test]
+    fn address_parts() {
+        for (address, local_part, domain, plus_address_base) in [
+            (
+                "jdoe@example.com",
+                Some("jdoe"),
+                Some("example.com"),
+                Some("jdoe@example.com"),
+            ),
+            (
+                "jdoe+lists@example.com",
+                Some("jdoe+lists"),
+                Some("example.com"),
+                Some("jdoe@example.com"),
+            ),
+            (
+                "jdoe+a+b@example.com",
+                Some("jdoe+a+b"),
+                Some("example.com"),
+                Some("jdoe@example.com"),
+            ),
+            (
+                "+tag@example.com",
+                Some("+tag"),
+                Some("example.com"),
+                Some("+tag@example.com"),
+            ),
+            (
+                "\"a@b\"@example.com",
+                Some("\"a@b\""),
+                Some("example.com"),
+                Some("\"a@b\"@example.com"),
+            ),
+            (
+                "\"a\\\"@b+c\"@example.com",
+                Some("\"a\\\"@b+c\""),
+                Some("example.com"),
+                Some("\"a\\\"@b+c\"@example.com"),
+            ),
+            ("\"a@b@example.com", None, None, None),
+            ("jdoe", None, None, None),
+            ("jdoe@", None, None, None),
+            ("@example.com", None, None, None),
+        ] {
+            let addr = Addr::new(None, address);
+            assert_eq!(addr.local_part(), local_part, "failed for {address:?}");
+            assert_eq!(addr.domain(), domain, "failed for {address:?}");
+            assert_eq!(
+                addr.plus_address_base().as_deref(),
+                plus_address_base,
+                "failed for {address:?}"
+            );
+        }
+    }
 }
@@ -9,6 +9,9 @@
  * except according to those terms.
  */
 
+#[cfg(feature = "serde_support")]
+use alloc::{borrow::Cow, vec::Vec};
+
 use crate::{AttachmentIterator, BodyPartIterator, Message, MessagePart, MessagePartId, PartType};
 
 impl<'x> PartType<'x> {
@@ -52,7 +55,56 @@ impl<'x> Iterator for AttachmentIterator<'x> {
     type Item = &'x MessagePart<'x>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        self.pos += 1;
-        self.message.attachment(self.pos as usize)
+        loop {
+            self.pos += 1;
+            let part = self.message.attachment(self.pos as usize)?;
+            if part.is_attachment() {
+                return Some(part);
+            }
+        }
+    }
+}
+
+/// Deserializes a binary body, borrowing from the input when possible.
+/// Byte arrays are also accepted as formats such as JSON serialize
+/// byte slices as a sequence of numbers.
+#[cfg(feature = "serde_support")]
+pub(crate) fn deserialize_bytes<'de: 'x, 'x, D>(deserializer: D) -> Result<Cow<'x, [u8]>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    struct BytesVisitor;
+
+    impl<'de> serde::de::Visitor<'de> for BytesVisitor {
+        type Value = Cow<'de, [u8]>;
+
+        fn expecting(&self, formatter: &mut core::fmt::Formatter) -> core::fmt::Result {
+            formatter.write_str("a byte array")
+        }
+
+        fn visit_borrowed_bytes<E>(self, v: &'de [u8]) -> Result<Self::Value, E> {
+            Ok(Cow::Borrowed(v))
+        }
+
+        fn visit_bytes<E>(self, v: &[u8]) -> Result<Self::Value, E> {
+            Ok(Cow::Owned(v.to_vec()))
+        }
+
+        fn visit_byte_buf<E>(self, v: Vec<u8>) -> Result<Self::Value, E> {
+            Ok(Cow::Owned(v))
+        }
+
+        fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
+        where
+            A: serde::de::SeqAccess<'de>,
+        {
+            let mut bytes = Vec::with_capacity(seq.size_hint().unwrap_or(0));
+            while let Some(byte) = seq.next_element()? {
+                bytes.push(byte);
+            }
+            Ok(Cow::Owned(bytes))
+        }
     }
+
+    deserializer.deserialize_bytes(BytesVisitor)
 }
@@ -0,0 +1,82 @@
+/*
+ * Copyright Stalwart Labs Ltd. See the COPYING
+ * file at the top-level directory of this distribution.
+ *
+ * Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+ * https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+ * <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+ * option. This file may not be copied, modified, or distributed
+ * except according to those terms.
+ */
+
+use alloc::vec::Vec;
+
+use crate::{Header, Message, MessageBuffers, MessagePartId, PartType};
+
+impl MessageBuffers {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the vectors owned by a message, including those of its nested
+    /// messages, to the pool.
+    pub fn recycle(&mut self, message: Message<'_>) {
+        let mut parts = message.parts;
+        for part in parts.drain(..) {
+            self.headers.push(reuse(part.headers));
+            match part.body {
+                PartType::Multipart(mut part_ids) => {
+                    part_ids.clear();
+                    self.part_ids.push(part_ids);
+                }
+                PartType::Message(message) => self.recycle(message),
+                _ => (),
+            }
+        }
+        self.parts.push(reuse(parts));
+        for mut part_ids in [message.html_body, message.text_body, message.attachments] {
+            part_ids.clear();
+            self.part_ids.push(part_ids);
+        }
+    }
+
+    /// Removes all vectors from the pool, releasing their memory.
+    pub fn clear(&mut self) {
+        self.parts.clear();
+        self.headers.clear();
+        self.part_ids.clear();
+    }
+
+    pub(crate) fn message<'x>(&mut self) -> Message<'x> {
+        Message {
+            html_body: self.part_ids(),
+            text_body: self.part_ids(),
+            attachments: self.part_ids(),
+            parts: self.parts.pop().map(reuse).unwrap_or_default(),
+            raw_message: Default::default(),
+        }
+    }
+
+    pub(crate) fn headers<'x>(&mut self) -> Vec<Header<'x>> {
+        self.headers.pop().map(reuse).unwrap_or_default()
+    }
+
+    pub(crate) fn part_ids(&mut self) -> Vec<MessagePartId> {
+        self.part_ids.pop().unwrap_or_default()
+    }
+
+    pub(crate) fn recycle_headers(&mut self, headers: Vec<Header<'_>>) {
+        if headers.capacity() > 0 {
+            self.headers.push(reuse(headers));
+        }
+    }
+}
+
+/// Empties a vector and converts it to a vector of the same element type
+/// borrowing for a different lifetime. Since both element types share the
+/// same layout, the standard library collects in place and the allocation is
+/// kept.
+fn reuse<T, U>(mut items: Vec<T>) -> Vec<U> {
+    items.clear();
+    items.into_iter().map(|_| unreachable!()).collect()
+}
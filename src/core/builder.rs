@@ -9,7 +9,12 @@
  * except according to those terms.
  */
 
-use crate::{HeaderName, HeaderValue, MessageParser};
+use crate::{
+    decoders::charsets::map::charset_decoder, HeaderName, HeaderParser, HeaderValue, MessageParser,
+};
+
+const DEFAULT_MAX_NESTED_MESSAGES: usize = 100;
+const DEFAULT_MAX_MULTIPART_DEPTH: usize = 100;
 
 impl MessageParser {
     /// Create a new builder for a message parser using the default settings.
@@ -17,15 +22,180 @@ impl MessageParser {
     /// The default settings are:
     ///
     /// * IANA-registered headers defined in `HeaderName` are parsed with their corresponding parser.
+    /// * `Disposition-Notification-To` and `Return-Receipt-To` are parsed as addresses.
     /// * Other headers (`HeaderName::Other`) are parsed as raw.
+    /// * Up to 100 levels of nested messages and 100 levels of nested multiparts are parsed.
+    /// * `format=flowed` text bodies are decoded.
+    /// * There is no limit on the number or total size of the headers of each part.
+    /// * There is no limit on the size of the message.
     ///
     pub fn new() -> Self {
         Self {
             header_map: Default::default(),
-            def_hdr_parse_fnc: |s| s.parse_raw(),
+            def_hdr_parse_fnc: None,
+            max_nested_messages: DEFAULT_MAX_NESTED_MESSAGES,
+            max_multipart_depth: DEFAULT_MAX_MULTIPART_DEPTH,
+            decode_flowed: true,
+            default_charset: None,
+            preserve_raw_headers: false,
+            raw_bodies: false,
+            header_index: false,
+            max_headers: usize::MAX,
+            max_header_bytes: usize::MAX,
+            max_size: usize::MAX,
+            uudecode: false,
+            preserve_whitespace: false,
+            #[cfg(feature = "binhex")]
+            binhex: false,
+            #[cfg(feature = "detect_charset")]
+            detect_charset: false,
         }
     }
 
+    /// Set the maximum number of nested `message/rfc822` parts to parse.
+    ///
+    /// Messages nested beyond this limit are stored as binary parts
+    /// flagged with `is_encoding_problem`.
+    pub fn with_max_nested_messages(mut self, max_nested_messages: usize) -> Self {
+        self.max_nested_messages = max_nested_messages;
+        self
+    }
+
+    /// Set the maximum depth of nested `multipart/*` parts to parse.
+    ///
+    /// Multiparts nested beyond this limit are stored as binary parts
+    /// flagged with `is_encoding_problem`.
+    pub fn with_max_multipart_depth(mut self, max_multipart_depth: usize) -> Self {
+        self.max_multipart_depth = max_multipart_depth;
+        self
+    }
+
+    /// Set whether `text/plain` parts with `format=flowed` (RFC3676) are decoded.
+    ///
+    /// When disabled, the text of flowed parts is returned as found in the message.
+    pub fn with_flowed_decoding(mut self, decode_flowed: bool) -> Self {
+        self.decode_flowed = decode_flowed;
+        self
+    }
+
+    /// Set the charset used to decode text parts and RFC2047 encoded-words
+    /// when their charset is missing or not supported, instead of UTF-8.
+    ///
+    /// Unsupported charset names are ignored.
+    pub fn with_default_charset(mut self, charset: &str) -> Self {
+        self.default_charset = charset_decoder(charset.as_bytes());
+        self
+    }
+
+    /// Set whether the raw bytes of each header value are kept alongside the
+    /// parsed value, which can be retrieved with [`MessagePart::header_raw`](crate::MessagePart::header_raw).
+    ///
+    /// Disabled by default.
+    pub fn with_preserve_raw_headers(mut self, preserve_raw_headers: bool) -> Self {
+        self.preserve_raw_headers = preserve_raw_headers;
+        self
+    }
+
+    /// Set whether part bodies are returned as found in the message, without
+    /// decoding their `Content-Transfer-Encoding`, charset or `format=flowed`.
+    ///
+    /// `MessagePart::encoding` still reports the declared encoding. Text parts are
+    /// returned as text when their raw body is valid UTF-8 and as binary otherwise,
+    /// so that their bytes are kept unchanged. Disabled by default.
+    pub fn with_raw_bodies(mut self, raw_bodies: bool) -> Self {
+        self.raw_bodies = raw_bodies;
+        self
+    }
+
+    /// Set whether an index of header names is built for each part, which
+    /// speeds up looking up many different headers on the same part. Lookups of
+    /// headers that are missing from the index, or whose indexed positions no
+    /// longer match `MessagePart::headers`, scan the headers instead.
+    ///
+    /// Disabled by default.
+    pub fn with_header_index(mut self, header_index: bool) -> Self {
+        self.header_index = header_index;
+        self
+    }
+
+    /// Set the maximum number of headers parsed for each part.
+    ///
+    /// Once the limit is reached, the remaining headers of the part are skipped
+    /// and the part is flagged with [`MessagePart::is_header_overflow`](crate::MessagePart::is_header_overflow).
+    /// Unlimited by default.
+    pub fn with_max_headers(mut self, max_headers: usize) -> Self {
+        self.max_headers = max_headers;
+        self
+    }
+
+    /// Set the maximum total size in bytes of the headers parsed for each part.
+    ///
+    /// The header that exceeds the limit and those following it are skipped
+    /// and the part is flagged with [`MessagePart::is_header_overflow`](crate::MessagePart::is_header_overflow).
+    /// Unlimited by default.
+    pub fn with_max_header_bytes(mut self, max_header_bytes: usize) -> Self {
+        self.max_header_bytes = max_header_bytes;
+        self
+    }
+
+    /// Set the maximum size in bytes of the messages to parse.
+    ///
+    /// Larger messages are not parsed: [`MessageParser::try_parse`] returns
+    /// [`ParseError::TooLarge`](crate::ParseError::TooLarge), [`MessageParser::parse`]
+    /// returns `None` and [`MessageParser::parse_reader`] stops reading once the
+    /// limit is exceeded. Unlimited by default.
+    pub fn with_max_size(mut self, max_size: usize) -> Self {
+        self.max_size = max_size;
+        self
+    }
+
+    /// Set whether `begin`/`end` uuencoded blocks embedded in `text/plain` parts
+    /// are extracted as attachments.
+    ///
+    /// The blocks are removed from the text and each decoded file is added after
+    /// its text part as an `application/octet-stream` attachment that is not part
+    /// of the MIME structure of the message, see [`MessagePart::uuencode_mode`](crate::MessagePart::uuencode_mode).
+    /// Disabled by default.
+    pub fn with_uudecode(mut self, uudecode: bool) -> Self {
+        self.uudecode = uudecode;
+        self
+    }
+
+    /// Set whether `application/mac-binhex40` parts are decoded.
+    ///
+    /// The body of a decoded part contains the data fork of the file, while its
+    /// name, type and creator are available from [`MessagePart::binhex_header`](crate::MessagePart::binhex_header).
+    /// Parts that fail to decode are kept as is and flagged with `is_encoding_problem`.
+    /// Disabled by default.
+    #[cfg(feature = "binhex")]
+    pub fn with_binhex(mut self, binhex: bool) -> Self {
+        self.binhex = binhex;
+        self
+    }
+
+    /// Set whether unstructured header values such as `Subject` keep their
+    /// original whitespace, including the whitespace following the colon.
+    ///
+    /// Line breaks are still unfolded and RFC2047 encoded-words decoded, but
+    /// runs of whitespace are no longer collapsed into a single space nor
+    /// trimmed. Disabled by default.
+    pub fn with_preserve_whitespace(mut self, preserve_whitespace: bool) -> Self {
+        self.preserve_whitespace = preserve_whitespace;
+        self
+    }
+
+    /// Set whether the charset of text parts is detected when it is missing,
+    /// not supported or the text does not decode cleanly with the declared charset.
+    ///
+    /// When enabled, the default charset is not used. The charset used to decode
+    /// each text part can be obtained with [`MessagePart::decoded_charset`](crate::MessagePart::decoded_charset).
+    /// Disabled by default.
+    #[cfg(feature = "detect_charset")]
+    pub fn with_charset_detection(mut self, detect_charset: bool) -> Self {
+        self.detect_charset = detect_charset;
+        self
+    }
+
     /// Parse all MIME headers:
     ///
     /// * `Content-Type`
@@ -176,6 +346,27 @@ impl MessageParser {
         self
     }
 
+    /// Parse a header with the specified parser, which is equivalent to calling
+    /// the matching `header_*` method, such as [`MessageParser::header_address`]
+    /// for [`HeaderParser::Address`].
+    pub fn with_header_parser(
+        self,
+        header: impl Into<HeaderName<'static>>,
+        parser: HeaderParser,
+    ) -> Self {
+        match parser {
+            HeaderParser::Text => self.header_text(header),
+            HeaderParser::Raw => self.header_raw(header),
+            HeaderParser::Address => self.header_address(header),
+            HeaderParser::Date => self.header_date(header),
+            HeaderParser::Id => self.header_id(header),
+            HeaderParser::ContentType => self.header_content_type(header),
+            HeaderParser::CommaSeparated => self.header_comma_separated(header),
+            HeaderParser::Received => self.header_received(header),
+            HeaderParser::Ignore => self.ignore_header(header),
+        }
+    }
+
     /// Ignore and skip parsing a header.
     pub fn ignore_header(mut self, header: impl Into<HeaderName<'static>>) -> Self {
         self.header_map.insert(header.into(), |s| {
@@ -185,24 +376,26 @@ impl MessageParser {
         self
     }
 
-    /// Parse all other headers as text decoding RFC 2047 encoded words.
+    /// Parse all headers without a custom parser as text decoding RFC 2047 encoded words,
+    /// instead of using their built-in parser.
     pub fn default_header_text(mut self) -> Self {
-        self.def_hdr_parse_fnc = |s| s.parse_unstructured();
+        self.def_hdr_parse_fnc = Some(|s| s.parse_unstructured());
         self
     }
 
-    /// Parse all other headers as raw strings, no RFC 2047 decoding is done.
+    /// Parse all headers without a custom parser as raw strings, instead of using
+    /// their built-in parser. No RFC 2047 decoding is done.
     pub fn default_header_raw(mut self) -> Self {
-        self.def_hdr_parse_fnc = |s| s.parse_raw();
+        self.def_hdr_parse_fnc = Some(|s| s.parse_raw());
         self
     }
 
-    /// Ignore and skip parsing all other headers.
+    /// Ignore and skip parsing all headers without a custom parser.
     pub fn default_header_ignore(mut self) -> Self {
-        self.def_hdr_parse_fnc = |s| {
+        self.def_hdr_parse_fnc = Some(|s| {
             s.parse_and_ignore();
             HeaderValue::Empty
-        };
+        });
         self
     }
 }
@@ -0,0 +1,170 @@
+/*
+ * Copyright Stalwart Labs Ltd. See the COPYING
+ * file at the top-level directory of this distribution.
+ *
+ * Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+ * https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+ * <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+ * option. This file may not be copied, modified, or distributed
+ * except according to those terms.
+ */
+
+use alloc::{borrow::Cow, vec::Vec};
+
+use crate::{
+    decoders::{base64::base64_decode, quoted_printable::quoted_printable_decode},
+    Encoding, MessagePart, MimeHeaders, PartType,
+};
+
+impl MessagePart<'_> {
+    /// Verifies the RFC1864 Content-MD5 field of a leaf part against its body,
+    /// returning `None` when the field is absent or cannot be decoded, when the
+    /// part is a multipart or a nested message, or when its transfer encoding
+    /// is invalid.
+    ///
+    /// The digest is computed over the raw body with only its
+    /// `Content-Transfer-Encoding` decoded, so any charset is hashed as sent.
+    /// As required by the RFC, text parts have their line breaks converted to
+    /// CRLF while other parts are hashed as they are.
+    ///
+    /// `message_bytes` must be the raw message this part was parsed from.
+    pub fn verify_content_md5(&self, message_bytes: &[u8]) -> Option<bool> {
+        if matches!(self.body, PartType::Message(_) | PartType::Multipart(_)) {
+            return None;
+        }
+        let expected = self
+            .header("Content-MD5")?
+            .as_text()?
+            .bytes()
+            .filter(|ch| !ch.is_ascii_whitespace())
+            .collect::<Vec<_>>();
+        let expected = base64_decode(&expected).filter(|digest| digest.len() == 16)?;
+
+        let raw = message_bytes.get(self.offset_body..self.offset_end)?;
+        let body = match self.encoding {
+            Encoding::Base64 => base64_decode(raw)?.into(),
+            Encoding::QuotedPrintable => quoted_printable_decode(raw)?.into(),
+            Encoding::None => Cow::Borrowed(raw),
+        };
+
+        let digest = if self
+            .content_type()
+            .map_or(true, |ct| ct.ctype().eq_ignore_ascii_case("text"))
+        {
+            let mut canonical = Vec::with_capacity(body.len() + body.len() / 32);
+            let mut last_ch = 0;
+            for &ch in body.iter() {
+                if ch == b'\n' && last_ch != b'\r' {
+                    canonical.push(b'\r');
+                }
+                canonical.push(ch);
+                last_ch = ch;
+            }
+            md5(&canonical)
+        } else {
+            md5(&body)
+        };
+
+        Some(digest[..] == expected[..])
+    }
+}
+
+static SHIFTS: [u32; 64] = [
+    7, 12, 17, 22, 7, 12, 17, 22, 7, 12, 17, 22, 7, 12, 17, 22, 5, 9, 14, 20, 5, 9, 14, 20, 5, 9,
+    14, 20, 5, 9, 14, 20, 4, 11, 16, 23, 4, 11, 16, 23, 4, 11, 16, 23, 4, 11, 16, 23, 6, 10, 15,
+    21, 6, 10, 15, 21, 6, 10, 15, 21, 6, 10, 15, 21,
+];
+
+static CONSTANTS: [u32; 64] = [
+    0xd76aa478, 0xe8c7b756, 0x242070db, 0xc1bdceee, 0xf57c0faf, 0x4787c62a, 0xa8304613, 0xfd469501,
+    0x698098d8, 0x8b44f7af, 0xffff5bb1, 0x895cd7be, 0x6b901122, 0xfd987193, 0xa679438e, 0x49b40821,
+    0xf61e2562, 0xc040b340, 0x265e5a51, 0xe9b6c7aa, 0xd62f105d, 0x02441453, 0xd8a1e681, 0xe7d3fbc8,
+    0x21e1cde6, 0xc33707d6, 0xf4d50d87, 0x455a14ed, 0xa9e3e905, 0xfcefa3f8, 0x676f02d9, 0x8d2a4c8a,
+    0xfffa3942, 0x8771f681, 0x6d9d6122, 0xfde5380c, 0xa4beea44, 0x4bdecfa9, 0xf6bb4b60, 0xbebfbc70,
+    0x289b7ec6, 0xeaa127fa, 0xd4ef3085, 0x04881d05, 0xd9d4d039, 0xe6db99e5, 0x1fa27cf8, 0xc4ac5665,
+    0xf4292244, 0x432aff97, 0xab9423a7, 0xfc93a039, 0x655b59c3, 0x8f0ccc92, 0xffeff47d, 0x85845dd1,
+    0x6fa87e4f, 0xfe2ce6e0, 0xa3014314, 0x4e0811a1, 0xf7537e82, 0xbd3af235, 0x2ad7d2bb, 0xeb86d391,
+];
+
+/// Computes the RFC1321 MD5 digest of `bytes`.
+pub(crate) fn md5(bytes: &[u8]) -> [u8; 16] {
+    let mut state: [u32; 4] = [0x67452301, 0xefcdab89, 0x98badcfe, 0x10325476];
+
+    // The message is padded with a single bit, zeros and its length in bits
+    let mut padded = Vec::with_capacity((bytes.len() + 72) & !63);
+    padded.extend_from_slice(bytes);
+    padded.push(0x80);
+    while padded.len() % 64 != 56 {
+        padded.push(0);
+    }
+    padded.extend_from_slice(&((bytes.len() as u64).wrapping_mul(8)).to_le_bytes());
+
+    for block in padded.chunks_exact(64) {
+        let mut words = [0u32; 16];
+        for (word, bytes) in words.iter_mut().zip(block.chunks_exact(4)) {
+            *word = u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+        }
+
+        let [mut a, mut b, mut c, mut d] = state;
+        for i in 0..64 {
+            let (f, g) = match i / 16 {
+                0 => ((b & c) | (!b & d), i),
+                1 => ((d & b) | (!d & c), (5 * i + 1) % 16),
+                2 => (b ^ c ^ d, (3 * i + 5) % 16),
+                _ => (c ^ (b | !d), (7 * i) % 16),
+            };
+            let f = f
+                .wrapping_add(a)
+                .wrapping_add(CONSTANTS[i])
+                .wrapping_add(words[g]);
+            a = d;
+            d = c;
+            c = b;
+            b = b.wrapping_add(f.rotate_left(SHIFTS[i]));
+        }
+
+        for (state, value) in state.iter_mut().zip([a, b, c, d]) {
+            *state = state.wrapping_add(value);
+        }
+    }
+
+    let mut digest = [0u8; 16];
+    for (bytes, word) in digest.chunks_exact_mut(4).zip(state) {
+        bytes.copy_from_slice(&word.to_le_bytes());
+    }
+    digest
+}
+
+#[cfg(test)]
+mod tests {
+    use super::md5;
+
+    #[test]
+    fn md5_digest() {
+        // Test suite from RFC1321, appendix A.5
+        for (input, expected) in [
+            ("", "d41d8cd98f00b204e9800998ecf8427e"),
+            ("a", "0cc175b9c0f1b6a831c399e269772661"),
+            ("abc", "900150983cd24fb0d6963f7d28e17f72"),
+            ("message digest", "f96b697d7cb7938d525a2f31aaf161d0"),
+            (
+                "abcdefghijklmnopqrstuvwxyz",
+                "c3fcd3d76192e4007dfb496cca67e13b",
+            ),
+            (
+                "ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789",
+                "d174ab98d277d9f5a5611c2c9f419d9f",
+            ),
+            (
+                "12345678901234567890123456789012345678901234567890123456789012345678901234567890",
+                "57edf4a22be3c955ac49da2e2107b67a",
+            ),
+        ] {
+            let digest = md5(input.as_bytes())
+                .iter()
+                .map(|byte| format!("{byte:02x}"))
+                .collect::<String>();
+            assert_eq!(digest, expected, "failed for {input:?}");
+        }
+    }
+}
@@ -0,0 +1,127 @@
+/*
+ * Copyright Stalwart Labs Ltd. See the COPYING
+ * file at the top-level directory of this distribution.
+ *
+ * Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+ * https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+ * <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+ * option. This file may not be copied, modified, or distributed
+ * except according to those terms.
+ */
+
+use alloc::{borrow::Cow, vec::Vec};
+
+use crate::{
+    parsers::MessageStream, DeliveryStatus, Header, HeaderValue, MessageParser, MessagePart,
+    MimeHeaders, RecipientStatus,
+};
+
+impl MessagePart<'_> {
+    /// Parses the body of a `message/delivery-status` part (RFC3464), or of its
+    /// internationalized `message/global-delivery-status` variant (RFC6533).
+    ///
+    /// The body consists of a block of per-message fields followed by one block
+    /// of per-recipient fields for each recipient, separated by blank lines.
+    /// Returns `None` for other parts or when no per-recipient block is found.
+    pub fn delivery_status(&self) -> Option<DeliveryStatus<'_>> {
+        let content_type = self.content_type()?;
+        if !content_type.ctype().eq_ignore_ascii_case("message")
+            || !content_type.subtype().is_some_and(|subtype| {
+                subtype.eq_ignore_ascii_case("delivery-status")
+                    || subtype.eq_ignore_ascii_case("global-delivery-status")
+            })
+        {
+            return None;
+        }
+
+        let contents = self.contents();
+        let status = if contents.ends_with(b"\n") {
+            parse_delivery_status(contents)
+        } else {
+            let mut contents = contents.to_vec();
+            contents.push(b'\n');
+            parse_delivery_status(&contents).into_owned()
+        };
+
+        if !status.recipients.is_empty() {
+            Some(status)
+        } else {
+            None
+        }
+    }
+}
+
+impl DeliveryStatus<'_> {
+    pub fn into_owned(self) -> DeliveryStatus<'static> {
+        DeliveryStatus {
+            reporting_mta: self.reporting_mta.map(|s| s.into_owned().into()),
+            original_envelope_id: self.original_envelope_id.map(|s| s.into_owned().into()),
+            recipients: self
+                .recipients
+                .into_iter()
+                .map(|recipient| recipient.into_owned())
+                .collect(),
+        }
+    }
+}
+
+impl RecipientStatus<'_> {
+    pub fn into_owned(self) -> RecipientStatus<'static> {
+        RecipientStatus {
+            original_recipient: self.original_recipient.map(|s| s.into_owned().into()),
+            final_recipient: self.final_recipient.map(|s| s.into_owned().into()),
+            action: self.action.map(|s| s.into_owned().into()),
+            status: self.status.map(|s| s.into_owned().into()),
+            remote_mta: self.remote_mta.map(|s| s.into_owned().into()),
+            diagnostic_code: self.diagnostic_code.map(|s| s.into_owned().into()),
+        }
+    }
+}
+
+fn parse_delivery_status(contents: &[u8]) -> DeliveryStatus<'_> {
+    // All fields are parsed as unstructured text, unfolding continuation lines
+    let parser = MessageParser::new()
+        .default_header_text()
+        .header_text("Diagnostic-Code");
+    let mut stream = MessageStream::new(contents);
+    let mut status = DeliveryStatus::default();
+    let mut is_per_message = true;
+
+    loop {
+        let mut headers = Vec::new();
+        let has_more = stream.parse_headers(&parser, &mut headers);
+
+        if !headers.is_empty() {
+            if is_per_message {
+                status.reporting_mta = field(&headers, "Reporting-MTA");
+                status.original_envelope_id = field(&headers, "Original-Envelope-Id");
+                is_per_message = false;
+            } else {
+                status.recipients.push(RecipientStatus {
+                    original_recipient: field(&headers, "Original-Recipient"),
+                    final_recipient: field(&headers, "Final-Recipient"),
+                    action: field(&headers, "Action"),
+                    status: field(&headers, "Status"),
+                    remote_mta: field(&headers, "Remote-MTA"),
+                    diagnostic_code: field(&headers, "Diagnostic-Code"),
+                });
+            }
+        }
+
+        if !has_more || stream.is_eof() {
+            break;
+        }
+    }
+
+    status
+}
+
+fn field<'x>(headers: &[Header<'x>], name: &str) -> Option<Cow<'x, str>> {
+    headers
+        .iter()
+        .find(|header| header.name.as_str().eq_ignore_ascii_case(name))
+        .and_then(|header| match &header.value {
+            HeaderValue::Text(text) => Some(text.clone()),
+            _ => None,
+        })
+}
@@ -9,14 +9,19 @@
  * except according to those terms.
  */
 
+use alloc::{borrow::Cow, boxed::Box, string::String, string::ToString, vec, vec::Vec};
 use core::fmt;
-use std::hash::Hash;
-use std::net::IpAddr;
-use std::{borrow::Cow, fmt::Display};
+use core::fmt::Display;
+use core::hash::Hash;
+use core::net::IpAddr;
 
 use crate::{
-    Address, ContentType, DateTime, GetHeader, Greeting, Header, HeaderName, HeaderValue, Host,
-    Message, MessagePart, MessagePartId, MimeHeaders, PartType, Protocol, Received, TlsVersion,
+    decoders::html::{html_to_text, html_to_text_with_links},
+    parsers::fields::remove_comments,
+    Address, AuthenticationProperty, AuthenticationResult, AuthenticationResults, BinHexHeader,
+    ContentDisposition, ContentType, DateTime, DispositionType, DkimSignature, GetHeader, Greeting,
+    Header, HeaderIndex, HeaderName, HeaderValue, Host, ListId, Message, MessagePart,
+    MessagePartId, MimeHeaders, PartType, Protocol, Received, TlsVersion,
 };
 
 impl<'x> Header<'x> {
@@ -26,7 +31,7 @@ impl<'x> Header<'x> {
     }
 
     /// Returns the parsed header value
-    pub fn value(&self) -> &HeaderValue {
+    pub fn value(&self) -> &HeaderValue<'_> {
         &self.value
     }
 
@@ -45,6 +50,12 @@ impl<'x> Header<'x> {
         self.offset_field
     }
 
+    /// Returns the raw header value without its terminating line break,
+    /// if preserved by the parser
+    pub fn raw(&self) -> Option<&[u8]> {
+        self.raw.as_deref()
+    }
+
     /// Returns an owned version of the header
     pub fn into_owned(self) -> Header<'static> {
         Header {
@@ -53,6 +64,7 @@ impl<'x> Header<'x> {
             offset_field: self.offset_field,
             offset_start: self.offset_start,
             offset_end: self.offset_end,
+            raw: self.raw.map(|raw| raw.into_owned().into()),
         }
     }
 }
@@ -171,14 +183,14 @@ impl<'x> HeaderValue<'x> {
         }
     }
 
-    pub fn as_received(&self) -> Option<&Received> {
+    pub fn as_received(&self) -> Option<&Received<'_>> {
         match *self {
             HeaderValue::Received(ref r) => Some(r),
             _ => None,
         }
     }
 
-    pub fn as_content_type(&self) -> Option<&ContentType> {
+    pub fn as_content_type(&self) -> Option<&ContentType<'_>> {
         match *self {
             HeaderValue::ContentType(ref c) => Some(c),
             _ => None,
@@ -260,7 +272,7 @@ impl PartialEq for HeaderName<'_> {
 }
 
 impl<'x> Hash for HeaderName<'x> {
-    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+    fn hash<H: core::hash::Hasher>(&self, state: &mut H) {
         match self {
             HeaderName::Other(value) => {
                 for ch in value.as_bytes() {
@@ -274,6 +286,24 @@ impl<'x> Hash for HeaderName<'x> {
 
 impl Eq for HeaderName<'_> {}
 
+impl PartialOrd for HeaderName<'_> {
+    fn partial_cmp(&self, other: &Self) -> Option<core::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for HeaderName<'_> {
+    fn cmp(&self, other: &Self) -> core::cmp::Ordering {
+        match (self, other) {
+            (Self::Other(a), Self::Other(b)) => a
+                .bytes()
+                .map(|ch| ch.to_ascii_lowercase())
+                .cmp(b.bytes().map(|ch| ch.to_ascii_lowercase())),
+            _ => self.id().cmp(&other.id()),
+        }
+    }
+}
+
 impl<'x> From<HeaderName<'x>> for u8 {
     fn from(name: HeaderName<'x>) -> Self {
         name.id()
@@ -542,7 +572,7 @@ impl<'x> MimeHeaders<'x> for Message<'x> {
             .and_then(|header| header.as_text())
     }
 
-    fn content_disposition(&self) -> Option<&ContentType> {
+    fn content_disposition(&self) -> Option<&ContentType<'_>> {
         self.parts[0]
             .headers
             .header_value(&HeaderName::ContentDisposition)
@@ -563,14 +593,14 @@ impl<'x> MimeHeaders<'x> for Message<'x> {
             .and_then(|header| header.as_text())
     }
 
-    fn content_type(&self) -> Option<&ContentType> {
+    fn content_type(&self) -> Option<&ContentType<'_>> {
         self.parts[0]
             .headers
             .header_value(&HeaderName::ContentType)
             .and_then(|header| header.as_content_type())
     }
 
-    fn content_language(&self) -> &HeaderValue {
+    fn content_language(&self) -> &HeaderValue<'_> {
         self.parts[0]
             .headers
             .header_value(&HeaderName::ContentLanguage)
@@ -601,13 +631,24 @@ impl<'x> MessagePart<'x> {
         match &self.body {
             PartType::Text(text) | PartType::Html(text) => text.as_ref().into(),
             PartType::Binary(bin) | PartType::InlineBinary(bin) => {
-                std::str::from_utf8(bin.as_ref()).ok()
+                core::str::from_utf8(bin.as_ref()).ok()
             }
-            PartType::Message(message) => std::str::from_utf8(message.raw_message()).ok(),
+            PartType::Message(message) => core::str::from_utf8(message.raw_message()).ok(),
             PartType::Multipart(_) => None,
         }
     }
 
+    /// Returns the body part's contents as plain text, converting HTML parts
+    /// and optionally rendering their links as `label (target)`
+    pub fn to_text(&self, annotate_links: bool) -> Option<Cow<'_, str>> {
+        match &self.body {
+            PartType::Text(text) => Some(text.as_ref().into()),
+            PartType::Html(html) if annotate_links => Some(html_to_text_with_links(html).into()),
+            PartType::Html(html) => Some(html_to_text(html).into()),
+            _ => None,
+        }
+    }
+
     /// Returns the nested message
     pub fn message(&self) -> Option<&Message<'x>> {
         if let PartType::Message(message) = &self.body {
@@ -617,7 +658,8 @@ impl<'x> MessagePart<'x> {
         }
     }
 
-    /// Returns the sub parts ids of a MIME part
+    /// Returns the sub parts ids of a MIME part. Parts extracted from uuencoded
+    /// blocks are not part of the MIME structure and are never listed.
     pub fn sub_parts(&self) -> Option<&[MessagePartId]> {
         if let PartType::Multipart(parts) = &self.body {
             Some(parts.as_ref())
@@ -626,7 +668,7 @@ impl<'x> MessagePart<'x> {
         }
     }
 
-    /// Returns the body part's length
+    /// Returns the body part's decoded length
     pub fn len(&self) -> usize {
         match &self.body {
             PartType::Text(text) | PartType::Html(text) => text.len(),
@@ -661,32 +703,328 @@ impl<'x> MessagePart<'x> {
         matches!(self.body, PartType::Message(_))
     }
 
+    /// Returns `true` when the part is an attachment.
+    ///
+    /// The `Content-Disposition` header field is used when present. Otherwise, a part
+    /// is considered an attachment when it has no `Content-ID` and it is either a binary part
+    /// that was not parsed as inline, a nested message, or a part with a file name.
+    pub fn is_attachment(&self) -> bool {
+        match self.content_disposition() {
+            Some(cd) if cd.is_attachment() => true,
+            Some(cd) if cd.is_inline() => false,
+            _ => {
+                self.content_id().is_none()
+                    && (matches!(self.body, PartType::Binary(_) | PartType::Message(_))
+                        || (!self.is_multipart() && self.attachment_name().is_some()))
+            }
+        }
+    }
+
+    /// Returns `true` when the part is meant to be displayed inline,
+    /// which is any part other than a multipart or an attachment.
+    pub fn is_inline(&self) -> bool {
+        !self.is_multipart() && !self.is_attachment()
+    }
+
+    /// Returns the exact bytes covered by the signature of a `multipart/signed` part
+    /// (RFC1847), which are the headers and body of its first sub-part excluding the
+    /// line break that precedes the next boundary delimiter. The signature itself is
+    /// the second sub-part.
+    ///
+    /// `message_bytes` must be the raw message this part was parsed from.
+    pub fn raw_signed_content<'y>(&self, message_bytes: &'y [u8]) -> Option<&'y [u8]> {
+        if !self.is_content_type("multipart", "signed") {
+            return None;
+        }
+        let boundary = self.content_type()?.attribute("boundary")?.as_bytes();
+        let body = message_bytes.get(self.offset_body..self.offset_end)?;
+
+        // Skip the first delimiter line, including any transport padding
+        let start = find_delimiter(body, 0, boundary)? + boundary.len() + 2;
+        let start = start + body[start..].iter().position(|&ch| ch == b'\n')? + 1;
+
+        // The line break before the next delimiter is part of the delimiter
+        let end = find_delimiter(body, start, boundary)?;
+        let end = if end > start && body[end - 1] == b'\n' {
+            if end - 1 > start && body[end - 2] == b'\r' {
+                end - 2
+            } else {
+                end - 1
+            }
+        } else {
+            end
+        };
+
+        Some(&body[start..end])
+    }
+
+    /// Returns an iterator over the raw name and value of each header of this
+    /// part, in order and including duplicates, without decoding or allocating.
+    /// Values are returned as found after the colon, including folding and the
+    /// trailing line break.
+    ///
+    /// `message_bytes` must be the raw message this part was parsed from.
+    pub fn raw_header_iter<'y>(
+        &'y self,
+        message_bytes: &'y [u8],
+    ) -> impl Iterator<Item = (&'y [u8], &'y [u8])> + 'y {
+        self.headers.iter().filter_map(move |header| {
+            let field = message_bytes.get(header.offset_field..header.offset_start)?;
+            let name = &field[..field.iter().position(|&ch| ch == b':')?];
+            let name_len = name.len()
+                - name
+                    .iter()
+                    .rev()
+                    .take_while(|ch| ch.is_ascii_whitespace())
+                    .count();
+            Some((
+                &name[..name_len],
+                message_bytes.get(header.offset_start..header.offset_end)?,
+            ))
+        })
+    }
+
+    /// Returns the `protocol` parameter of a `multipart/signed` part,
+    /// such as `application/pgp-signature`
+    pub fn signature_protocol(&self) -> Option<&str> {
+        if self.is_content_type("multipart", "signed") {
+            self.content_type()?.attribute("protocol")
+        } else {
+            None
+        }
+    }
+
+    /// Returns the `micalg` parameter of a `multipart/signed` part,
+    /// such as `pgp-sha256`
+    pub fn signature_micalg(&self) -> Option<&str> {
+        if self.is_content_type("multipart", "signed") {
+            self.content_type()?.attribute("micalg")
+        } else {
+            None
+        }
+    }
+
+    /// Returns the iCalendar method of a `text/calendar` part,
+    /// such as `REQUEST` or `CANCEL`
+    pub fn calendar_method(&self) -> Option<&str> {
+        if self.is_content_type("text", "calendar") {
+            self.content_type()?.attribute("method")
+        } else {
+            None
+        }
+    }
+
+    /// Returns the RFC2424 Content-Duration header field, which is the
+    /// length in seconds of an audio or video part
+    pub fn content_duration(&self) -> Option<u32> {
+        let value = self.header("Content-Duration")?.as_text()?;
+        let value = remove_comments(value);
+        let value = value.trim();
+        if !value.is_empty() && value.bytes().all(|ch| ch.is_ascii_digit()) {
+            value.parse().ok()
+        } else {
+            None
+        }
+    }
+
     /// Returns `true` when the body part is empty
     pub fn is_empty(&self) -> bool {
         self.len() == 0
     }
 
     /// Get the message headers
-    pub fn headers(&self) -> &[Header] {
+    pub fn headers(&self) -> &[Header<'_>] {
         &self.headers
     }
 
-    /// Returns the body raw length
+    /// Returns all headers of this part in their original order, along with
+    /// the raw offsets of each header value
+    pub fn headers_raw(
+        &self,
+    ) -> impl Iterator<Item = (&HeaderName<'x>, &HeaderValue<'x>, usize, usize)> {
+        self.headers.iter().map(|header| {
+            (
+                &header.name,
+                &header.value,
+                header.offset_start,
+                header.offset_end,
+            )
+        })
+    }
+
+    /// Returns the value of the last header with the specified name
+    pub fn header(&self, name: impl Into<HeaderName<'x>>) -> Option<&HeaderValue<'x>> {
+        let name = name.into();
+        match self.indexed_headers(&name) {
+            Some(positions) => positions.last().map(|&pos| &self.headers[pos].value),
+            None => self
+                .headers
+                .iter()
+                .rev()
+                .find(|header| header.name == name)
+                .map(|header| &header.value),
+        }
+    }
+
+    /// Returns the values of all headers with the specified name, in their original order
+    pub fn header_values<'y>(
+        &'y self,
+        name: impl Into<HeaderName<'x>>,
+    ) -> impl Iterator<Item = &'y HeaderValue<'x>> + 'y {
+        let name = name.into();
+        let (indexed, scanned) = match self.indexed_headers(&name) {
+            Some(positions) => (Some(positions), None),
+            None => (None, Some(&self.headers)),
+        };
+
+        indexed
+            .into_iter()
+            .flatten()
+            .map(|&pos| &self.headers[pos].value)
+            .chain(
+                scanned
+                    .into_iter()
+                    .flatten()
+                    .filter(move |header| header.name == name)
+                    .map(|header| &header.value),
+            )
+    }
+
+    /// Returns the positions of a header from the index, or `None` if the part
+    /// has no index, the header is not indexed or the index is out of date, in
+    /// which case the headers are scanned instead.
+    fn indexed_headers<'y>(&'y self, name: &HeaderName<'x>) -> Option<&'y [usize]> {
+        let index = self
+            .header_index
+            .as_ref()
+            .filter(|index| index.len == self.headers.len())?;
+        match index.positions.get(name) {
+            Some(positions)
+                if positions
+                    .iter()
+                    .all(|&pos| self.headers.get(pos).is_some_and(|h| &h.name == name)) =>
+            {
+                Some(positions)
+            }
+            _ => None,
+        }
+    }
+
+    /// Returns the raw bytes of a header field as they appear between the colon
+    /// and the terminating line break, the same as [`Message::part_header_raw`](crate::Message::part_header_raw).
+    /// This requires enabling [`MessageParser::with_preserve_raw_headers`](crate::MessageParser::with_preserve_raw_headers),
+    /// which keeps the value available once the part is detached from the message.
+    pub fn header_raw(&self, name: impl Into<HeaderName<'x>>) -> Option<&[u8]> {
+        self.headers.header(name).and_then(|header| header.raw())
+    }
+
+    /// Returns the raw length of the part including its headers, which is
+    /// `raw_end_offset() - raw_header_offset()`
     pub fn raw_len(&self) -> usize {
         self.offset_end.saturating_sub(self.offset_header)
     }
 
-    /// Get the raw header offset of this part
+    /// Returns the length of the raw body, before any transfer decoding
+    pub fn raw_body_len(&self) -> usize {
+        self.offset_end.saturating_sub(self.offset_body)
+    }
+
+    /// Returns the charset used to decode a text part, which is only
+    /// recorded when charset detection is enabled
+    pub fn decoded_charset(&self) -> Option<&str> {
+        self.decoded_charset.as_deref()
+    }
+
+    /// Returns the Content-Type of this part, or the RFC2045 default when the
+    /// field is missing: `message/rfc822` for the parts of a `multipart/digest`
+    /// parsed as nested messages and `text/plain` otherwise.
+    ///
+    /// Content-Type fields without a subtype are completed when parsing, with
+    /// `plain` for `text`, `mixed` for `multipart`, `rfc822` for `message` and
+    /// `octet-stream` for `application`.
+    pub fn effective_content_type(&self) -> &ContentType<'_> {
+        static TEXT_PLAIN: ContentType<'static> = ContentType {
+            c_type: Cow::Borrowed("text"),
+            c_subtype: Some(Cow::Borrowed("plain")),
+            attributes: None,
+        };
+        static MESSAGE_RFC822: ContentType<'static> = ContentType {
+            c_type: Cow::Borrowed("message"),
+            c_subtype: Some(Cow::Borrowed("rfc822")),
+            attributes: None,
+        };
+
+        self.content_type().unwrap_or(match self.body {
+            PartType::Message(_) => &MESSAGE_RFC822,
+            _ => &TEXT_PLAIN,
+        })
+    }
+
+    /// Returns the boundary delimiting the children of a multipart part,
+    /// or `None` for any other part type
+    pub fn boundary(&self) -> Option<&str> {
+        if let PartType::Multipart(_) = self.body {
+            self.content_type()?.attribute("boundary")
+        } else {
+            None
+        }
+    }
+
+    /// Returns `true` if some headers of this part were skipped because the
+    /// limits set with [`MessageParser::with_max_headers`](crate::MessageParser::with_max_headers)
+    /// or [`MessageParser::with_max_header_bytes`](crate::MessageParser::with_max_header_bytes) were exceeded
+    pub fn is_header_overflow(&self) -> bool {
+        self.is_header_overflow
+    }
+
+    /// Returns the Unix file mode of a part extracted from a uuencoded block
+    /// of a text part by [`MessageParser::with_uudecode`](crate::MessageParser::with_uudecode),
+    /// or `None` for regular MIME parts
+    pub fn uuencode_mode(&self) -> Option<u32> {
+        self.uuencode_mode
+    }
+
+    /// Returns `true` if this part was extracted from a uuencoded block of a text part.
+    /// Such parts are only listed in `Message::attachments`, they are detached
+    /// from the MIME tree and are not returned by [`MessagePart::sub_parts`].
+    pub fn is_uuencoded(&self) -> bool {
+        self.uuencode_mode.is_some()
+    }
+
+    /// Returns the name, type and creator of a part decoded from BinHex 4.0,
+    /// whose body then holds the data fork of the file
+    pub fn binhex_header(&self) -> Option<&BinHexHeader> {
+        self.binhex.as_deref()
+    }
+
+    /// Returns the number of lines in the raw body of a text part,
+    /// or `0` for any other part type
+    pub fn line_count(&self) -> usize {
+        self.line_count
+    }
+
+    /// Get the raw header offset of this part.
+    ///
+    /// Offsets are relative to the input passed to the parser, including
+    /// those of the parts of nested messages. The only exception are the
+    /// parts of a nested message with a `base64` or `quoted-printable`
+    /// transfer encoding, whose offsets are relative to the decoded message
+    /// returned by [`Message::raw_message`].
     pub fn raw_header_offset(&self) -> usize {
         self.offset_header
     }
 
-    /// Get the raw body offset of this part
+    /// Get the raw body offset of this part, which follows the blank line
+    /// ending its headers. See [`MessagePart::raw_header_offset`] for the
+    /// input these offsets are relative to.
     pub fn raw_body_offset(&self) -> usize {
         self.offset_body
     }
 
-    /// Get the raw body end offset of this part
+    /// Get the raw body end offset of this part, excluding the line break
+    /// that precedes the next boundary delimiter. See
+    /// [`MessagePart::raw_header_offset`] for the input these offsets are
+    /// relative to.
     pub fn raw_end_offset(&self) -> usize {
         self.offset_end
     }
@@ -708,7 +1046,42 @@ impl<'x> MessagePart<'x> {
             offset_header: self.offset_header,
             offset_body: self.offset_body,
             offset_end: self.offset_end,
+            line_count: self.line_count,
+            decoded_charset: self.decoded_charset,
+            is_header_overflow: self.is_header_overflow,
+            uuencode_mode: self.uuencode_mode,
+            binhex: self.binhex,
+            header_index: self.header_index.map(|index| {
+                Box::new(HeaderIndex {
+                    len: index.len,
+                    positions: index
+                        .positions
+                        .into_iter()
+                        .map(|(name, positions)| (name.into_owned(), positions))
+                        .collect(),
+                })
+            }),
+        }
+    }
+}
+
+/// Returns the offset of the next line starting with `--boundary` followed by
+/// either `--`, linear whitespace or the end of the line
+fn find_delimiter(bytes: &[u8], from: usize, boundary: &[u8]) -> Option<usize> {
+    let mut pos = from;
+    loop {
+        let line = bytes.get(pos..)?;
+        if let Some(rest) = line
+            .strip_prefix(b"--")
+            .and_then(|line| line.strip_prefix(boundary))
+        {
+            if rest.starts_with(b"--")
+                || matches!(rest.first(), None | Some(b' ' | b'\t' | b'\r' | b'\n'))
+            {
+                return Some(pos);
+            }
         }
+        pos += line.iter().position(|&ch| ch == b'\n')? + 1;
     }
 }
 
@@ -720,44 +1093,37 @@ impl<'x> fmt::Display for MessagePart<'x> {
 
 impl<'x> MimeHeaders<'x> for MessagePart<'x> {
     fn content_description(&self) -> Option<&str> {
-        self.headers
-            .header_value(&HeaderName::ContentDescription)
+        self.header(HeaderName::ContentDescription)
             .and_then(|header| header.as_text())
     }
 
-    fn content_disposition(&self) -> Option<&ContentType> {
-        self.headers
-            .header_value(&HeaderName::ContentDisposition)
+    fn content_disposition(&self) -> Option<&ContentType<'_>> {
+        self.header(HeaderName::ContentDisposition)
             .and_then(|header| header.as_content_type())
     }
 
     fn content_id(&self) -> Option<&str> {
-        self.headers
-            .header_value(&HeaderName::ContentId)
+        self.header(HeaderName::ContentId)
             .and_then(|header| header.as_text())
     }
 
     fn content_transfer_encoding(&self) -> Option<&str> {
-        self.headers
-            .header_value(&HeaderName::ContentTransferEncoding)
+        self.header(HeaderName::ContentTransferEncoding)
             .and_then(|header| header.as_text())
     }
 
-    fn content_type(&self) -> Option<&ContentType> {
-        self.headers
-            .header_value(&HeaderName::ContentType)
+    fn content_type(&self) -> Option<&ContentType<'_>> {
+        self.header(HeaderName::ContentType)
             .and_then(|header| header.as_content_type())
     }
 
-    fn content_language(&self) -> &HeaderValue {
-        self.headers
-            .header_value(&HeaderName::ContentLanguage)
+    fn content_language(&self) -> &HeaderValue<'_> {
+        self.header(HeaderName::ContentLanguage)
             .unwrap_or(&HeaderValue::Empty)
     }
 
     fn content_location(&self) -> Option<&str> {
-        self.headers
-            .header_value(&HeaderName::ContentLocation)
+        self.header(HeaderName::ContentLocation)
             .and_then(|header| header.as_text())
     }
 }
@@ -774,37 +1140,49 @@ impl<'x> ContentType<'x> {
         self.c_subtype.as_ref()?.as_ref().into()
     }
 
-    /// Returns an attribute by name
+    /// Returns an attribute by name, ignoring case. RFC2231 encoded
+    /// attributes are returned decoded under their plain name.
     pub fn attribute(&self, name: &str) -> Option<&str> {
         self.attributes
             .as_ref()?
             .iter()
-            .find(|(key, _)| key == name)?
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))?
             .1
             .as_ref()
             .into()
     }
 
-    /// Removes an attribute by name
-    pub fn remove_attribute(&mut self, name: &str) -> Option<Cow<str>> {
+    /// Removes an attribute by name, ignoring case
+    pub fn remove_attribute(&mut self, name: &str) -> Option<Cow<'_, str>> {
         let attributes = self.attributes.as_mut()?;
 
         attributes
             .iter()
-            .position(|(key, _)| key == name)
-            .map(|pos| attributes.swap_remove(pos).1)
+            .position(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|pos| attributes.remove(pos).1)
     }
 
-    /// Returns all attributes
-    pub fn attributes(&self) -> Option<&[(Cow<str>, Cow<str>)]> {
+    /// Returns all attributes in the order they appear in the header
+    pub fn attributes(&self) -> Option<&[(Cow<'_, str>, Cow<'_, str>)]> {
         self.attributes.as_deref()
     }
 
-    /// Returns `true` when the provided attribute name is present
+    /// Returns `true` when the provided attribute name is present, ignoring case
     pub fn has_attribute(&self, name: &str) -> bool {
-        self.attributes
-            .as_ref()
-            .map_or_else(|| false, |attr| attr.iter().any(|(key, _)| key == name))
+        self.attributes.as_ref().map_or_else(
+            || false,
+            |attr| attr.iter().any(|(key, _)| key.eq_ignore_ascii_case(name)),
+        )
+    }
+
+    /// Returns the `charset` attribute
+    pub fn charset(&self) -> Option<&str> {
+        self.attribute("charset")
+    }
+
+    /// Returns the `boundary` attribute
+    pub fn boundary(&self) -> Option<&str> {
+        self.attribute("boundary")
     }
 
     /// Returns ```true``` if the Content-Disposition type is "attachment"
@@ -816,6 +1194,34 @@ impl<'x> ContentType<'x> {
     pub fn is_inline(&self) -> bool {
         self.c_type.eq_ignore_ascii_case("inline")
     }
+
+    /// Returns the Content-Disposition field as a structured value. Date
+    /// parameters that are not valid RFC5322 dates and sizes that are not
+    /// integers are returned as `None`.
+    pub fn to_content_disposition(&self) -> ContentDisposition<'_> {
+        let date = |name| {
+            self.attribute(name)
+                .and_then(|value| HeaderValue::parse_date(value).as_datetime().copied())
+                .filter(|date| date.is_valid())
+        };
+
+        ContentDisposition {
+            disposition_type: if self.is_inline() {
+                DispositionType::Inline
+            } else if self.is_attachment() {
+                DispositionType::Attachment
+            } else {
+                DispositionType::Other(self.ctype())
+            },
+            filename: self.attribute("filename"),
+            creation_date: date("creation-date"),
+            modification_date: date("modification-date"),
+            read_date: date("read-date"),
+            size: self
+                .attribute("size")
+                .and_then(|size| size.trim().parse().ok()),
+        }
+    }
 }
 
 /// A Received header
@@ -840,7 +1246,7 @@ impl<'x> Received<'x> {
     }
 
     /// Returns the hostname or IP address of the machine that originated the message
-    pub fn from(&self) -> Option<&Host> {
+    pub fn from(&self) -> Option<&Host<'_>> {
         self.from.as_ref()
     }
 
@@ -855,7 +1261,7 @@ impl<'x> Received<'x> {
     }
 
     /// Returns the hostname or IP address of the machine that received the message
-    pub fn by(&self) -> Option<&Host> {
+    pub fn by(&self) -> Option<&Host<'_>> {
         self.by.as_ref()
     }
 
@@ -890,7 +1296,7 @@ impl<'x> Received<'x> {
     }
 
     /// Returns the EHLO/LHLO/HELO hostname or IP address of the machine that sent the message
-    pub fn helo(&self) -> Option<&Host> {
+    pub fn helo(&self) -> Option<&Host<'_>> {
         self.helo.as_ref()
     }
 
@@ -910,6 +1316,140 @@ impl<'x> Received<'x> {
     }
 }
 
+/// A List-Id header
+impl<'x> ListId<'x> {
+    /// Returns the description of the list
+    pub fn description(&self) -> Option<&str> {
+        self.description.as_deref()
+    }
+
+    /// Returns the list identifier
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    pub fn into_owned(self) -> ListId<'static> {
+        ListId {
+            description: self.description.map(|d| d.into_owned().into()),
+            id: self.id.into_owned().into(),
+        }
+    }
+}
+
+/// A DKIM-Signature header
+impl<'x> DkimSignature<'x> {
+    /// Returns the value of a tag, tag names are case-sensitive
+    pub fn tag(&self, name: &str) -> Option<&str> {
+        self.tags
+            .iter()
+            .find(|(tag, _)| tag == name)
+            .map(|(_, value)| value.as_ref())
+    }
+
+    /// Returns the signing algorithm (`a=` tag)
+    pub fn algorithm(&self) -> Option<&str> {
+        self.tag("a")
+    }
+
+    /// Returns the base64 encoded signature (`b=` tag)
+    pub fn signature(&self) -> Option<&str> {
+        self.tag("b")
+    }
+
+    /// Returns the base64 encoded body hash (`bh=` tag)
+    pub fn body_hash(&self) -> Option<&str> {
+        self.tag("bh")
+    }
+
+    /// Returns the canonicalization algorithms (`c=` tag)
+    pub fn canonicalization(&self) -> Option<&str> {
+        self.tag("c")
+    }
+
+    /// Returns the signing domain (`d=` tag)
+    pub fn domain(&self) -> Option<&str> {
+        self.tag("d")
+    }
+
+    /// Returns the selector (`s=` tag)
+    pub fn selector(&self) -> Option<&str> {
+        self.tag("s")
+    }
+
+    /// Returns the signed header field names (`h=` tag)
+    pub fn signed_headers(&self) -> &[Cow<'x, str>] {
+        &self.signed_headers
+    }
+
+    pub fn into_owned(self) -> DkimSignature<'static> {
+        DkimSignature {
+            tags: self
+                .tags
+                .into_iter()
+                .map(|(tag, value)| (tag.into_owned().into(), value.into_owned().into()))
+                .collect(),
+            signed_headers: self
+                .signed_headers
+                .into_iter()
+                .map(|name| name.into_owned().into())
+                .collect(),
+        }
+    }
+}
+
+/// An Authentication-Results header
+impl<'x> AuthenticationResults<'x> {
+    /// Returns the first result of an authentication method such as `spf`, `dkim` or `dmarc`
+    pub fn result(&self, method: &str) -> Option<&AuthenticationResult<'x>> {
+        self.results
+            .iter()
+            .find(|result| result.method.eq_ignore_ascii_case(method))
+    }
+
+    pub fn into_owned(self) -> AuthenticationResults<'static> {
+        AuthenticationResults {
+            authserv_id: self.authserv_id.into_owned().into(),
+            version: self.version,
+            results: self.results.into_iter().map(|r| r.into_owned()).collect(),
+        }
+    }
+}
+
+impl<'x> AuthenticationResult<'x> {
+    /// Returns `true` when the result is `pass`
+    pub fn is_pass(&self) -> bool {
+        self.result.eq_ignore_ascii_case("pass")
+    }
+
+    /// Returns the value of a property such as `smtp.mailfrom` or `header.d`
+    pub fn property(&self, ptype: &str, property: &str) -> Option<&str> {
+        self.properties
+            .iter()
+            .find(|p| {
+                p.ptype.eq_ignore_ascii_case(ptype) && p.property.eq_ignore_ascii_case(property)
+            })
+            .map(|p| p.value.as_ref())
+    }
+
+    pub fn into_owned(self) -> AuthenticationResult<'static> {
+        AuthenticationResult {
+            method: self.method.into_owned().into(),
+            method_version: self.method_version,
+            result: self.result.into_owned().into(),
+            reason: self.reason.map(|s| s.into_owned().into()),
+            properties: self
+                .properties
+                .into_iter()
+                .map(|p| AuthenticationProperty {
+                    ptype: p.ptype.into_owned().into(),
+                    property: p.property.into_owned().into(),
+                    value: p.value.into_owned().into(),
+                })
+                .collect(),
+        }
+    }
+}
+
 /// A hostname or IP address.
 impl<'x> Host<'x> {
     pub fn into_owned(self) -> Host<'static> {
@@ -928,7 +1468,7 @@ impl<'x> GetHeader<'x> for Vec<Header<'x>> {
             .map(|header| &header.value)
     }
 
-    fn header(&self, name: impl Into<HeaderName<'x>>) -> Option<&Header> {
+    fn header(&self, name: impl Into<HeaderName<'x>>) -> Option<&Header<'_>> {
         let name = name.into();
         self.iter().rev().find(|header| header.name == name)
     }
@@ -946,6 +1486,12 @@ impl<'x> From<Cow<'x, str>> for HeaderName<'x> {
     }
 }
 
+impl<'x> From<&HeaderName<'x>> for HeaderName<'x> {
+    fn from(value: &HeaderName<'x>) -> Self {
+        value.clone()
+    }
+}
+
 impl<'x> From<String> for HeaderName<'x> {
     fn from(value: String) -> Self {
         HeaderName::parse(value).unwrap_or(HeaderName::Other("".into()))
@@ -1106,3 +1652,20 @@ impl Display for HeaderName<'_> {
         write!(f, "{}", self.as_str())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::find_delimiter;
+
+    #[test]
+    fn find_boundary_delimiter() {
+        let body = b"--b2\n--b-x\n--b \nline --b\n--b\r\n--b--\n--b";
+        let mut offsets = vec![];
+        let mut pos = 0;
+        while let Some(offset) = find_delimiter(body, pos, b"b") {
+            offsets.push(offset);
+            pos = offset + 1;
+        }
+        assert_eq!(offsets, [11, 25, 30, 36]);
+    }
+}
@@ -0,0 +1,323 @@
+/*
+ * Copyright Stalwart Labs Ltd. See the COPYING
+ * file at the top-level directory of this distribution.
+ *
+ * Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+ * https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+ * <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+ * option. This file may not be copied, modified, or distributed
+ * except according to those terms.
+ */
+
+use alloc::{borrow::Cow, format, string::String};
+use core::fmt::Write;
+
+use crate::{
+    core::address::write_encoded_words, parsers::message::count_lines, Addr, Address, ContentType,
+    HeaderName, HeaderValue, Message, MimeHeaders, PartType,
+};
+
+impl<'x> Message<'x> {
+    /// Returns the IMAP `BODYSTRUCTURE` of the message as defined in RFC 3501,
+    /// including the extension data when `extended` is `true` or the
+    /// non-extensible `BODY` form otherwise.
+    pub fn body_structure(&self, extended: bool) -> String {
+        let mut buf = String::with_capacity(128);
+        self.write_body_structure(&mut buf, 0, false, extended);
+        buf
+    }
+
+    /// Returns the IMAP `ENVELOPE` of the message as defined in RFC 3501.
+    ///
+    /// Non-ASCII display names are sent as RFC2047 encoded-words.
+    pub fn envelope(&self) -> String {
+        let mut buf = String::with_capacity(128);
+        self.write_envelope(&mut buf);
+        buf
+    }
+
+    fn write_body_structure(&self, buf: &mut String, pos: usize, in_digest: bool, extended: bool) {
+        let Some(part) = self.parts.get(pos) else {
+            // Truncated nested message, describe it as an empty text part
+            buf.push_str("(\"text\" \"plain\" (\"charset\" \"us-ascii\") NIL NIL \"7bit\" 0 0");
+            if extended {
+                buf.push_str(" NIL NIL NIL NIL");
+            }
+            buf.push(')');
+            return;
+        };
+        let content_type = part.content_type();
+
+        buf.push('(');
+        if let PartType::Multipart(sub_parts) = &part.body {
+            let is_digest = content_type
+                .and_then(|ct| ct.subtype())
+                .is_some_and(|st| st.eq_ignore_ascii_case("digest"));
+            for &sub_part in sub_parts {
+                self.write_body_structure(buf, sub_part, is_digest, extended);
+            }
+            buf.push(' ');
+            write_string(
+                buf,
+                content_type.and_then(|ct| ct.subtype()).unwrap_or("mixed"),
+            );
+            if extended {
+                buf.push(' ');
+                write_params(buf, content_type);
+                buf.push(' ');
+                self.write_extension(buf, pos);
+            }
+        } else {
+            let (c_type, c_subtype) = match content_type {
+                Some(ct) => (
+                    ct.ctype(),
+                    ct.subtype().unwrap_or(match ct.ctype() {
+                        "text" => "plain",
+                        "message" => "rfc822",
+                        _ => "octet-stream",
+                    }),
+                ),
+                None if in_digest => ("message", "rfc822"),
+                None => ("text", "plain"),
+            };
+            write_string(buf, c_type);
+            buf.push(' ');
+            write_string(buf, c_subtype);
+            buf.push(' ');
+            if content_type.is_some() || in_digest {
+                write_params(buf, content_type);
+            } else {
+                buf.push_str("(\"charset\" \"us-ascii\")");
+            }
+            buf.push(' ');
+            write_nstring(buf, part.content_id().map(|id| format!("<{id}>")));
+            buf.push(' ');
+            write_nstring(
+                buf,
+                self.part_header_text(pos, HeaderName::ContentDescription),
+            );
+            buf.push(' ');
+            write_string(buf, part.content_transfer_encoding().unwrap_or("7bit"));
+            let _ = write!(buf, " {}", part.raw_body_len());
+
+            match &part.body {
+                PartType::Message(message) => {
+                    buf.push(' ');
+                    message.write_envelope(buf);
+                    buf.push(' ');
+                    message.write_body_structure(buf, 0, false, extended);
+                    let _ = write!(
+                        buf,
+                        " {}",
+                        self.raw_message
+                            .get(part.offset_body..part.offset_end)
+                            .map_or(0, count_lines)
+                    );
+                }
+                PartType::Text(_) | PartType::Html(_) => {
+                    let _ = write!(buf, " {}", part.line_count());
+                }
+                _ => (),
+            }
+
+            if extended {
+                buf.push(' ');
+                write_nstring(buf, self.part_header_text(pos, "Content-MD5"));
+                buf.push(' ');
+                self.write_extension(buf, pos);
+            }
+        }
+        buf.push(')');
+    }
+
+    fn write_extension(&self, buf: &mut String, pos: usize) {
+        let part = &self.parts[pos];
+
+        // Disposition
+        if let Some(disposition) = part.content_disposition() {
+            buf.push('(');
+            write_string(buf, disposition.ctype());
+            buf.push(' ');
+            write_params(buf, Some(disposition));
+            buf.push(')');
+        } else {
+            buf.push_str("NIL");
+        }
+
+        // Language
+        buf.push(' ');
+        match part.content_language() {
+            HeaderValue::Text(language) => write_string(buf, language),
+            HeaderValue::TextList(languages) => {
+                buf.push('(');
+                for (pos, language) in languages.iter().enumerate() {
+                    if pos > 0 {
+                        buf.push(' ');
+                    }
+                    write_string(buf, language);
+                }
+                buf.push(')');
+            }
+            _ => buf.push_str("NIL"),
+        }
+
+        // Location
+        buf.push(' ');
+        write_nstring(buf, part.content_location());
+    }
+
+    fn write_envelope(&self, buf: &mut String) {
+        if self.parts.is_empty() {
+            buf.push_str("NIL");
+            return;
+        }
+        buf.push('(');
+        write_nstring(buf, self.part_header_text(0, HeaderName::Date));
+        buf.push(' ');
+        write_nstring(buf, self.part_header_text(0, HeaderName::Subject));
+        let from = self.from();
+        for address in [
+            from,
+            self.sender().or(from),
+            self.reply_to().or(from),
+            self.to(),
+            self.cc(),
+            self.bcc(),
+        ] {
+            buf.push(' ');
+            write_addresses(buf, address);
+        }
+        buf.push(' ');
+        write_nstring(buf, self.part_header_text(0, HeaderName::InReplyTo));
+        buf.push(' ');
+        write_nstring(buf, self.part_header_text(0, HeaderName::MessageId));
+        buf.push(')');
+    }
+
+    /// Returns the unfolded raw value of a header field
+    fn part_header_text(&self, pos: usize, header: impl Into<HeaderName<'x>>) -> Option<String> {
+        let value = String::from_utf8_lossy(self.part_header_raw(pos, header)?);
+        let value = value
+            .split('\n')
+            .map(|line| line.strip_suffix('\r').unwrap_or(line))
+            .collect::<String>();
+        let value = value.trim();
+        (!value.is_empty()).then(|| value.into())
+    }
+}
+
+fn write_addresses(buf: &mut String, address: Option<&Address<'_>>) {
+    let address = match address {
+        Some(address @ Address::List(list)) if !list.is_empty() => address,
+        Some(address @ Address::Group(groups))
+            if groups
+                .iter()
+                .any(|group| group.name.is_some() || !group.addresses.is_empty()) =>
+        {
+            address
+        }
+        _ => {
+            buf.push_str("NIL");
+            return;
+        }
+    };
+
+    buf.push('(');
+    match address {
+        Address::List(list) => {
+            for addr in list {
+                write_addr(buf, addr);
+            }
+        }
+        Address::Group(groups) => {
+            for group in groups {
+                if let Some(name) = &group.name {
+                    buf.push_str("(NIL NIL ");
+                    write_phrase(buf, name);
+                    buf.push_str(" NIL)");
+                    for addr in &group.addresses {
+                        write_addr(buf, addr);
+                    }
+                    buf.push_str("(NIL NIL NIL NIL)");
+                } else {
+                    for addr in &group.addresses {
+                        write_addr(buf, addr);
+                    }
+                }
+            }
+        }
+    }
+    buf.push(')');
+}
+
+fn write_addr(buf: &mut String, addr: &Addr<'_>) {
+    // A NIL host is reserved for group delimiters
+    let (mailbox, host) = addr
+        .address()
+        .map(|address| address.rsplit_once('@').unwrap_or((address, "")))
+        .unwrap_or_default();
+
+    buf.push('(');
+    if let Some(name) = addr.name().filter(|name| !name.is_empty()) {
+        write_phrase(buf, name);
+    } else {
+        buf.push_str("NIL");
+    }
+    buf.push_str(" NIL ");
+    write_string(buf, mailbox);
+    buf.push(' ');
+    write_string(buf, host);
+    buf.push(')');
+}
+
+fn write_phrase(buf: &mut String, phrase: &str) {
+    if phrase.is_ascii() {
+        write_string(buf, phrase);
+    } else {
+        let mut encoded = String::with_capacity(phrase.len() * 2);
+        let _ = write_encoded_words(&mut encoded, phrase);
+        write_string(buf, &encoded);
+    }
+}
+
+fn write_params(buf: &mut String, content_type: Option<&ContentType<'_>>) {
+    match content_type.and_then(|ct| ct.attributes()) {
+        Some(attributes) if !attributes.is_empty() => {
+            buf.push('(');
+            for (pos, (name, value)) in attributes.iter().enumerate() {
+                if pos > 0 {
+                    buf.push(' ');
+                }
+                write_string(buf, name);
+                buf.push(' ');
+                write_string(buf, value);
+            }
+            buf.push(')');
+        }
+        _ => buf.push_str("NIL"),
+    }
+}
+
+fn write_nstring<'y>(buf: &mut String, value: Option<impl Into<Cow<'y, str>>>) {
+    if let Some(value) = value {
+        write_string(buf, &value.into());
+    } else {
+        buf.push_str("NIL");
+    }
+}
+
+fn write_string(buf: &mut String, value: &str) {
+    if value.bytes().all(|ch| (0x20..0x7f).contains(&ch)) {
+        buf.push('"');
+        for ch in value.chars() {
+            if ch == '"' || ch == '\\' {
+                buf.push('\\');
+            }
+            buf.push(ch);
+        }
+        buf.push('"');
+    } else {
+        // Non-ASCII or control characters require a literal
+        let _ = write!(buf, "{{{}}}\r\n{}", value.len(), value);
+    }
+}
@@ -9,17 +9,30 @@
  * except according to those terms.
  */
 
-use std::{borrow::Cow, convert::TryInto};
+use alloc::{borrow::Cow, string::String, vec, vec::Vec};
+use core::convert::TryInto;
 
 use crate::{
-    decoders::html::{html_to_text, text_to_html},
+    decoders::{
+        flowed::decode_flowed,
+        hex::decode_hex,
+        html::{html_to_text, text_to_html},
+    },
     parsers::{
-        fields::thread::thread_name,
+        fields::{
+            id::is_valid_message_id,
+            remove_comments,
+            thread::{normalized_thread_name, thread_name},
+        },
+        header::strip_line_break,
         preview::{preview_html, preview_text},
+        urls::{html_urls, is_cid_url, text_urls},
         MessageStream,
     },
-    Address, AttachmentIterator, BodyPartIterator, DateTime, GetHeader, Header, HeaderForm,
-    HeaderName, HeaderValue, Message, MessageParser, MessagePart, PartType, Received,
+    Addr, Address, AttachmentIterator, AuthenticationResults, Autocrypt, BodyPartIterator,
+    DateTime, DkimSignature, GetHeader, Header, HeaderForm, HeaderName, HeaderValue, ListId,
+    Message, MessageParser, MessagePart, MessagePartId, MimeHeaders, PartType, Priority, Received,
+    ResentBlock, ThreadIndex,
 };
 
 impl<'x> Message<'x> {
@@ -29,26 +42,36 @@ impl<'x> Message<'x> {
     }
 
     /// Returns a parsed header.
-    pub fn header(&self, header: impl Into<HeaderName<'x>>) -> Option<&HeaderValue> {
-        self.parts[0].headers.header(header).map(|h| &h.value)
+    pub fn header(&self, header: impl Into<HeaderName<'x>>) -> Option<&HeaderValue<'_>> {
+        self.parts[0].header(header)
     }
 
     /// Removed a parsed header and returns its value.
-    pub fn remove_header(&mut self, header: impl Into<HeaderName<'x>>) -> Option<HeaderValue> {
+    pub fn remove_header(&mut self, header: impl Into<HeaderName<'x>>) -> Option<HeaderValue<'_>> {
         let header = header.into();
-        let headers = &mut self.parts[0].headers;
-        headers
+        let part = &mut self.parts[0];
+        part.header_index = None;
+        part.headers
             .iter()
             .position(|h| h.name == header)
-            .map(|pos| headers.swap_remove(pos).value)
+            .map(|pos| part.headers.swap_remove(pos).value)
     }
 
-    /// Returns the raw header.
+    /// Returns the raw header value, including its terminating line break.
+    /// Use [`Message::part_header_raw`] to obtain it without the line break.
     pub fn header_raw(&self, header: impl Into<HeaderName<'x>>) -> Option<&str> {
-        self.parts[0]
-            .headers
-            .header(header)
-            .and_then(|h| std::str::from_utf8(&self.raw_message[h.offset_start..h.offset_end]).ok())
+        self.parts[0].headers.header(header).and_then(|h| {
+            core::str::from_utf8(&self.raw_message[h.offset_start..h.offset_end]).ok()
+        })
+    }
+
+    /// Returns the raw bytes of a header of the specified part, as they appear
+    /// between the colon and the terminating line break.
+    pub fn part_header_raw(&self, pos: usize, header: impl Into<HeaderName<'x>>) -> Option<&[u8]> {
+        let header = self.parts.get(pos)?.headers.header(header)?;
+        self.raw_message
+            .get(header.offset_start..header.offset_end)
+            .map(strip_line_break)
     }
 
     // Parse a header as a specific type.
@@ -56,7 +79,7 @@ impl<'x> Message<'x> {
         &self,
         header: impl Into<HeaderName<'x>>,
         form: HeaderForm,
-    ) -> Vec<HeaderValue> {
+    ) -> Vec<HeaderValue<'_>> {
         let header = header.into();
         let mut results = Vec::new();
         for header_ in &self.parts[0].headers {
@@ -66,7 +89,10 @@ impl<'x> Message<'x> {
                         .get(header_.offset_start..header_.offset_end)
                         .map_or(HeaderValue::Empty, |bytes| match form {
                             HeaderForm::Raw => HeaderValue::Text(
-                                std::str::from_utf8(bytes).unwrap_or_default().trim().into(),
+                                core::str::from_utf8(bytes)
+                                    .unwrap_or_default()
+                                    .trim()
+                                    .into(),
                             ),
                             HeaderForm::Text => MessageStream::new(bytes).parse_unstructured(),
                             HeaderForm::Addresses => MessageStream::new(bytes).parse_address(),
@@ -85,7 +111,7 @@ impl<'x> Message<'x> {
     }
 
     /// Returns an iterator over the RFC headers of this message.
-    pub fn headers(&self) -> &[Header] {
+    pub fn headers(&self) -> &[Header<'_>] {
         &self.parts[0].headers
     }
 
@@ -93,15 +119,8 @@ impl<'x> Message<'x> {
     pub fn header_values<'y: 'x>(
         &'y self,
         name: impl Into<HeaderName<'x>>,
-    ) -> impl Iterator<Item = &HeaderValue<'x>> {
-        let name = name.into();
-        self.parts[0].headers.iter().filter_map(move |header| {
-            if header.name == name {
-                Some(&header.value)
-            } else {
-                None
-            }
-        })
+    ) -> impl Iterator<Item = &'y HeaderValue<'x>> {
+        self.parts[0].header_values(name)
     }
 
     /// Returns all headers in raw format
@@ -109,12 +128,21 @@ impl<'x> Message<'x> {
         self.parts[0].headers.iter().filter_map(move |header| {
             Some((
                 header.name.as_str(),
-                std::str::from_utf8(&self.raw_message[header.offset_start..header.offset_end])
+                core::str::from_utf8(&self.raw_message[header.offset_start..header.offset_end])
                     .ok()?,
             ))
         })
     }
 
+    /// Returns an iterator over the headers of all parts of this message in
+    /// their original order, along with the raw offsets of each header value.
+    /// Headers of nested messages can be obtained from the nested message itself.
+    pub fn headers_iter(
+        &self,
+    ) -> impl Iterator<Item = (&HeaderName<'x>, &HeaderValue<'x>, usize, usize)> {
+        self.parts.iter().flat_map(|part| part.headers_raw())
+    }
+
     /// Returns the raw message
     pub fn raw_message(&self) -> &[u8] {
         let part = &self.parts[0];
@@ -123,8 +151,27 @@ impl<'x> Message<'x> {
             .unwrap_or_default()
     }
 
+    /// Returns the size in bytes of the raw message, which is the number of
+    /// bytes consumed from the input
+    pub fn raw_len(&self) -> usize {
+        self.parts.first().map_or(0, MessagePart::raw_len)
+    }
+
+    /// Returns the raw body of the message, before any decoding
+    pub fn raw_body(&self) -> &[u8] {
+        let part = &self.parts[0];
+        self.raw_message
+            .get(part.offset_body..part.offset_end)
+            .unwrap_or_default()
+    }
+
+    /// Returns the offset where the raw body of the message starts
+    pub fn raw_body_offset(&self) -> usize {
+        self.parts[0].offset_body
+    }
+
     /// Returns the BCC header field
-    pub fn bcc<'y: 'x>(&'y self) -> Option<&Address<'x>> {
+    pub fn bcc<'y: 'x>(&'y self) -> Option<&'y Address<'x>> {
         self.parts[0]
             .headers
             .header_value(&HeaderName::Bcc)
@@ -132,7 +179,7 @@ impl<'x> Message<'x> {
     }
 
     /// Returns the CC header field
-    pub fn cc<'y: 'x>(&'y self) -> Option<&Address<'x>> {
+    pub fn cc<'y: 'x>(&'y self) -> Option<&'y Address<'x>> {
         self.parts[0]
             .headers
             .header_value(&HeaderName::Cc)
@@ -140,7 +187,7 @@ impl<'x> Message<'x> {
     }
 
     /// Returns all Comments header fields
-    pub fn comments(&self) -> &HeaderValue {
+    pub fn comments(&self) -> &HeaderValue<'_> {
         self.parts[0]
             .headers
             .header_value(&HeaderName::Comments)
@@ -156,7 +203,7 @@ impl<'x> Message<'x> {
     }
 
     /// Returns the From header field
-    pub fn from<'y: 'x>(&'y self) -> Option<&Address<'x>> {
+    pub fn from<'y: 'x>(&'y self) -> Option<&'y Address<'x>> {
         self.parts[0]
             .headers
             .header_value(&HeaderName::From)
@@ -164,7 +211,7 @@ impl<'x> Message<'x> {
     }
 
     /// Returns all In-Reply-To header fields
-    pub fn in_reply_to(&self) -> &HeaderValue {
+    pub fn in_reply_to(&self) -> &HeaderValue<'_> {
         self.parts[0]
             .headers
             .header_value(&HeaderName::InReplyTo)
@@ -172,7 +219,7 @@ impl<'x> Message<'x> {
     }
 
     /// Returns all Keywords header fields
-    pub fn keywords(&self) -> &HeaderValue {
+    pub fn keywords(&self) -> &HeaderValue<'_> {
         self.parts[0]
             .headers
             .header_value(&HeaderName::Keywords)
@@ -180,7 +227,7 @@ impl<'x> Message<'x> {
     }
 
     /// Returns the List-Archive header field
-    pub fn list_archive(&self) -> &HeaderValue {
+    pub fn list_archive(&self) -> &HeaderValue<'_> {
         self.parts[0]
             .headers
             .header_value(&HeaderName::ListArchive)
@@ -188,7 +235,7 @@ impl<'x> Message<'x> {
     }
 
     /// Returns the List-Help header field
-    pub fn list_help(&self) -> &HeaderValue {
+    pub fn list_help(&self) -> &HeaderValue<'_> {
         self.parts[0]
             .headers
             .header_value(&HeaderName::ListHelp)
@@ -196,15 +243,77 @@ impl<'x> Message<'x> {
     }
 
     /// Returns the List-ID header field
-    pub fn list_id(&self) -> &HeaderValue {
+    pub fn list_id(&self) -> &HeaderValue<'_> {
         self.parts[0]
             .headers
             .header_value(&HeaderName::ListId)
             .unwrap_or(&HeaderValue::Empty)
     }
 
+    /// Returns the description and identifier of the List-Id header field
+    pub fn list_identifier(&self) -> Option<ListId<'_>> {
+        let addr = match self.parts[0].headers.header_value(&HeaderName::ListId)? {
+            HeaderValue::Address(address) => address.first().cloned(),
+            // Headers parsed with a custom parser are reparsed as an address
+            _ => HeaderValue::parse_address(self.header_raw(HeaderName::ListId)?)
+                .into_address()
+                .and_then(|address| address.into_list().into_iter().next()),
+        }?;
+
+        match addr.address {
+            Some(id) if !id.is_empty() => Some(ListId {
+                description: addr.name.filter(|name| !name.is_empty()),
+                id,
+            }),
+            // Identifiers that are not enclosed in angle brackets
+            _ => addr
+                .name
+                .filter(|name| !name.is_empty() && !name.contains(char::is_whitespace))
+                .map(|id| ListId {
+                    description: None,
+                    id,
+                }),
+        }
+    }
+
+    /// Returns the RFC8098 Disposition-Notification-To header field, the
+    /// addresses a read receipt was requested to be sent to
+    pub fn read_receipt_to<'y: 'x>(&'y self) -> Option<&'y Address<'x>> {
+        self.parts[0]
+            .headers
+            .header_value(&HeaderName::Other("Disposition-Notification-To".into()))
+            .and_then(|a| a.as_address())
+    }
+
+    /// Returns the legacy Return-Receipt-To header field
+    pub fn return_receipt_to<'y: 'x>(&'y self) -> Option<&'y Address<'x>> {
+        self.parts[0]
+            .headers
+            .header_value(&HeaderName::Other("Return-Receipt-To".into()))
+            .and_then(|a| a.as_address())
+    }
+
+    /// Returns the Original-Message-ID header field
+    pub fn original_message_id(&self) -> Option<Cow<'_, str>> {
+        match HeaderValue::parse_id(self.header_raw("Original-Message-ID")?) {
+            HeaderValue::Text(id) => Some(id),
+            HeaderValue::TextList(ids) => ids.into_iter().next(),
+            _ => None,
+        }
+    }
+
+    /// Returns the Microsoft Thread-Index header field
+    pub fn thread_index(&self) -> Option<ThreadIndex> {
+        ThreadIndex::parse(self.header_raw("Thread-Index")?)
+    }
+
+    /// Returns the Microsoft Thread-Topic header field
+    pub fn thread_topic(&self) -> Option<Cow<'_, str>> {
+        HeaderValue::parse_text(self.header_raw("Thread-Topic")?).into_text()
+    }
+
     /// Returns the List-Owner header field
-    pub fn list_owner(&self) -> &HeaderValue {
+    pub fn list_owner(&self) -> &HeaderValue<'_> {
         self.parts[0]
             .headers
             .header_value(&HeaderName::ListOwner)
@@ -212,7 +321,7 @@ impl<'x> Message<'x> {
     }
 
     /// Returns the List-Post header field
-    pub fn list_post(&self) -> &HeaderValue {
+    pub fn list_post(&self) -> &HeaderValue<'_> {
         self.parts[0]
             .headers
             .header_value(&HeaderName::ListPost)
@@ -220,7 +329,7 @@ impl<'x> Message<'x> {
     }
 
     /// Returns the List-Subscribe header field
-    pub fn list_subscribe(&self) -> &HeaderValue {
+    pub fn list_subscribe(&self) -> &HeaderValue<'_> {
         self.parts[0]
             .headers
             .header_value(&HeaderName::ListSubscribe)
@@ -228,13 +337,144 @@ impl<'x> Message<'x> {
     }
 
     /// Returns the List-Unsubscribe header field
-    pub fn list_unsubscribe(&self) -> &HeaderValue {
+    pub fn list_unsubscribe(&self) -> &HeaderValue<'_> {
         self.parts[0]
             .headers
             .header_value(&HeaderName::ListUnsubscribe)
             .unwrap_or(&HeaderValue::Empty)
     }
 
+    /// Returns the URIs listed in the List-Unsubscribe header field
+    pub fn list_unsubscribe_uris(&self) -> Vec<Cow<'_, str>> {
+        self.list_uris(HeaderName::ListUnsubscribe)
+    }
+
+    /// Returns the URIs listed in the List-Subscribe header field
+    pub fn list_subscribe_uris(&self) -> Vec<Cow<'_, str>> {
+        self.list_uris(HeaderName::ListSubscribe)
+    }
+
+    /// Returns the URIs listed in the List-Post header field, which is empty
+    /// when posting is not allowed (see [`Message::is_list_post_disallowed`])
+    pub fn list_post_uris(&self) -> Vec<Cow<'_, str>> {
+        if !self.is_list_post_disallowed() {
+            self.list_uris(HeaderName::ListPost)
+        } else {
+            Vec::new()
+        }
+    }
+
+    /// Returns `true` when the List-Post header field is `NO`, meaning
+    /// that posting to the list is not allowed
+    pub fn is_list_post_disallowed(&self) -> bool {
+        self.header_raw(HeaderName::ListPost).is_some_and(|value| {
+            // Strip any comment, such as "NO (posting not allowed)"
+            value
+                .split('(')
+                .next()
+                .unwrap_or_default()
+                .trim()
+                .eq_ignore_ascii_case("NO")
+        })
+    }
+
+    /// Returns the URIs listed in the List-Archive header field
+    pub fn list_archive_uris(&self) -> Vec<Cow<'_, str>> {
+        self.list_uris(HeaderName::ListArchive)
+    }
+
+    /// Returns the URIs listed in the List-Help header field
+    pub fn list_help_uris(&self) -> Vec<Cow<'_, str>> {
+        self.list_uris(HeaderName::ListHelp)
+    }
+
+    /// Returns the URIs listed in the List-Owner header field
+    pub fn list_owner_uris(&self) -> Vec<Cow<'_, str>> {
+        self.list_uris(HeaderName::ListOwner)
+    }
+
+    /// Returns the angle-bracketed URIs of an RFC2369 List-* header field
+    fn list_uris(&self, header: HeaderName<'_>) -> Vec<Cow<'_, str>> {
+        match self.parts[0]
+            .headers
+            .header_value(&header)
+            .and_then(|v| v.as_address())
+        {
+            Some(address) => address
+                .iter()
+                .filter_map(|addr| addr.address.clone())
+                .collect(),
+            None => Vec::new(),
+        }
+    }
+
+    /// Returns `true` when the List-Unsubscribe-Post header field requests
+    /// one-click unsubscription (RFC8058)
+    pub fn is_list_unsubscribe_one_click(&self) -> bool {
+        self.parts[0]
+            .headers
+            .iter()
+            .filter(|h| {
+                h.name
+                    .as_str()
+                    .eq_ignore_ascii_case("List-Unsubscribe-Post")
+            })
+            .any(|h| {
+                h.value
+                    .as_text()
+                    .is_some_and(|v| v.trim().eq_ignore_ascii_case("List-Unsubscribe=One-Click"))
+            })
+    }
+
+    /// Returns the priority of the message from its `Importance`, `X-Priority`
+    /// or `Priority` header fields, in that order of preference, or
+    /// [`Priority::Normal`] when none of them is present or recognized.
+    pub fn priority(&self) -> Priority {
+        let value = |name: &'static str| {
+            self.header_raw(name).map(|value| {
+                // Strip any comment, such as "1 (Highest)"
+                value.split('(').next().unwrap_or_default().trim()
+            })
+        };
+
+        value("Importance")
+            .and_then(|value| {
+                if value.eq_ignore_ascii_case("high") {
+                    Some(Priority::High)
+                } else if value.eq_ignore_ascii_case("normal") {
+                    Some(Priority::Normal)
+                } else if value.eq_ignore_ascii_case("low") {
+                    Some(Priority::Low)
+                } else {
+                    None
+                }
+            })
+            .or_else(|| {
+                value("X-Priority").and_then(|value| {
+                    match value.split_ascii_whitespace().next()?.parse::<u8>().ok()? {
+                        1 | 2 => Some(Priority::High),
+                        3 => Some(Priority::Normal),
+                        4 | 5 => Some(Priority::Low),
+                        _ => None,
+                    }
+                })
+            })
+            .or_else(|| {
+                value("Priority").and_then(|value| {
+                    if value.eq_ignore_ascii_case("urgent") {
+                        Some(Priority::High)
+                    } else if value.eq_ignore_ascii_case("normal") {
+                        Some(Priority::Normal)
+                    } else if value.eq_ignore_ascii_case("non-urgent") {
+                        Some(Priority::Low)
+                    } else {
+                        None
+                    }
+                })
+            })
+            .unwrap_or_default()
+    }
+
     /// Returns the Message-ID header field
     pub fn message_id(&self) -> Option<&str> {
         self.parts[0]
@@ -243,100 +483,244 @@ impl<'x> Message<'x> {
             .and_then(|header| header.as_text())
     }
 
-    /// Returns the MIME-Version header field
-    pub fn mime_version(&self) -> &HeaderValue {
-        self.parts[0]
+    /// Returns the domain part of the Message-ID header field, which is
+    /// the text following the last `@`
+    pub fn message_id_domain(&self) -> Option<&str> {
+        self.message_id()?
+            .rsplit_once('@')
+            .map(|(_, domain)| domain)
+            .filter(|domain| !domain.is_empty())
+    }
+
+    /// Returns the version declared by the MIME-Version header field, with any
+    /// comments and whitespace removed, such as `1.0` for
+    /// `1.0 (produced by MetaSend Vx.x)`
+    pub fn mime_version(&self) -> Option<Cow<'_, str>> {
+        let value = self.parts[0]
             .headers
-            .header_value(&HeaderName::MimeVersion)
-            .unwrap_or(&HeaderValue::Empty)
+            .header_value(&HeaderName::MimeVersion)?
+            .as_text()?;
+        let version = remove_comments(value);
+
+        if version.is_empty() || version.contains(char::is_whitespace) {
+            let version = version
+                .chars()
+                .filter(|ch| !ch.is_whitespace())
+                .collect::<String>();
+            (!version.is_empty()).then_some(version.into())
+        } else {
+            Some(version)
+        }
     }
 
-    /// Returns the first Received header field
-    pub fn received(&self) -> Option<&Received> {
+    /// Returns the newest Received header field, which is the topmost
+    /// one as relays prepend their trace fields to the message
+    pub fn received(&self) -> Option<&Received<'_>> {
         self.parts[0]
             .headers
-            .header_value(&HeaderName::Received)
-            .and_then(|header| header.as_received())
+            .iter()
+            .find(|header| header.name == HeaderName::Received)
+            .and_then(|header| header.value.as_received())
+    }
+
+    /// Returns all Authentication-Results header fields (RFC8601),
+    /// in the order they appear in the message
+    pub fn authentication_results(&self) -> impl Iterator<Item = AuthenticationResults<'_>> {
+        self.parts[0].headers.iter().filter_map(|header| {
+            if header
+                .name
+                .as_str()
+                .eq_ignore_ascii_case("Authentication-Results")
+            {
+                MessageStream::new(
+                    self.raw_message
+                        .get(header.offset_start..header.offset_end)?,
+                )
+                .parse_authentication_results()
+            } else {
+                None
+            }
+        })
+    }
+
+    /// Returns all DKIM-Signature header fields (RFC6376),
+    /// in the order they appear in the message
+    pub fn dkim_signatures(&self) -> impl Iterator<Item = DkimSignature<'_>> {
+        self.parts[0].headers.iter().filter_map(|header| {
+            if header.name.as_str().eq_ignore_ascii_case("DKIM-Signature") {
+                MessageStream::new(
+                    self.raw_message
+                        .get(header.offset_start..header.offset_end)?,
+                )
+                .parse_dkim_signature()
+            } else {
+                None
+            }
+        })
+    }
+
+    /// Returns the Autocrypt header field, or `None` when it is absent, cannot
+    /// be parsed or more than one valid Autocrypt header is present. The `addr`
+    /// attribute is not matched against the From address.
+    pub fn autocrypt(&self) -> Option<Autocrypt<'_>> {
+        let mut headers = self.parts[0].headers.iter().filter_map(|header| {
+            if header.name.as_str().eq_ignore_ascii_case("Autocrypt") {
+                MessageStream::new(
+                    self.raw_message
+                        .get(header.offset_start..header.offset_end)?,
+                )
+                .parse_autocrypt()
+            } else {
+                None
+            }
+        });
+        let autocrypt = headers.next()?;
+        if headers.next().is_none() {
+            Some(autocrypt)
+        } else {
+            None
+        }
     }
 
     /// Returns all References header fields
-    pub fn references(&self) -> &HeaderValue {
+    pub fn references(&self) -> &HeaderValue<'_> {
         self.parts[0]
             .headers
             .header_value(&HeaderName::References)
             .unwrap_or(&HeaderValue::Empty)
     }
 
+    /// Returns the message ids of the References and In-Reply-To header fields
+    /// merged in that order, without duplicates or syntactically invalid ids.
+    pub fn thread_references(&self) -> Vec<&str> {
+        let mut ids: Vec<&str> = Vec::new();
+        for id in [self.references(), self.in_reply_to()]
+            .into_iter()
+            .flat_map(|value| value.as_text_list().unwrap_or_default())
+        {
+            if is_valid_message_id(id) && !ids.contains(&id) {
+                ids.push(id);
+            }
+        }
+        ids
+    }
+
     /// Returns the Reply-To header field
-    pub fn reply_to<'y: 'x>(&'y self) -> Option<&Address<'x>> {
+    pub fn reply_to<'y: 'x>(&'y self) -> Option<&'y Address<'x>> {
         self.parts[0]
             .headers
             .header_value(&HeaderName::ReplyTo)
             .and_then(|a| a.as_address())
     }
 
-    /// Returns the Resent-BCC header field
-    pub fn resent_bcc<'y: 'x>(&'y self) -> Option<&Address<'x>> {
-        self.parts[0]
-            .headers
-            .header_value(&HeaderName::ResentBcc)
-            .and_then(|a| a.as_address())
+    /// Returns the Resent-BCC header field of the most recent resent block
+    pub fn resent_bcc(&self) -> Option<&Address<'_>> {
+        self.resent_blocks().next()?.bcc
     }
 
-    /// Returns the Resent-CC header field
-    pub fn resent_cc<'y: 'x>(&'y self) -> Option<&Address<'x>> {
-        self.parts[0]
-            .headers
-            .header_value(&HeaderName::ResentTo)
-            .and_then(|a| a.as_address())
+    /// Returns the Resent-CC header field of the most recent resent block
+    pub fn resent_cc(&self) -> Option<&Address<'_>> {
+        self.resent_blocks().next()?.cc
     }
 
-    /// Returns all Resent-Date header fields
-    pub fn resent_date(&self) -> &HeaderValue {
-        self.parts[0]
-            .headers
-            .header_value(&HeaderName::ResentDate)
-            .unwrap_or(&HeaderValue::Empty)
+    /// Returns the Resent-Date header field of the most recent resent block
+    pub fn resent_date(&self) -> Option<&DateTime> {
+        self.resent_blocks().next()?.date
     }
 
-    /// Returns the Resent-From header field
-    pub fn resent_from<'y: 'x>(&'y self) -> Option<&Address<'x>> {
-        self.parts[0]
-            .headers
-            .header_value(&HeaderName::ResentFrom)
-            .and_then(|a| a.as_address())
+    /// Returns the Resent-From header field of the most recent resent block
+    pub fn resent_from(&self) -> Option<&Address<'_>> {
+        self.resent_blocks().next()?.from
     }
 
-    /// Returns all Resent-Message-ID header fields
-    pub fn resent_message_id(&self) -> &HeaderValue {
-        self.parts[0]
-            .headers
-            .header_value(&HeaderName::ResentMessageId)
-            .unwrap_or(&HeaderValue::Empty)
+    /// Returns the Resent-Message-ID header field of the most recent resent block
+    pub fn resent_message_id(&self) -> Option<&str> {
+        self.resent_blocks().next()?.message_id
     }
 
-    /// Returns the Sender header field
-    pub fn resent_sender<'y: 'x>(&'y self) -> Option<&Address<'x>> {
-        self.parts[0]
-            .headers
-            .header_value(&HeaderName::ResentSender)
-            .and_then(|a| a.as_address())
+    /// Returns the Resent-Sender header field of the most recent resent block
+    pub fn resent_sender(&self) -> Option<&Address<'_>> {
+        self.resent_blocks().next()?.sender
     }
 
-    /// Returns the Resent-To header field
-    pub fn resent_to<'y: 'x>(&'y self) -> Option<&Address<'x>> {
-        self.parts[0]
-            .headers
-            .header_value(&HeaderName::ResentTo)
-            .and_then(|a| a.as_address())
+    /// Returns the Resent-To header field of the most recent resent block
+    pub fn resent_to(&self) -> Option<&Address<'_>> {
+        self.resent_blocks().next()?.to
     }
 
-    /// Returns all Return-Path header fields
-    pub fn return_path(&self) -> &HeaderValue {
-        self.parts[0]
+    /// Returns an iterator over the blocks of Resent-* header fields, starting
+    /// with the topmost (most recent) one. As resending agents prepend their
+    /// fields to the message, a new block starts whenever a Resent-* field
+    /// already present in the current block is found.
+    pub fn resent_blocks(&self) -> impl Iterator<Item = ResentBlock<'_>> {
+        let mut headers = self.parts[0].headers.iter().peekable();
+
+        core::iter::from_fn(move || {
+            let mut block = ResentBlock::default();
+            let mut seen = 0u8;
+
+            while let Some(header) = headers.peek() {
+                let field = match header.name {
+                    HeaderName::ResentDate => 0,
+                    HeaderName::ResentFrom => 1,
+                    HeaderName::ResentSender => 2,
+                    HeaderName::ResentTo => 3,
+                    HeaderName::ResentCc => 4,
+                    HeaderName::ResentBcc => 5,
+                    HeaderName::ResentMessageId => 6,
+                    _ => {
+                        headers.next();
+                        continue;
+                    }
+                };
+                if seen & (1 << field) != 0 {
+                    // The field starts the next block
+                    break;
+                }
+                seen |= 1 << field;
+
+                let value = &headers.next()?.value;
+                match field {
+                    0 => block.date = value.as_datetime(),
+                    1 => block.from = value.as_address(),
+                    2 => block.sender = value.as_address(),
+                    3 => block.to = value.as_address(),
+                    4 => block.cc = value.as_address(),
+                    5 => block.bcc = value.as_address(),
+                    _ => block.message_id = value.as_text(),
+                }
+            }
+
+            (seen != 0).then_some(block)
+        })
+    }
+
+    /// Returns the envelope sender from the first Return-Path header field.
+    /// The null sender `<>` is returned as an `Addr` with an empty address.
+    pub fn return_path(&self) -> Option<Addr<'_>> {
+        let header = self.parts[0]
             .headers
-            .header_value(&HeaderName::ReturnPath)
-            .unwrap_or(&HeaderValue::Empty)
+            .iter()
+            .find(|header| header.name == HeaderName::ReturnPath)?;
+        let raw = self
+            .raw_message
+            .get(header.offset_start..header.offset_end)?;
+
+        if raw
+            .iter()
+            .filter(|ch| !ch.is_ascii_whitespace())
+            .eq(b"<>".iter())
+        {
+            return Some(Addr::new(None, ""));
+        }
+
+        match MessageStream::new(raw).parse_address() {
+            HeaderValue::Address(address) => address
+                .first()
+                .filter(|addr| addr.address.as_ref().is_some_and(|addr| !addr.is_empty()))
+                .cloned(),
+            _ => None,
+        }
     }
 
     /// Returns the return address from either the Return-Path
@@ -353,7 +737,7 @@ impl<'x> Message<'x> {
     }
 
     /// Returns the Sender header field
-    pub fn sender<'y: 'x>(&'y self) -> Option<&Address<'x>> {
+    pub fn sender<'y: 'x>(&'y self) -> Option<&'y Address<'x>> {
         self.parts[0]
             .headers
             .header_value(&HeaderName::Sender)
@@ -374,8 +758,14 @@ impl<'x> Message<'x> {
         thread_name(self.subject()?).into()
     }
 
+    /// Returns the thread name of the message with any runs of whitespace
+    /// collapsed into a single space.
+    pub fn normalized_thread_name(&self) -> Option<Cow<'_, str>> {
+        normalized_thread_name(self.subject()?).into()
+    }
+
     /// Returns the To header field
-    pub fn to<'y: 'x>(&'y self) -> Option<&Address<'x>> {
+    pub fn to<'y: 'x>(&'y self) -> Option<&'y Address<'x>> {
         self.parts[0]
             .headers
             .header_value(&HeaderName::To)
@@ -413,22 +803,251 @@ impl<'x> Message<'x> {
         }
     }
 
+    /// Returns the preferred plain text representation of the message body.
+    ///
+    /// When the first body part belongs to a `multipart/alternative`, the last
+    /// `text/plain` alternative is returned, as RFC 2046 orders alternatives from
+    /// least to most preferred. If there is none, the last `text/html` alternative
+    /// is converted to plain text. Otherwise, this is the same as `body_text(0)`,
+    /// which converts the first body part to plain text if it is HTML.
+    pub fn best_text(&self) -> Option<Cow<'_, str>> {
+        match self.best_alternative(false) {
+            Some(PartType::Text(text)) => Some(text.as_ref().into()),
+            Some(PartType::Html(html)) => Some(html_to_text(html.as_ref()).into()),
+            _ => self.body_text(0),
+        }
+    }
+
+    /// Returns the preferred HTML representation of the message body.
+    ///
+    /// When the first body part belongs to a `multipart/alternative`, the last
+    /// `text/html` alternative is returned. If there is none, the last `text/plain`
+    /// alternative is converted to HTML. Otherwise, this is the same as
+    /// `body_html(0)`, which converts the first body part to HTML if it is plain text.
+    pub fn best_html(&self) -> Option<Cow<'_, str>> {
+        match self.best_alternative(true) {
+            Some(PartType::Html(html)) => Some(html.as_ref().into()),
+            Some(PartType::Text(text)) => Some(text_to_html(text.as_ref()).into()),
+            _ => self.body_html(0),
+        }
+    }
+
+    /// Returns the body of the last alternative of the requested type, or of the
+    /// other type if there is none, of the `multipart/alternative` closest to
+    /// the first body part.
+    fn best_alternative(&self, html: bool) -> Option<&PartType<'x>> {
+        let parent_of = |part_id: MessagePartId| {
+            self.parts
+                .iter()
+                .position(|part| part.sub_parts().is_some_and(|ids| ids.contains(&part_id)))
+        };
+        let mut part_id = *self.text_body.first().or_else(|| self.html_body.first())?;
+        let alternatives = loop {
+            part_id = parent_of(part_id)?;
+            let part = &self.parts[part_id];
+            if part.is_content_type("multipart", "alternative") {
+                break part.sub_parts()?;
+            }
+        };
+
+        // Alternatives may be multiparts, such as a multipart/related HTML body
+        let bodies = alternatives.iter().filter_map(|&part_id| {
+            let mut part = self.parts.get(part_id)?;
+            while let Some(ids) = part.sub_parts() {
+                part = self.parts.get(*ids.first()?)?;
+            }
+            match &part.body {
+                PartType::Html(_) => Some((true, &part.body)),
+                PartType::Text(_)
+                    if part
+                        .effective_content_type()
+                        .subtype()
+                        .is_some_and(|subtype| subtype.eq_ignore_ascii_case("plain")) =>
+                {
+                    Some((false, &part.body))
+                }
+                _ => None,
+            }
+        });
+        let (mut best, mut fallback) = (None, None);
+        for (is_html, body) in bodies {
+            if is_html == html {
+                best = Some(body);
+            } else {
+                fallback = Some(body);
+            }
+        }
+        best.or(fallback)
+    }
+
+    /// Returns the text of all the inline text parts of the message and its
+    /// nested messages, separated by a line break. HTML parts are converted
+    /// to plain text and, unless `include_html` is `true`, only used when no
+    /// text/plain alternative is available.
+    pub fn text_contents(&self, include_html: bool) -> String {
+        let mut contents = String::new();
+        self.write_text_contents(&mut contents, include_html);
+        contents
+    }
+
+    fn write_text_contents(&self, contents: &mut String, include_html: bool) {
+        for (pos, part) in self.parts.iter().enumerate() {
+            let text = match &part.body {
+                PartType::Message(message) => {
+                    message.write_text_contents(contents, include_html);
+                    continue;
+                }
+                _ if !(self.text_body.contains(&pos)
+                    || include_html && self.html_body.contains(&pos)) =>
+                {
+                    continue;
+                }
+                PartType::Text(text) => Cow::from(text.as_ref()),
+                PartType::Html(html) => html_to_text(html.as_ref()).into(),
+                _ => continue,
+            };
+            if !text.trim().is_empty() {
+                if !contents.is_empty() {
+                    contents.push('\n');
+                }
+                contents.push_str(text.trim_end());
+            }
+        }
+    }
+
+    /// Returns the URLs found in the text and HTML parts of the message and its
+    /// nested messages, in order of appearance and without duplicates. Bare
+    /// URLs are taken from text parts and `href` or `src` attributes from HTML
+    /// parts, `cid:` references are returned by [`Message::cid_references`].
+    pub fn urls(&self) -> Vec<Cow<'_, str>> {
+        let mut urls = Vec::new();
+        self.collect_urls(&mut urls, true);
+        urls.retain(|url| !is_cid_url(url));
+        urls
+    }
+
+    /// Returns the `cid:` references found in the HTML parts of the message
+    /// along with the part they point to, if it exists.
+    pub fn cid_references(&self) -> Vec<(Cow<'_, str>, Option<&MessagePart<'x>>)> {
+        let mut urls = Vec::new();
+        self.collect_urls(&mut urls, false);
+        urls.into_iter()
+            .filter(|url| is_cid_url(url))
+            .map(|url| {
+                let (_, cid) = decode_hex(url[4..].as_bytes());
+                let part = self.part_by_cid(&String::from_utf8_lossy(&cid));
+                (url, part)
+            })
+            .collect()
+    }
+
+    fn collect_urls<'y>(&'y self, urls: &mut Vec<Cow<'y, str>>, nested: bool) {
+        for part in &self.parts {
+            match &part.body {
+                PartType::Text(text) => match part.content_type().filter(|ct| {
+                    ct.attribute("format")
+                        .is_some_and(|f| f.eq_ignore_ascii_case("flowed"))
+                }) {
+                    Some(ct) => {
+                        // URLs may be split across soft line breaks
+                        let delsp = ct
+                            .attribute("delsp")
+                            .is_some_and(|d| d.eq_ignore_ascii_case("yes"));
+                        let text = decode_flowed(text, delsp);
+                        let mut flowed_urls = Vec::new();
+                        text_urls(&text, &mut flowed_urls);
+                        for url in flowed_urls {
+                            let url = Cow::Owned(url.into_owned());
+                            if !urls.contains(&url) {
+                                urls.push(url);
+                            }
+                        }
+                    }
+                    None => text_urls(text, urls),
+                },
+                PartType::Html(html) => html_urls(html, urls),
+                PartType::Message(message) if nested => message.collect_urls(urls, nested),
+                _ => (),
+            }
+        }
+    }
+
     /// Returns a message part by position
-    pub fn part(&self, pos: usize) -> Option<&MessagePart> {
+    pub fn part(&self, pos: usize) -> Option<&MessagePart<'_>> {
         self.parts.get(pos)
     }
 
+    /// Returns `true` if any part of this message, including the parts of
+    /// nested messages, is flagged with `is_encoding_problem`
+    pub fn has_encoding_problems(&self) -> bool {
+        self.problem_parts().next().is_some()
+    }
+
+    /// Returns the positions of the parts flagged with `is_encoding_problem`,
+    /// along with those of the nested messages containing such parts
+    pub fn problem_parts(&self) -> impl Iterator<Item = MessagePartId> + '_ {
+        self.parts
+            .iter()
+            .enumerate()
+            .filter(|(_, part)| {
+                part.is_encoding_problem
+                    || matches!(&part.body, PartType::Message(message) if message.has_encoding_problems())
+            })
+            .map(|(pos, _)| pos)
+    }
+
+    /// Returns all the messages nested in this message, at any depth, in the
+    /// order they appear in the message. Nested messages without any parts,
+    /// such as truncated `message/rfc822` parts, are skipped.
+    pub fn nested_messages(&self) -> impl Iterator<Item = &Message<'x>> + '_ {
+        let mut stack = vec![self.parts.iter()];
+        core::iter::from_fn(move || loop {
+            match stack.last_mut()?.next() {
+                Some(MessagePart {
+                    body: PartType::Message(message),
+                    ..
+                }) if !message.parts.is_empty() => {
+                    stack.push(message.parts.iter());
+                    return Some(message);
+                }
+                Some(_) => (),
+                None => {
+                    stack.pop();
+                }
+            }
+        })
+    }
+
+    /// Returns the part with the given Content-ID, which may be enclosed in angle brackets
+    pub fn part_by_cid(&self, cid: &str) -> Option<&MessagePart<'x>> {
+        let cid = cid
+            .strip_prefix('<')
+            .and_then(|cid| cid.strip_suffix('>'))
+            .unwrap_or(cid);
+        self.content_ids()
+            .find_map(|(part_cid, part)| (part_cid == cid).then_some(part))
+    }
+
+    /// Returns an iterator over the parts that have a Content-ID, along with their Content-ID
+    pub fn content_ids(&self) -> impl Iterator<Item = (&str, &MessagePart<'x>)> {
+        self.parts
+            .iter()
+            .filter_map(|part| Some((part.content_id()?, part)))
+    }
+
     /// Returns an inline HTML body part by position
-    pub fn html_part(&self, pos: usize) -> Option<&MessagePart> {
+    pub fn html_part(&self, pos: usize) -> Option<&MessagePart<'_>> {
         self.parts.get(*self.html_body.get(pos)?)
     }
 
     /// Returns an inline text body part by position
-    pub fn text_part(&self, pos: usize) -> Option<&MessagePart> {
+    pub fn text_part(&self, pos: usize) -> Option<&MessagePart<'_>> {
         self.parts.get(*self.text_body.get(pos)?)
     }
 
-    /// Returns an attacment by position
+    /// Returns an attacment by position. Unlike [`Message::attachments`], this
+    /// also counts the inline parts that are not part of the text or HTML bodies,
+    /// so positions cover both [`Message::attachments`] and [`Message::inline_parts`].
     pub fn attachment(&self, pos: usize) -> Option<&MessagePart<'x>> {
         self.parts.get(*self.attachments.get(pos)?)
     }
@@ -443,7 +1062,9 @@ impl<'x> Message<'x> {
         self.html_body.len()
     }
 
-    /// Returns the number of attachments
+    /// Returns the number of attachments, including the inline parts that are
+    /// not part of the text or HTML bodies. This is the combined length of
+    /// [`Message::attachments`] and [`Message::inline_parts`].
     pub fn attachment_count(&self) -> usize {
         self.attachments.len()
     }
@@ -458,11 +1079,75 @@ impl<'x> Message<'x> {
         BodyPartIterator::new(self, &self.html_body)
     }
 
-    /// Returns an Interator over the attachments
+    /// Returns an Interator over the attachments.
+    ///
+    /// Only the parts for which [`MessagePart::is_attachment`] returns `true`
+    /// are returned, parts such as inline images are available from
+    /// [`Message::inline_parts`] instead.
     pub fn attachments(&'x self) -> AttachmentIterator<'x> {
         AttachmentIterator::new(self)
     }
 
+    /// Returns an Interator over the parts that are not part of the text
+    /// or HTML bodies but are meant to be displayed inline, such as images
+    /// referenced from an HTML body.
+    pub fn inline_parts(&self) -> impl Iterator<Item = &MessagePart<'x>> {
+        self.attachments
+            .iter()
+            .filter_map(|pos| self.parts.get(*pos))
+            .filter(|part| part.is_inline())
+    }
+
+    /// Returns an Iterator over the attachments of the message and of its
+    /// nested messages that match the predicate. Only the parts for which
+    /// [`MessagePart::is_attachment`] returns `true` are considered.
+    pub fn attachments_filter<'y>(
+        &'y self,
+        pred: impl Fn(&MessagePart<'x>) -> bool + 'y,
+    ) -> impl Iterator<Item = &'y MessagePart<'x>> + 'y {
+        core::iter::once(self)
+            .chain(self.nested_messages())
+            .flat_map(|message| {
+                message
+                    .attachments
+                    .iter()
+                    .filter_map(|pos| message.parts.get(*pos))
+            })
+            .filter(move |part| part.is_attachment() && pred(part))
+    }
+
+    /// Returns an Iterator over the attachments, including those of nested
+    /// messages, with an `image/*` content type.
+    pub fn image_attachments(&self) -> impl Iterator<Item = &MessagePart<'x>> {
+        self.attachments_filter(|part| {
+            part.content_type()
+                .is_some_and(|ct| ct.ctype().eq_ignore_ascii_case("image"))
+        })
+    }
+
+    /// Returns an Iterator over the attachments, including those of nested
+    /// messages, whose file name has the given extension. The extension is
+    /// compared case-insensitively and may include the leading dot.
+    pub fn attachments_with_extension<'y>(
+        &'y self,
+        ext: &'y str,
+    ) -> impl Iterator<Item = &'y MessagePart<'x>> + 'y {
+        let ext = ext.strip_prefix('.').unwrap_or(ext);
+        self.attachments_filter(move |part| {
+            part.attachment_name()
+                .and_then(|name| name.rsplit_once('.'))
+                .is_some_and(|(_, part_ext)| part_ext.eq_ignore_ascii_case(ext))
+        })
+    }
+
+    /// Returns an Iterator over the `text/calendar` parts of the message,
+    /// such as meeting invitations.
+    pub fn calendar_parts(&self) -> impl Iterator<Item = &MessagePart<'x>> {
+        self.parts
+            .iter()
+            .filter(|part| part.is_content_type("text", "calendar"))
+    }
+
     /// Returns an owned version of the message
     pub fn into_owned(self) -> Message<'static> {
         Message {
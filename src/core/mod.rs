@@ -11,6 +11,14 @@
 
 pub mod address;
 pub mod body;
+pub mod buffers;
 pub mod builder;
+#[cfg(feature = "content_md5")]
+pub mod content_md5;
+pub mod delivery_status;
 pub mod header;
+pub mod imap;
 pub mod message;
+pub mod partial;
+#[cfg(feature = "std")]
+pub mod writer;
@@ -0,0 +1,153 @@
+/*
+ * Copyright Stalwart Labs Ltd. See the COPYING
+ * file at the top-level directory of this distribution.
+ *
+ * Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+ * https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+ * <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+ * option. This file may not be copied, modified, or distributed
+ * except according to those terms.
+ */
+
+use alloc::vec::Vec;
+
+use crate::{Header, HeaderName, Message, MessageParser, MimeHeaders};
+
+impl<'x> Message<'x> {
+    /// Reassembles the messages that were split into `message/partial` fragments
+    /// as described in RFC2046, section 5.2.2, parsing them with `parser`.
+    ///
+    /// Fragments are grouped by their `id` parameter and other messages are
+    /// ignored. A message is returned for each group that is complete, groups
+    /// with a missing or duplicated fragment number, or whose `total` parameter
+    /// is missing or does not match the number of fragments, are skipped.
+    pub fn reassemble_partials(
+        parser: &MessageParser,
+        parts: &[Message<'_>],
+    ) -> Vec<Message<'static>> {
+        let mut groups: Vec<Fragments<'_, '_>> = Vec::new();
+
+        for part in parts {
+            let content_type = match part.content_type() {
+                Some(ct)
+                    if ct.ctype().eq_ignore_ascii_case("message")
+                        && ct
+                            .subtype()
+                            .is_some_and(|st| st.eq_ignore_ascii_case("partial")) =>
+                {
+                    ct
+                }
+                _ => continue,
+            };
+            let (Some(part_id), Some(number)) = (
+                content_type.attribute("id"),
+                content_type
+                    .attribute("number")
+                    .and_then(|number| number.trim().parse::<usize>().ok())
+                    .filter(|&number| number > 0),
+            ) else {
+                continue;
+            };
+            let group = match groups.iter_mut().position(|group| group.id == part_id) {
+                Some(pos) => &mut groups[pos],
+                None => {
+                    groups.push(Fragments {
+                        id: part_id,
+                        total: None,
+                        is_invalid: false,
+                        fragments: Vec::new(),
+                    });
+                    groups.last_mut().unwrap()
+                }
+            };
+            if let Some(part_total) = content_type.attribute("total") {
+                match part_total.trim().parse::<usize>() {
+                    Ok(part_total) if *group.total.get_or_insert(part_total) == part_total => (),
+                    _ => group.is_invalid = true,
+                }
+            }
+            group.fragments.push((number, part));
+        }
+
+        groups
+            .into_iter()
+            .filter_map(|group| group.reassemble(parser))
+            .collect()
+    }
+}
+
+/// The fragments of a partial message sharing the same `id` parameter
+struct Fragments<'y, 'x> {
+    id: &'y str,
+    total: Option<usize>,
+    is_invalid: bool,
+    fragments: Vec<(usize, &'y Message<'x>)>,
+}
+
+impl Fragments<'_, '_> {
+    fn reassemble(mut self, parser: &MessageParser) -> Option<Message<'static>> {
+        let total = self.total.filter(|_| !self.is_invalid)?;
+        let fragments = &mut self.fragments;
+        fragments.sort_unstable_by_key(|(number, _)| *number);
+        if fragments.len() != total
+            || fragments
+                .iter()
+                .enumerate()
+                .any(|(pos, (number, _))| *number != pos + 1)
+        {
+            return None;
+        }
+
+        // Concatenate the fragments to obtain the enclosed message
+        let mut enclosed = Vec::with_capacity(
+            fragments
+                .iter()
+                .map(|(_, part)| part.raw_body().len())
+                .sum(),
+        );
+        for (_, part) in fragments.iter() {
+            enclosed.extend_from_slice(part.raw_body());
+        }
+        let inner = parser.parse_headers(&enclosed)?;
+        let outer = fragments[0].1;
+
+        // The headers of the first enclosing message are copied except for those
+        // that describe the enclosed content, which are taken from the enclosed message.
+        let mut raw_message = Vec::with_capacity(enclosed.len() + outer.raw_message().len());
+        for header in outer.headers() {
+            if !is_enclosed_header(header) {
+                raw_message
+                    .extend_from_slice(&outer.raw_message[header.offset_field..header.offset_end]);
+            }
+        }
+        for header in inner.headers() {
+            if is_enclosed_header(header) {
+                raw_message
+                    .extend_from_slice(&inner.raw_message[header.offset_field..header.offset_end]);
+            }
+        }
+        raw_message.extend_from_slice(
+            &enclosed[inner
+                .headers()
+                .last()
+                .map_or(inner.parts[0].offset_header, |header| header.offset_end)..],
+        );
+
+        parser
+            .parse(&raw_message)
+            .map(|message| message.into_owned())
+    }
+}
+
+fn is_enclosed_header(header: &Header<'_>) -> bool {
+    match &header.name {
+        HeaderName::Subject | HeaderName::MessageId | HeaderName::MimeVersion => true,
+        name => {
+            let name = name.as_str();
+            name.eq_ignore_ascii_case("Encrypted")
+                || name
+                    .get(..8)
+                    .is_some_and(|prefix| prefix.eq_ignore_ascii_case("Content-"))
+        }
+    }
+}
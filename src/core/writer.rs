@@ -0,0 +1,393 @@
+/*
+ * Copyright Stalwart Labs Ltd. See the COPYING
+ * file at the top-level directory of this distribution.
+ *
+ * Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+ * https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+ * <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+ * option. This file may not be copied, modified, or distributed
+ * except according to those terms.
+ */
+
+use std::io::{self, Write};
+
+use alloc::{borrow::Cow, string::String};
+
+use crate::{
+    core::address::write_encoded_words, decoders::base64::base64_encode, Address, ContentType,
+    Encoding, Header, HeaderName, HeaderValue, Message, PartType,
+};
+
+impl Message<'_> {
+    /// Writes the message as RFC5322 bytes, which can be parsed back into an
+    /// equivalent message.
+    ///
+    /// Headers are serialized from their parsed values using CRLF line endings,
+    /// with non-ASCII text and names encoded as RFC2047 encoded-words, except for
+    /// `Received` headers which are copied from the original message. Multiparts
+    /// are written using the boundary of their `Content-Type` header.
+    ///
+    /// Bodies that are unchanged from the original message, such as those parsed
+    /// with [`MessageParser::with_raw_bodies`](crate::MessageParser::with_raw_bodies),
+    /// are copied verbatim. Other bodies are encoded using the declared
+    /// `Content-Transfer-Encoding`, in which case text parts are written as UTF-8
+    /// and their `charset` and `format` parameters are updated accordingly.
+    pub fn write_to<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        self.write_part(w, 0)
+    }
+
+    fn write_part(&self, w: &mut impl Write, part_id: usize) -> io::Result<()> {
+        let part = self
+            .parts
+            .get(part_id)
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "message part not found"))?;
+        let raw_body = self.raw_message.get(part.offset_body..part.offset_end);
+        let is_verbatim = match &part.body {
+            PartType::Text(text) | PartType::Html(text) => raw_body == Some(text.as_bytes()),
+            PartType::Binary(bytes) | PartType::InlineBinary(bytes) => {
+                raw_body == Some(bytes.as_ref())
+            }
+            PartType::Message(_) | PartType::Multipart(_) => true,
+        };
+        let is_text = matches!(part.body, PartType::Text(_) | PartType::Html(_));
+
+        for header in &part.headers {
+            self.write_header(w, header, is_text && !is_verbatim)?;
+        }
+        w.write_all(b"\r\n")?;
+
+        match &part.body {
+            PartType::Text(text) | PartType::Html(text) if is_verbatim => {
+                w.write_all(text.as_bytes())
+            }
+            PartType::Binary(bytes) | PartType::InlineBinary(bytes) if is_verbatim => {
+                w.write_all(bytes)
+            }
+            PartType::Text(text) | PartType::Html(text) => {
+                write_body(w, text.as_bytes(), part.encoding, true)
+            }
+            PartType::Binary(bytes) | PartType::InlineBinary(bytes) => {
+                write_body(w, bytes, part.encoding, false)
+            }
+            PartType::Message(message) if part.encoding == Encoding::None => message.write_to(w),
+            PartType::Message(message) => {
+                let mut bytes = Vec::with_capacity(message.raw_message.len());
+                message.write_to(&mut bytes)?;
+                write_body(w, &bytes, part.encoding, false)
+            }
+            PartType::Multipart(part_ids) => {
+                let boundary = part.boundary().ok_or_else(|| {
+                    io::Error::new(io::ErrorKind::InvalidInput, "multipart without boundary")
+                })?;
+                for &part_id in part_ids {
+                    write!(w, "--{boundary}\r\n")?;
+                    self.write_part(w, part_id)?;
+                    w.write_all(b"\r\n")?;
+                }
+                write!(w, "--{boundary}--\r\n")
+            }
+        }
+    }
+
+    fn write_header(
+        &self,
+        w: &mut impl Write,
+        header: &Header<'_>,
+        is_reencoded_text: bool,
+    ) -> io::Result<()> {
+        let value: Cow<'_, str> = match (&header.name, &header.value) {
+            (_, HeaderValue::Received(_)) => {
+                // Received headers are copied from the original message
+                match header.raw.as_deref().or_else(|| {
+                    self.raw_message
+                        .get(header.offset_start..header.offset_end)
+                        .filter(|raw| !raw.is_empty())
+                }) {
+                    Some(raw) => match String::from_utf8_lossy(raw) {
+                        Cow::Borrowed(raw) => raw.trim().into(),
+                        Cow::Owned(raw) => raw.trim().to_string().into(),
+                    },
+                    None => return Ok(()),
+                }
+            }
+            (
+                HeaderName::MessageId
+                | HeaderName::References
+                | HeaderName::InReplyTo
+                | HeaderName::ReturnPath
+                | HeaderName::ContentId
+                | HeaderName::ResentMessageId,
+                HeaderValue::Text(_) | HeaderValue::TextList(_),
+            ) => {
+                let mut value = String::new();
+                for id in header.value.as_text_list().unwrap_or_default() {
+                    if !value.is_empty() {
+                        value.push(' ');
+                    }
+                    value.push('<');
+                    value.push_str(id);
+                    value.push('>');
+                }
+                value.into()
+            }
+            (
+                HeaderName::ListArchive
+                | HeaderName::ListHelp
+                | HeaderName::ListId
+                | HeaderName::ListOwner
+                | HeaderName::ListPost
+                | HeaderName::ListSubscribe
+                | HeaderName::ListUnsubscribe,
+                HeaderValue::Address(Address::List(list)),
+            ) => {
+                // List headers enclose their URLs and identifiers in angle brackets
+                let mut value = String::new();
+                for addr in list {
+                    if !value.is_empty() {
+                        value.push_str(", ");
+                    }
+                    if addr.name.as_deref().is_some_and(|name| !name.is_empty()) {
+                        value.push_str(&addr.to_string());
+                    } else {
+                        value.push('<');
+                        value.push_str(addr.address.as_deref().unwrap_or_default());
+                        value.push('>');
+                    }
+                }
+                value.into()
+            }
+            (_, HeaderValue::Address(address)) => address.to_header_value().into(),
+            (_, HeaderValue::Text(text)) => encode_text(text),
+            (_, HeaderValue::TextList(list)) => {
+                let mut value = String::new();
+                for item in list {
+                    if !value.is_empty() {
+                        value.push_str(", ");
+                    }
+                    value.push_str(&encode_text(item));
+                }
+                value.into()
+            }
+            (_, HeaderValue::DateTime(date)) => date.to_rfc2822().into(),
+            (_, HeaderValue::ContentType(ct)) => write_content_type(
+                ct,
+                is_reencoded_text && header.name == HeaderName::ContentType,
+            )
+            .into(),
+            (_, HeaderValue::Empty) => "".into(),
+        };
+
+        w.write_all(header.name.as_str().as_bytes())?;
+        w.write_all(b":")?;
+        if !value.is_empty() {
+            w.write_all(b" ")?;
+            write_crlf(w, value.as_bytes())?;
+        }
+        w.write_all(b"\r\n")
+    }
+}
+
+/// Encodes non-ASCII text as RFC2047 encoded-words, one per line.
+fn encode_text(text: &str) -> Cow<'_, str> {
+    if text.is_ascii() {
+        text.into()
+    } else {
+        let mut encoded = String::with_capacity(text.len() * 2);
+        let unfolded = text.replace(['\r', '\n'], "");
+        let _ = write_encoded_words(&mut encoded, &unfolded);
+        encoded.replace(" =?", "\r\n =?").into()
+    }
+}
+
+fn write_content_type(ct: &ContentType<'_>, is_reencoded_text: bool) -> String {
+    let mut value = String::from(ct.ctype());
+    if let Some(subtype) = ct.subtype() {
+        value.push('/');
+        value.push_str(subtype);
+    }
+
+    for (name, attr_value) in ct.attributes().unwrap_or_default() {
+        let attr_value = if !is_reencoded_text {
+            attr_value.as_ref()
+        } else if name.eq_ignore_ascii_case("charset") {
+            // The text is written as UTF-8 and without format=flowed line breaks
+            "utf-8"
+        } else if name.eq_ignore_ascii_case("format") || name.eq_ignore_ascii_case("delsp") {
+            continue;
+        } else {
+            attr_value.as_ref()
+        };
+
+        value.push_str("; ");
+        value.push_str(name);
+        if !attr_value.is_ascii() {
+            // RFC2231 extended parameter value
+            value.push_str("*=utf-8''");
+            for &ch in attr_value.as_bytes() {
+                if ch.is_ascii_alphanumeric() || b"!#$&+-.^_`|~".contains(&ch) {
+                    value.push(ch as char);
+                } else {
+                    value.push_str(&format!("%{ch:02X}"));
+                }
+            }
+        } else if !attr_value.is_empty()
+            && attr_value
+                .bytes()
+                .all(|ch| ch.is_ascii_graphic() && !b"()<>@,;:\\\"/[]?=".contains(&ch))
+        {
+            value.push('=');
+            value.push_str(attr_value);
+        } else {
+            value.push_str("=\"");
+            for ch in attr_value.chars() {
+                match ch {
+                    '"' | '\\' => {
+                        value.push('\\');
+                        value.push(ch);
+                    }
+                    '\r' | '\n' => (),
+                    _ => value.push(ch),
+                }
+            }
+            value.push('"');
+        }
+    }
+
+    value
+}
+
+fn write_body(
+    w: &mut impl Write,
+    bytes: &[u8],
+    encoding: Encoding,
+    is_text: bool,
+) -> io::Result<()> {
+    match encoding {
+        Encoding::Base64 => write_base64(w, bytes),
+        Encoding::QuotedPrintable => write_quoted_printable(w, bytes, is_text),
+        Encoding::None if is_text => write_crlf(w, bytes),
+        Encoding::None => w.write_all(bytes),
+    }
+}
+
+/// Writes the bytes converting bare LF line endings to CRLF.
+fn write_crlf(w: &mut impl Write, bytes: &[u8]) -> io::Result<()> {
+    let mut last_pos = 0;
+    for (pos, &ch) in bytes.iter().enumerate() {
+        if ch == b'\n' && (pos == 0 || bytes[pos - 1] != b'\r') {
+            w.write_all(&bytes[last_pos..pos])?;
+            w.write_all(b"\r")?;
+            last_pos = pos;
+        }
+    }
+    w.write_all(&bytes[last_pos..])
+}
+
+/// Writes the bytes as base64 in lines of 76 characters.
+fn write_base64(w: &mut impl Write, bytes: &[u8]) -> io::Result<()> {
+    for (line_num, line) in bytes.chunks(57).enumerate() {
+        if line_num > 0 {
+            w.write_all(b"\r\n")?;
+        }
+        let mut buf = [0u8; 76];
+        let mut len = 0;
+        for ch in base64_encode(line) {
+            buf[len] = ch;
+            len += 1;
+        }
+        w.write_all(&buf[..len])?;
+    }
+    Ok(())
+}
+
+/// Writes the bytes as quoted-printable with soft line breaks before 76 characters.
+///
+/// Line breaks of text parts are kept as CRLF line breaks, while those of
+/// binary parts are encoded.
+fn write_quoted_printable(w: &mut impl Write, bytes: &[u8], is_text: bool) -> io::Result<()> {
+    const HEX: &[u8; 16] = b"0123456789ABCDEF";
+
+    let mut line_len = 0;
+    for (pos, &ch) in bytes.iter().enumerate() {
+        let next_ch = bytes.get(pos + 1).copied();
+        if is_text && ch == b'\n' {
+            w.write_all(b"\r\n")?;
+            line_len = 0;
+            continue;
+        } else if is_text && ch == b'\r' && next_ch == Some(b'\n') {
+            continue;
+        }
+
+        let is_line_end = next_ch.is_none() || (is_text && matches!(next_ch, Some(b'\r' | b'\n')));
+        let is_literal =
+            matches!(ch, b'!'..=b'<' | b'>'..=b'~') || (matches!(ch, b' ' | b'\t') && !is_line_end);
+        let len = if is_literal { 1 } else { 3 };
+        if line_len + len > 75 {
+            w.write_all(b"=\r\n")?;
+            line_len = 0;
+        }
+        if is_literal {
+            w.write_all(&[ch])?;
+        } else {
+            w.write_all(&[b'=', HEX[(ch >> 4) as usize], HEX[(ch & 0x0f) as usize]])?;
+        }
+        line_len += len;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{decoders::quoted_printable::quoted_printable_decode, MessageParser};
+
+    use super::{write_base64, write_quoted_printable};
+
+    #[test]
+    fn encode_quoted_printable() {
+        for (input, is_text, expected) in [
+            ("Café = 10€\n", true, "Caf=C3=A9 =3D 10=E2=82=AC\r\n"),
+            ("trailing space \r\nand tab\t", true, "trailing space=20\r\nand tab=09"),
+            ("line\nbreak", false, "line=0Abreak"),
+            (
+                concat!(
+                    "A long line that needs to be wrapped because it is longer than ",
+                    "seventy-six characters"
+                ),
+                true,
+                concat!(
+                    "A long line that needs to be wrapped because it is longer than seventy-six =\r\n",
+                    "characters"
+                ),
+            ),
+        ] {
+            let mut output = Vec::new();
+            write_quoted_printable(&mut output, input.as_bytes(), is_text).unwrap();
+            assert_eq!(
+                core::str::from_utf8(&output).unwrap(),
+                expected,
+                "failed for {input:?}"
+            );
+            if is_text {
+                assert_eq!(
+                    quoted_printable_decode(&output).unwrap(),
+                    input.replace("\r\n", "\n").replace('\n', "\r\n").as_bytes(),
+                    "failed for {input:?}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn encode_base64() {
+        let input = (0..=255u8).collect::<Vec<_>>();
+        let mut output = Vec::new();
+        write_base64(&mut output, &input).unwrap();
+        assert!(output.split(|&ch| ch == b'\n').all(|line| line.len() <= 77));
+
+        let mut raw = b"Content-Type: application/octet-stream\r\n".to_vec();
+        raw.extend_from_slice(b"Content-Transfer-Encoding: base64\r\n\r\n");
+        raw.extend_from_slice(&output);
+        let message = MessageParser::default().parse(&raw).unwrap();
+        assert_eq!(message.parts[0].contents(), &input[..]);
+    }
+}
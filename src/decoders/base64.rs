@@ -9,15 +9,39 @@
  * except according to those terms.
  */
 
-use std::borrow::Cow;
+use alloc::{borrow::Cow, vec::Vec};
 
 use crate::parsers::MessageStream;
 
+#[cfg(feature = "simd")]
+use super::base64_simd::base64_decode_blocks;
+
 #[inline(always)]
 pub fn base64_decode(bytes: &[u8]) -> Option<Vec<u8>> {
     base64_decode_stream(bytes.iter(), bytes.len(), u8::MAX)
 }
 
+/// Encodes the bytes as padded base64, returning one character at a time.
+pub(crate) fn base64_encode(bytes: &[u8]) -> impl Iterator<Item = u8> + '_ {
+    const CHARS: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+    bytes.chunks(3).flat_map(|chunk| {
+        let n = chunk.iter().enumerate().fold(0usize, |n, (pos, &ch)| {
+            n | ((ch as usize) << (16 - pos * 8))
+        });
+        [18, 12, 6, 0]
+            .into_iter()
+            .enumerate()
+            .map(move |(pos, shift)| {
+                if pos <= chunk.len() {
+                    CHARS[(n >> shift) & 0x3f]
+                } else {
+                    b'='
+                }
+            })
+    })
+}
+
 pub fn base64_decode_stream<'x>(
     stream: impl Iterator<Item = &'x u8>,
     stream_len: usize,
@@ -127,6 +151,20 @@ impl<'x> MessageStream<'x> {
                         }
                         #[cfg(not(feature = "ludicrous_mode"))]
                         buf.extend_from_slice(&chunk.to_le_bytes()[0..3]);
+
+                        // Decode the following unbroken run of base64 characters in blocks
+                        #[cfg(feature = "simd")]
+                        {
+                            let offset = self.offset();
+                            let run = self.bytes(offset..offset + self.remaining());
+                            let consumed = base64_decode_blocks(run, &mut buf);
+                            if consumed > 0 {
+                                self.skip_bytes(consumed);
+                                before_last_ch = run[consumed - 2];
+                                last_ch = run[consumed - 1];
+                                continue;
+                            }
+                        }
                     }
                 }
             } else {
@@ -166,25 +204,25 @@ impl<'x> MessageStream<'x> {
                     b' ' | b'\t' | b'\r' => (),
                     b'-' => {
                         if last_ch == b'-' {
-                            return if !boundary.is_empty() && self.try_skip(boundary) {
+                            if !boundary.is_empty() && self.try_skip(boundary) {
                                 buf.shrink_to_fit();
-                                (
+                                return (
                                     if before_last_ch == b'\n' {
                                         end_pos
                                     } else {
                                         self.offset() - boundary.len() - 2
                                     },
                                     buf.into(),
-                                )
-                            } else {
-                                self.restore();
-                                (usize::MAX, b""[..].into())
-                            };
+                                );
+                            }
+                            self.has_decode_errors = true;
+                        } else if self.peek() != Some(&&b'-') {
+                            self.has_decode_errors = true;
                         }
                     }
                     _ => {
-                        self.restore();
-                        return (usize::MAX, b""[..].into());
+                        // Skip bytes outside the base64 alphabet
+                        self.has_decode_errors = true;
                     }
                 }
             }
@@ -465,6 +503,25 @@ pub static BASE64_MAP: &[&[u32]] = &[
 mod tests {
     use crate::parsers::MessageStream;
 
+    use super::base64_encode;
+
+    #[test]
+    fn encode_base64() {
+        for (input, expected) in [
+            ("", ""),
+            ("A", "QQ=="),
+            ("Ye", "WWU="),
+            ("Test", "VGVzdA=="),
+            ("áéíóú", "w6HDqcOtw7PDug=="),
+        ] {
+            assert_eq!(
+                base64_encode(input.as_bytes()).collect::<Vec<_>>(),
+                expected.as_bytes(),
+                "failed for {input:?}"
+            );
+        }
+    }
+
     #[test]
     fn decode_base64() {
         for (encoded_str, expected_result) in [
@@ -532,6 +589,40 @@ mod tests {
         }
     }
 
+    #[test]
+    fn decode_base64_mime_garbage() {
+        for (encoded_str, expected_result, has_errors) in [
+            (
+                "w6HD\r\nqcOt\r\n w7PD\tug==\r\n--boundary\n",
+                "áéíóú",
+                false,
+            ),
+            (
+                "w6HD\r\nqc\u{a0}Ot\r\n w7PD\tug==\r\n--boundary\n",
+                "áéíóú",
+                true,
+            ),
+            ("w6HD\r\nqcOt\r\n w7“PD”ug==\r\n--boundary\n", "áéíóú", true),
+            ("VGVz!dA==\r\n--boundary--\r\n", "Test", true),
+            ("VGVz-dA==\r\n--boundary--\r\n", "Test", true),
+            ("VGVz--dA==\r\n--boundary--\r\n", "Test", true),
+        ] {
+            let mut s = MessageStream::new(encoded_str.as_bytes());
+            let (offset, result) = s.decode_base64_mime(b"boundary");
+
+            assert_ne!(offset, usize::MAX, "Failed for {encoded_str:?}");
+            assert_eq!(
+                result,
+                expected_result.as_bytes(),
+                "Failed for {encoded_str:?}",
+            );
+            assert_eq!(
+                s.has_decode_errors, has_errors,
+                "Failed for {encoded_str:?}"
+            );
+        }
+    }
+
     #[test]
     fn decode_base64_word() {
         for (encoded_str, expected_result) in [
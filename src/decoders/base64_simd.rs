@@ -0,0 +1,149 @@
+/*
+ * Copyright Stalwart Labs Ltd. See the COPYING
+ * file at the top-level directory of this distribution.
+ *
+ * Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+ * https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+ * <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+ * option. This file may not be copied, modified, or distributed
+ * except according to those terms.
+ */
+
+use alloc::vec::Vec;
+
+/// Decodes the 16 byte blocks at the start of `bytes` that only contain
+/// characters of the base64 alphabet, appending 12 decoded bytes per block
+/// to `buf`. Returns the number of bytes consumed, which is zero when SIMD
+/// is not available on the target so that the scalar decoder handles them.
+#[inline(always)]
+pub fn base64_decode_blocks(bytes: &[u8], buf: &mut Vec<u8>) -> usize {
+    #[cfg(target_arch = "x86_64")]
+    if has_ssse3() {
+        // SAFETY: SSSE3 support was checked above
+        return unsafe { ssse3::base64_decode_blocks(bytes, buf) };
+    }
+
+    let _ = (bytes, buf);
+    0
+}
+
+#[cfg(target_arch = "x86_64")]
+#[inline(always)]
+fn has_ssse3() -> bool {
+    #[cfg(feature = "std")]
+    {
+        std::is_x86_feature_detected!("ssse3")
+    }
+    #[cfg(not(feature = "std"))]
+    {
+        cfg!(target_feature = "ssse3")
+    }
+}
+
+#[cfg(target_arch = "x86_64")]
+mod ssse3 {
+    use alloc::vec::Vec;
+    use core::arch::x86_64::*;
+
+    // Wojciech Muła's vectorized base64 decoding, see
+    // http://0x80.pl/notesen/2016-01-17-sse-base64-decoding.html
+    #[target_feature(enable = "ssse3")]
+    pub unsafe fn base64_decode_blocks(bytes: &[u8], buf: &mut Vec<u8>) -> usize {
+        // Every character outside the alphabet has a bit set in both tables
+        let lut_lo = _mm_setr_epi8(
+            0x15, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x13, 0x1a, 0x1b, 0x1b,
+            0x1b, 0x1a,
+        );
+        let lut_hi = _mm_setr_epi8(
+            0x10, 0x10, 0x01, 0x02, 0x04, 0x08, 0x04, 0x08, 0x10, 0x10, 0x10, 0x10, 0x10, 0x10,
+            0x10, 0x10,
+        );
+        // Offsets turning each character into its 6-bit value
+        let lut_roll = _mm_setr_epi8(0, 16, 19, 4, -65, -65, -71, -71, 0, 0, 0, 0, 0, 0, 0, 0);
+        let pack_shuffle = _mm_setr_epi8(2, 1, 0, 6, 5, 4, 10, 9, 8, 14, 13, 12, -1, -1, -1, -1);
+        let mask_0f = _mm_set1_epi8(0x0f);
+        let slash = _mm_set1_epi8(b'/' as i8);
+
+        let mut consumed = 0;
+        let mut decoded = [0u8; 16];
+
+        while let Some(block) = bytes.get(consumed..consumed + 16) {
+            let input = _mm_loadu_si128(block.as_ptr() as *const __m128i);
+            let hi_nibbles = _mm_and_si128(_mm_srli_epi32(input, 4), mask_0f);
+            let lo_nibbles = _mm_and_si128(input, mask_0f);
+            let invalid = _mm_and_si128(
+                _mm_shuffle_epi8(lut_lo, lo_nibbles),
+                _mm_shuffle_epi8(lut_hi, hi_nibbles),
+            );
+            if _mm_movemask_epi8(_mm_cmpeq_epi8(invalid, _mm_setzero_si128())) != 0xffff {
+                break;
+            }
+
+            let roll = _mm_shuffle_epi8(
+                lut_roll,
+                _mm_add_epi8(_mm_cmpeq_epi8(input, slash), hi_nibbles),
+            );
+            let values = _mm_add_epi8(input, roll);
+            let values = _mm_maddubs_epi16(values, _mm_set1_epi32(0x01400140));
+            let values = _mm_madd_epi16(values, _mm_set1_epi32(0x00011000));
+            _mm_storeu_si128(
+                decoded.as_mut_ptr() as *mut __m128i,
+                _mm_shuffle_epi8(values, pack_shuffle),
+            );
+            buf.extend_from_slice(&decoded[..12]);
+            consumed += 16;
+        }
+
+        consumed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::base64_decode_blocks;
+    use crate::decoders::base64::base64_decode;
+
+    #[test]
+    fn decode_base64_blocks() {
+        const ALPHABET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+        // Compare against the scalar decoder on pseudo-random blocks, each
+        // followed by a block with one character replaced by a random byte
+        let mut seed: u32 = 0x2545f491;
+        let mut next = || {
+            seed ^= seed << 13;
+            seed ^= seed >> 17;
+            seed ^= seed << 5;
+            seed
+        };
+
+        for _ in 0..10000 {
+            let mut input = (0..32)
+                .map(|_| ALPHABET[next() as usize % ALPHABET.len()])
+                .collect::<Vec<_>>();
+            let invalid = next() as u8;
+            input[16 + next() as usize % 16] = invalid;
+
+            let mut buf = Vec::new();
+            let consumed = base64_decode_blocks(&input, &mut buf);
+            if consumed == 0 {
+                // SIMD not supported on this target
+                return;
+            }
+            assert_eq!(
+                consumed,
+                if ALPHABET.contains(&invalid) { 32 } else { 16 },
+                "failed for {invalid:#04x}"
+            );
+            assert_eq!(buf, base64_decode(&input[..consumed]).unwrap());
+        }
+
+        let mut buf = Vec::new();
+        assert_eq!(base64_decode_blocks(b"QUJD", &mut buf), 0);
+        assert_eq!(
+            base64_decode_blocks(b"QUJDREVGR0hJSktMTU5P\n", &mut buf),
+            16
+        );
+        assert_eq!(buf, b"ABCDEFGHIJKL");
+    }
+}
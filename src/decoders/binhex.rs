@@ -0,0 +1,264 @@
+/*
+ * Copyright Stalwart Labs Ltd. See the COPYING
+ * file at the top-level directory of this distribution.
+ *
+ * Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+ * https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+ * <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+ * option. This file may not be copied, modified, or distributed
+ * except according to those terms.
+ */
+
+use alloc::vec::Vec;
+
+use crate::BinHexHeader;
+
+use super::charsets::single_byte::decoder_macintosh;
+
+const ALPHABET: &[u8; 64] = b"!\"#$%&'()*+,-012345689@ABCDEFGHIJKLMNPQRSTUVXYZ[`abcdefhijklmpqr";
+
+static HQX_MAP: [u8; 256] = {
+    let mut map = [u8::MAX; 256];
+    let mut i = 0;
+    while i < ALPHABET.len() {
+        map[ALPHABET[i] as usize] = i as u8;
+        i += 1;
+    }
+    map
+};
+
+const RLE_MARKER: u8 = 0x90;
+
+/// Decodes a BinHex 4.0 file, returning its header along with the contents
+/// of its data fork, or `None` when no complete BinHex stream is found or
+/// when any of its checksums does not match. The resource fork is discarded.
+pub fn binhex_decode(bytes: &[u8]) -> Option<(BinHexHeader, Vec<u8>)> {
+    let encoded = decode_6bit(find_stream(bytes)?)?;
+    let mut decoder = RleDecoder::new(&encoded);
+
+    // Name length, name, version, type, creator, flags and both fork lengths,
+    // validated before expanding the forks
+    let mut header = decoder.read(1)?;
+    let name_len = header[0] as usize;
+    header.extend(decoder.read(name_len + 1 + 4 + 4 + 2 + 4 + 4)?);
+    decoder.check_crc(&header)?;
+    let fields = &header[name_len + 2..];
+    let data_len = u32::from_be_bytes(fields[10..14].try_into().ok()?) as usize;
+    let rsrc_len = u32::from_be_bytes(fields[14..18].try_into().ok()?) as usize;
+
+    let data = decoder.read(data_len)?;
+    decoder.check_crc(&data)?;
+    let rsrc = decoder.read(rsrc_len)?;
+    decoder.check_crc(&rsrc)?;
+
+    // Runs expanding past the end of the resource fork
+    if decoder.next_byte().is_some() {
+        return None;
+    }
+
+    Some((
+        BinHexHeader {
+            filename: decoder_macintosh(&header[1..name_len + 1]),
+            file_type: fields[0..4].try_into().ok()?,
+            creator: fields[4..8].try_into().ok()?,
+            flags: u16::from_be_bytes(fields[8..10].try_into().ok()?),
+        },
+        data,
+    ))
+}
+
+/// Returns the characters between the colon starting a line and the next colon.
+fn find_stream(bytes: &[u8]) -> Option<&[u8]> {
+    let mut line_start = true;
+    let start = bytes.iter().position(|&ch| {
+        let is_start = line_start && ch == b':';
+        line_start = ch == b'\n';
+        is_start
+    })? + 1;
+    let len = bytes[start..].iter().position(|&ch| ch == b':')?;
+    Some(&bytes[start..start + len])
+}
+
+/// Decodes the 6-bit characters, ignoring line breaks and discarding the
+/// bits left over at the end of the stream.
+fn decode_6bit(encoded: &[u8]) -> Option<Vec<u8>> {
+    let mut buf = Vec::with_capacity(encoded.len() / 4 * 3);
+    let mut chunk: u32 = 0;
+    let mut bits = 0;
+
+    for &ch in encoded {
+        match HQX_MAP[ch as usize] {
+            u8::MAX if ch.is_ascii_whitespace() => (),
+            u8::MAX => return None,
+            value => {
+                chunk = (chunk << 6) | value as u32;
+                bits += 6;
+                if bits >= 8 {
+                    bits -= 8;
+                    buf.push((chunk >> bits) as u8);
+                }
+            }
+        }
+    }
+
+    Some(buf)
+}
+
+/// Expands `<byte> 0x90 <count>` runs, `0x90 0x00` being a literal `0x90`,
+/// as the bytes are read so that only the sections being read are expanded.
+struct RleDecoder<'x> {
+    bytes: core::slice::Iter<'x, u8>,
+    last: Option<u8>,
+    repeat: usize,
+}
+
+impl<'x> RleDecoder<'x> {
+    fn new(bytes: &'x [u8]) -> Self {
+        RleDecoder {
+            bytes: bytes.iter(),
+            last: None,
+            repeat: 0,
+        }
+    }
+
+    /// Returns the next decoded byte, or `None` at the end of the stream
+    /// or when a run is invalid.
+    fn next_byte(&mut self) -> Option<u8> {
+        loop {
+            if self.repeat > 0 {
+                self.repeat -= 1;
+                return self.last;
+            }
+            match *self.bytes.next()? {
+                RLE_MARKER => match *self.bytes.next()? {
+                    0 => {
+                        self.last = Some(RLE_MARKER);
+                        return self.last;
+                    }
+                    count => {
+                        self.last?;
+                        self.repeat = count as usize - 1;
+                    }
+                },
+                ch => {
+                    self.last = Some(ch);
+                    return self.last;
+                }
+            }
+        }
+    }
+
+    /// Returns the next `len` decoded bytes.
+    fn read(&mut self, len: usize) -> Option<Vec<u8>> {
+        let mut buf = Vec::new();
+        while buf.len() < len {
+            buf.push(self.next_byte()?);
+        }
+        Some(buf)
+    }
+
+    /// Reads the CRC following a section and checks that it matches.
+    fn check_crc(&mut self, section: &[u8]) -> Option<()> {
+        let crc = u16::from_be_bytes([self.next_byte()?, self.next_byte()?]);
+        (crc16(section) == crc).then_some(())
+    }
+}
+
+/// CRC-16/XMODEM, used by BinHex for each of its sections.
+fn crc16(bytes: &[u8]) -> u16 {
+    let mut crc: u16 = 0;
+    for &ch in bytes {
+        crc ^= (ch as u16) << 8;
+        for _ in 0..8 {
+            crc = if crc & 0x8000 != 0 {
+                (crc << 1) ^ 0x1021
+            } else {
+                crc << 1
+            };
+        }
+    }
+    crc
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::{string::String, vec::Vec};
+
+    use super::{binhex_decode, crc16, ALPHABET};
+    use crate::BinHexHeader;
+
+    #[test]
+    fn decode_binhex() {
+        let input = concat!(
+            "(This file must be converted with BinHex 4.0)\r\n",
+            "\r\n",
+            ":\"e*PB@3J6@8!9%9B9(4dH(3\"!*!%&`#3!`5AK%KPE'a[,#\"0B@-K$3#3#T!!Z\"Y5\r\n",
+            "8e*$MlF:\r\n"
+        );
+        let (header, data) = binhex_decode(input.as_bytes()).unwrap();
+        assert_eq!(
+            header,
+            BinHexHeader {
+                filename: "Read Me".into(),
+                file_type: *b"TEXT",
+                creator: *b"ttxt",
+                flags: 0x0100,
+            }
+        );
+        assert_eq!(data, b"Hello, Mac!\r\0\0\0\0\0\0\0\0\0\0\x90");
+
+        for input in [
+            // Missing final colon
+            ":\"e*PB@3J6@8!9%9B9(4dH(3\"!*!%&`#3!`5AK%KPE'a[,#\"0B@-K$3#3#T!!Z\"Y5\n8e*$MlF\n",
+            // Corrupted data fork
+            ":\"e*PB@3J6@8!9%9B9(4dH(3\"!*!%&`#3!`5AK%KPE'a[,#\"0C@-K$3#3#T!!Z\"Y5\n8e*$MlF:\n",
+            // Truncated stream
+            ":\"e*PB@3J6@8!9%9B9(4dH(3\"!*!%&`#3!`5AK%KPE'a[:\n",
+            // Characters outside the alphabet
+            ":\"e*PB@3J6@8!9%9B9(4dH(3\"!*!%&`#3!`5AK%KPE'a[,#\"0B@-K$3#3#T!!Z\"Y5\n8e*$MlF~:\n",
+            // Colon not at the start of a line
+            "Subject :\"e*PB@3J6@8!9%9B9(4dH(3\"!*!%&`#3!`5AK%KPE'a[,#\"0B@-K$3#3#T!!Z\"Y5\n8e*$MlF:\n",
+            "",
+        ] {
+            assert_eq!(binhex_decode(input.as_bytes()), None, "failed for {input:?}");
+        }
+    }
+
+    #[test]
+    fn decode_binhex_runs() {
+        let encode = |sections: &[&[u8]], trailer: &[u8]| {
+            let mut bytes = Vec::new();
+            for section in sections {
+                bytes.extend_from_slice(section);
+                bytes.extend_from_slice(&crc16(section).to_be_bytes());
+            }
+            bytes.extend_from_slice(trailer);
+            let mut encoded = String::from(":");
+            let (mut chunk, mut bits) = (0u32, 0);
+            for byte in bytes {
+                chunk = (chunk << 8) | byte as u32;
+                bits += 8;
+                while bits >= 6 {
+                    bits -= 6;
+                    encoded.push(ALPHABET[(chunk >> bits) as usize & 0x3f] as char);
+                }
+            }
+            if bits > 0 {
+                encoded.push(ALPHABET[(chunk << (6 - bits)) as usize & 0x3f] as char);
+            }
+            encoded.push(':');
+            encoded
+        };
+        let header = b"\x01a\0TEXTttxt\0\0\0\0\0\x04\0\0\0\0";
+        let data = b"zzzz";
+
+        // Stream ending right after the resource fork
+        let input = encode(&[header, data, b""], b"");
+        assert_eq!(binhex_decode(input.as_bytes()).unwrap().1, data);
+
+        // Expansion past the forks described by the header
+        let bomb = [b'z', 0x90, 0xff].repeat(1000);
+        let input = encode(&[header, data, b""], &bomb);
+        assert_eq!(binhex_decode(input.as_bytes()), None);
+    }
+}
@@ -0,0 +1,133 @@
+/*
+ * Copyright Stalwart Labs Ltd. See the COPYING
+ * file at the top-level directory of this distribution.
+ *
+ * Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+ * https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+ * <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+ * option. This file may not be copied, modified, or distributed
+ * except according to those terms.
+ */
+
+/// Guesses the charset of a text using a lightweight heuristic.
+///
+/// ISO-2022-JP escape sequences and valid UTF-8 are detected first, followed by
+/// Shift_JIS and EUC-JP when `full_encoding` is enabled. These are only chosen when
+/// double-byte characters make up at least a quarter of the text, and Shift_JIS also
+/// requires a trail byte outside of the ASCII range, so that Latin-1 text is not
+/// mistaken for Japanese. Any other text is assumed to be `windows-1252`, a superset
+/// of ISO-8859-1 that decodes every byte.
+pub fn detect_charset(bytes: &[u8]) -> &'static str {
+    #[cfg(feature = "full_encoding")]
+    if bytes
+        .windows(3)
+        .any(|w| matches!(w, [0x1b, b'$', b'@' | b'B'] | [0x1b, b'(', b'J']))
+    {
+        return "iso-2022-jp";
+    }
+
+    if core::str::from_utf8(bytes).is_ok() {
+        return "utf-8";
+    }
+
+    #[cfg(feature = "full_encoding")]
+    {
+        let min_chars = (bytes.len() + 7) / 8;
+        let sjis = score_shift_jis(bytes).filter(|&chars| chars >= min_chars);
+        let euc = score_euc_jp(bytes).filter(|&chars| chars >= min_chars);
+        match (sjis, euc) {
+            (Some(sjis), Some(euc)) if sjis > 0 || euc > 0 => {
+                return if euc >= sjis { "euc-jp" } else { "shift_jis" };
+            }
+            (Some(sjis), None) if sjis > 0 => return "shift_jis",
+            (None, Some(euc)) if euc > 0 => return "euc-jp",
+            _ => (),
+        }
+    }
+
+    "windows-1252"
+}
+
+/// Returns the number of double-byte characters if the text is valid Shift_JIS
+/// and at least one of them has a trail byte outside of the ASCII range.
+#[cfg(feature = "full_encoding")]
+fn score_shift_jis(bytes: &[u8]) -> Option<usize> {
+    let mut chars = 0;
+    let mut has_high_trail = false;
+    let mut iter = bytes.iter();
+    while let Some(&ch) = iter.next() {
+        match ch {
+            0x00..=0x7f | 0xa1..=0xdf => (),
+            0x81..=0x9f | 0xe0..=0xfc => {
+                match iter.next()? {
+                    0x40..=0x7e => (),
+                    0x80..=0xfc => has_high_trail = true,
+                    _ => return None,
+                }
+                chars += 1;
+            }
+            _ => return None,
+        }
+    }
+    has_high_trail.then_some(chars)
+}
+
+/// Returns the number of double-byte characters if the text is valid EUC-JP.
+#[cfg(feature = "full_encoding")]
+fn score_euc_jp(bytes: &[u8]) -> Option<usize> {
+    let mut chars = 0;
+    let mut iter = bytes.iter();
+    while let Some(&ch) = iter.next() {
+        match ch {
+            0x00..=0x7f => (),
+            0x8e => {
+                if !matches!(iter.next()?, 0xa1..=0xdf) {
+                    return None;
+                }
+            }
+            0x8f | 0xa1..=0xfe => {
+                if ch == 0x8f && !matches!(iter.next()?, 0xa1..=0xfe) {
+                    return None;
+                }
+                if !matches!(iter.next()?, 0xa1..=0xfe) {
+                    return None;
+                }
+                chars += 1;
+            }
+            _ => return None,
+        }
+    }
+    Some(chars)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::detect_charset;
+
+    #[test]
+    fn detect_charsets() {
+        for (bytes, expected) in [
+            (&b"Hello, world"[..], "utf-8"),
+            ("¡El ñandú comió ñoquis!".as_bytes(), "utf-8"),
+            (b"\xa1El \xf1and\xfa comi\xf3 \xf1oquis!", "windows-1252"),
+            (b"caf\xe9 cr\xe8me \x93quoted\x94", "windows-1252"),
+            (b"Merci de r\xe9pondre", "windows-1252"),
+            (b"Caf\xe9s", "windows-1252"),
+            (b"Viele Gr\xfc\xdfe aus M\xfcnchen", "windows-1252"),
+            #[cfg(feature = "full_encoding")]
+            (b"\x1b$B%O%m!<!&%o!<%k%I\x1b(B", "iso-2022-jp"),
+            #[cfg(feature = "full_encoding")]
+            (
+                b"\x83n\x83\x8D\x81[\x81E\x83\x8F\x81[\x83\x8B\x83h",
+                "shift_jis",
+            ),
+            #[cfg(feature = "full_encoding")]
+            (
+                b"\xa5\xcf\xa5\xed\xa1\xbc\xa1\xa6\xa5\xef\xa1\xbc\xa5\xeb\xa5\xc9",
+                "euc-jp",
+            ),
+        ] {
+            assert_eq!(detect_charset(bytes), expected, "failed for {bytes:?}");
+        }
+    }
+}
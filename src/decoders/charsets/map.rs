@@ -15,6 +15,7 @@ use super::{
     utf::{decoder_utf16, decoder_utf16_be, decoder_utf16_le, decoder_utf7, decoder_utf8},
     DecoderFnc,
 };
+use alloc::{string::String, string::ToString};
 
 pub fn charset_decoder(charset: &[u8]) -> Option<DecoderFnc> {
     if (2..=45).contains(&charset.len()) {
@@ -72,11 +73,91 @@ pub fn charset_decoder(charset: &[u8]) -> Option<DecoderFnc> {
                 });
             }
         }
+
+        return CH_ALIASES
+            .iter()
+            .find(|(alias, _)| l_charset[..charset.len()].eq(*alias))
+            .and_then(|(_, charset)| charset_decoder(charset));
     }
 
     None
 }
 
+// Vendor aliases missing from the perfect hashing table
+static CH_ALIASES: &[(&[u8], &[u8])] = &[
+    (b"cp1250", b"windows-1250"),
+    (b"cp1251", b"windows-1251"),
+    (b"cp1252", b"windows-1252"),
+    (b"cp1253", b"windows-1253"),
+    (b"cp1254", b"windows-1254"),
+    (b"cp1255", b"windows-1255"),
+    (b"cp1256", b"windows-1256"),
+    (b"cp1257", b"windows-1257"),
+    (b"cp1258", b"windows-1258"),
+    (b"x-cp1250", b"windows-1250"),
+    (b"x-cp1251", b"windows-1251"),
+    (b"x-cp1252", b"windows-1252"),
+    (b"x-cp1253", b"windows-1253"),
+    (b"x-cp1254", b"windows-1254"),
+    (b"x-cp1255", b"windows-1255"),
+    (b"x-cp1256", b"windows-1256"),
+    (b"x-cp1257", b"windows-1257"),
+    (b"x-cp1258", b"windows-1258"),
+    (b"x-gbk", b"gbk"),
+    (b"cn-big5", b"big5"),
+    (b"x-x-big5", b"big5"),
+    (b"big5-hkscs", b"big5"),
+    (b"cp949", b"euc-kr"),
+    (b"sjis", b"shift_jis"),
+    (b"x-sjis", b"shift_jis"),
+    (b"cp932", b"shift_jis"),
+    (b"ms932", b"shift_jis"),
+    (b"windows-31j", b"shift_jis"),
+];
+
+// Labels of UTF-8 and US-ASCII, which are decoded without a decoder
+static UTF8_ASCII_LABELS: &[&[u8]] = &[
+    b"utf-8",
+    b"utf8",
+    b"csutf8",
+    b"unicode-1-1-utf-8",
+    b"x-unicode20utf8",
+    b"us-ascii",
+    b"us_ascii",
+    b"ascii",
+    b"us",
+    b"csascii",
+    b"ansi_x3.4-1968",
+    b"ansi_x3.4-1986",
+    b"iso-ir-6",
+    b"iso646-us",
+    b"iso_646.irv:1991",
+    b"ibm367",
+    b"cp367",
+];
+
+/// Returns `true` if the charset is a label of UTF-8 or US-ASCII.
+pub(crate) fn is_utf8_or_ascii(charset: &[u8]) -> bool {
+    UTF8_ASCII_LABELS
+        .iter()
+        .any(|label| charset.eq_ignore_ascii_case(label))
+}
+
+/// Returns the decoder for a charset, or the default decoder when the charset
+/// is not supported. UTF-8 and US-ASCII are decoded without a decoder.
+pub(crate) fn charset_decoder_or(
+    charset: &[u8],
+    default: Option<DecoderFnc>,
+) -> Option<DecoderFnc> {
+    charset_decoder(charset).or_else(|| {
+        if is_utf8_or_ascii(charset) {
+            None
+        } else {
+            default
+        }
+    })
+}
+
 pub fn no_op(_bytes: &[u8]) -> String {
     "".to_string()
 }
@@ -1647,4 +1728,70 @@ mod tests {
             );
         }
     }
+
+    #[test]
+    fn decode_legacy_charsets() {
+        let mut inputs = vec![
+            (
+                &["windows-1250", "cp1250", "x-cp1250"][..],
+                &b"\xaf\xf3\xb3w"[..],
+                "Żółw",
+            ),
+            (
+                &["windows-1251", "cp1251", "X-CP1251"],
+                b"\xcf\xf0\xe8\xe2\xe5\xf2",
+                "Привет",
+            ),
+            (&["windows-1252", "cp1252"], b"caf\xe9 \x80", "café €"),
+            (
+                &["windows-1253", "cp1253"],
+                b"\xd9\xec\xdd\xe3\xe1",
+                "Ωμέγα",
+            ),
+            (&["windows-1254", "cp1254"], b"\xdd\xfe\xe7i", "İşçi"),
+            (&["windows-1255", "cp1255"], b"\xf9\xec\xe5\xed", "שלום"),
+            (&["windows-1256", "cp1256"], b"\xd3\xe1\xc7\xe3", "سلام"),
+            (&["windows-1257", "cp1257"], b"\xc0\xfeuolas", "Ąžuolas"),
+            (&["windows-1258", "cp1258"], b"\xd0\xfe", "Đ₫"),
+            (&["koi8-r", "KOI8-R"], b"\xf0\xd2\xc9\xd7\xc5\xd4", "Привет"),
+        ];
+        #[cfg(feature = "full_encoding")]
+        inputs.extend([
+            (
+                &["gb2312", "gbk", "x-gbk", "cp936"][..],
+                &b"\xd6\xd0\xce\xc4"[..],
+                "中文",
+            ),
+            (
+                &["big5", "cn-big5", "big5-hkscs"],
+                b"\xa4\xa4\xa4\xe5",
+                "中文",
+            ),
+            (
+                &["euc-kr", "ks_c_5601-1987", "cp949"],
+                b"\xc7\xd1\xb1\xb9",
+                "한국",
+            ),
+            (
+                &[
+                    "shift_jis",
+                    "sjis",
+                    "ms_kanji",
+                    "x-sjis",
+                    "windows-31j",
+                    "cp932",
+                ],
+                b"\x93\xfa\x96\x7b",
+                "日本",
+            ),
+        ]);
+
+        for (charsets, bytes, expected) in inputs {
+            for charset in charsets {
+                let decoder = charset_decoder(charset.as_bytes())
+                    .unwrap_or_else(|| panic!("No decoder for {charset}"));
+                assert_eq!(decoder(bytes), expected, "Failed for {charset}");
+            }
+        }
+    }
 }
@@ -9,6 +9,10 @@
  * except according to those terms.
  */
 
+use alloc::string::String;
+
+#[cfg(feature = "detect_charset")]
+pub mod detect;
 pub mod map;
 pub mod multi_byte;
 pub mod single_byte;
@@ -65,8 +69,9 @@ mod tests {
             ];
 
         for input in inputs {
-            let decoder = charset_decoder(input.0.as_bytes())
-                .expect(&("Failed to find decoder for ".to_owned() + input.0));
+            let decoder = charset_decoder(input.0.as_bytes()).unwrap_or_else(|| {
+                panic!("{}", ("Failed to find decoder for ".to_owned() + input.0))
+            });
 
             assert_eq!(decoder(&input.1), input.2);
         }
@@ -9,6 +9,7 @@
  * except according to those terms.
  */
 
+use alloc::string::String;
 #[cfg(feature = "full_encoding")]
 use encoding_rs::*;
 
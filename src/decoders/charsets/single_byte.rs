@@ -9,6 +9,8 @@
  * except according to those terms.
  */
 
+use alloc::string::String;
+
 fn single_byte_decoder(table: &[char], bytes: &[u8]) -> String {
     let mut result = String::with_capacity(bytes.len() * 2);
 
@@ -9,91 +9,72 @@
  * except according to those terms.
  */
 
-use std::char::{decode_utf16, REPLACEMENT_CHARACTER};
+use alloc::{borrow::Cow, string::String, string::ToString, vec::Vec};
+use core::char::{decode_utf16, REPLACEMENT_CHARACTER};
 
-use crate::decoders::base64::BASE64_MAP;
-
-struct Utf7DecoderState {
-    utf16_bytes: Vec<u16>,
-    pending_byte: Option<u8>,
-    b64_bytes: u32,
+pub fn decoder_utf7(bytes: &[u8]) -> String {
+    decode_utf7_(bytes, b'+', b'/')
 }
 
-fn add_utf16_bytes(state: &mut Utf7DecoderState, n_bytes: usize) {
-    debug_assert!(n_bytes < std::mem::size_of::<u32>());
-
-    for byte in state.b64_bytes.to_le_bytes()[0..n_bytes].iter() {
-        if let Some(pending_byte) = state.pending_byte {
-            state
-                .utf16_bytes
-                .push(u16::from_be_bytes([pending_byte, *byte]));
-            state.pending_byte = None;
-        } else {
-            state.pending_byte = Some(*byte);
-        }
+/// Decodes an IMAP mailbox name encoded in modified UTF-7 (RFC3501, section 5.1.3),
+/// borrowing the input when it contains no encoded runs.
+pub fn decode_utf7_imap(name: &str) -> Cow<'_, str> {
+    if name.contains('&') {
+        decode_utf7_(name.as_bytes(), b'&', b',').into()
+    } else {
+        name.into()
     }
 }
 
-pub fn decoder_utf7(bytes: &[u8]) -> String {
-    let mut result = String::with_capacity(bytes.len());
-    let mut byte_count: u8 = 0;
-    let mut in_b64 = false;
-
-    let mut state = Utf7DecoderState {
-        utf16_bytes: Vec::with_capacity(10),
-        pending_byte: None,
-        b64_bytes: 0,
+/// Decodes UTF-7 text that uses `shift` to start a base64 run and `slash`
+/// as the 64th character of the base64 alphabet.
+/// Malformed runs are copied as they are and unshifted text is decoded as UTF-8.
+fn decode_utf7_(bytes: &[u8], shift: u8, slash: u8) -> String {
+    let b64_value = |ch: u8| match ch {
+        b'A'..=b'Z' => Some(ch - b'A'),
+        b'a'..=b'z' => Some(ch - b'a' + 26),
+        b'0'..=b'9' => Some(ch - b'0' + 52),
+        b'+' => Some(62),
+        _ if ch == slash => Some(63),
+        _ => None,
     };
+    let mut result = String::with_capacity(bytes.len());
+    let mut pos = 0;
+
+    while pos < bytes.len() {
+        let unshifted = &bytes[pos..];
+        let len = unshifted
+            .iter()
+            .position(|&ch| ch == shift)
+            .unwrap_or(unshifted.len());
+        result.push_str(&String::from_utf8_lossy(&unshifted[..len]));
+        pos += len + 1;
+        if pos > bytes.len() {
+            break;
+        }
 
-    for byte in bytes {
-        if in_b64 {
-            let val = BASE64_MAP[byte_count as usize][*byte as usize];
-
-            if val < 0x01ffffff {
-                byte_count = (byte_count + 1) & 3;
-
-                if byte_count == 1 {
-                    state.b64_bytes = val;
-                } else {
-                    state.b64_bytes |= val;
-
-                    if byte_count == 0 {
-                        add_utf16_bytes(&mut state, 3);
-                    }
-                }
-            } else {
-                match byte_count {
-                    1 | 2 => {
-                        add_utf16_bytes(&mut state, 1);
-                    }
-                    3 => {
-                        add_utf16_bytes(&mut state, 2);
-                    }
-                    _ => (),
-                }
-
-                if !state.utf16_bytes.is_empty() {
-                    result.push_str(
-                        decode_utf16(state.utf16_bytes.drain(..))
-                            .map(|r| r.unwrap_or(REPLACEMENT_CHARACTER))
-                            .collect::<String>()
-                            .as_str(),
-                    );
-                } else if byte_count > 0 || state.pending_byte.is_some() {
-                    result.push(REPLACEMENT_CHARACTER);
-                } else {
-                    result.push('+');
-                    result.push(char::from(*byte));
-                }
-
-                state.pending_byte = None;
-                byte_count = 0;
-                in_b64 = false;
+        let start = pos;
+        while bytes.get(pos).and_then(|&ch| b64_value(ch)).is_some() {
+            pos += 1;
+        }
+        let run = &bytes[start..pos];
+        let decoded = if !run.is_empty() {
+            decode_utf7_run(run.iter().filter_map(|&ch| b64_value(ch)))
+        } else if bytes.get(pos) == Some(&b'-') {
+            // Escaped shift character
+            Some(char::from(shift).into())
+        } else {
+            None
+        };
+
+        if let Some(decoded) = decoded {
+            result.push_str(&decoded);
+            // An explicit end of run is absorbed
+            if bytes.get(pos) == Some(&b'-') {
+                pos += 1;
             }
-        } else if byte == &b'+' {
-            in_b64 = true;
         } else {
-            result.push(char::from(*byte));
+            result.extend(bytes[start - 1..pos].iter().map(|&ch| char::from(ch)));
         }
     }
 
@@ -101,6 +82,28 @@ pub fn decoder_utf7(bytes: &[u8]) -> String {
     result
 }
 
+fn decode_utf7_run(values: impl Iterator<Item = u8>) -> Option<String> {
+    let mut utf16 = Vec::new();
+    let mut bits: u32 = 0;
+    let mut n_bits = 0;
+
+    for value in values {
+        bits = (bits << 6) | u32::from(value);
+        n_bits += 6;
+        if n_bits >= 16 {
+            n_bits -= 16;
+            utf16.push((bits >> n_bits) as u16);
+            bits &= (1 << n_bits) - 1;
+        }
+    }
+
+    // Leftover bits must be zero padding
+    if n_bits >= 6 || bits != 0 || utf16.is_empty() {
+        return None;
+    }
+    decode_utf16(utf16).collect::<Result<String, _>>().ok()
+}
+
 fn decoder_utf16_(bytes: &[u8], fnc: fn([u8; 2]) -> u16) -> String {
     if bytes.len() >= 2 {
         decode_utf16(bytes.chunks_exact(2).map(|c| fnc([c[0], c[1]])))
@@ -138,7 +141,7 @@ pub fn decoder_utf8(bytes: &[u8]) -> String {
 
 #[cfg(test)]
 mod tests {
-    use crate::decoders::charsets::utf::decoder_utf7;
+    use crate::decoders::charsets::utf::{decode_utf7_imap, decoder_utf7};
 
     #[test]
     fn decode_utf7() {
@@ -147,7 +150,13 @@ mod tests {
             ("Hi Mom -+Jjo--!", "Hi Mom -☺-!"),
             ("+ZeVnLIqe-", "日本語"),
             ("Item 3 is +AKM-1.", "Item 3 is £1."),
-            ("Plus minus +- -+ +--", "Plus minus +- -+ +--"),
+            ("Plus minus +- -+ +--", "Plus minus + -+ +-"),
+            ("Item 3 is +AKM 1.", "Item 3 is £ 1."),
+            (
+                "Malformed +AK- and +ZeVnLIqe-+-",
+                "Malformed +AK- and 日本語+",
+            ),
+            ("Lone surrogate +2D0-.", "Lone surrogate +2D0-."),
             (
                 "+APw-ber ihre mi+AN8-liche Lage+ADs- +ACI-wir",
                 "über ihre mißliche Lage; \"wir",
@@ -167,7 +176,29 @@ mod tests {
         ];
 
         for input in inputs {
-            assert_eq!(decoder_utf7(input.0.as_bytes()), input.1);
+            assert_eq!(
+                decoder_utf7(input.0.as_bytes()),
+                input.1,
+                "failed for {:?}",
+                input.0
+            );
+        }
+    }
+
+    #[test]
+    fn decode_utf7_imap_names() {
+        for (input, expected) in [
+            ("INBOX", "INBOX"),
+            ("~peter/mail/&U,BTFw-/&ZeVnLIqe-", "~peter/mail/台北/日本語"),
+            ("Tom &- Jerry", "Tom & Jerry"),
+            ("Entw&APw-rfe", "Entwürfe"),
+            ("Broken &Jjo!", "Broken ☺!"),
+            ("Broken &AK-", "Broken &AK-"),
+            ("Café &- Bar", "Café & Bar"),
+            ("日本 &ZeVnLIqe-", "日本 日本語"),
+            ("Trailing &", "Trailing &"),
+        ] {
+            assert_eq!(decode_utf7_imap(input), expected, "failed for {input:?}");
         }
     }
 }
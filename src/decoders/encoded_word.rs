@@ -9,7 +9,8 @@
  * except according to those terms.
  */
 
-use crate::{decoders::charsets::map::charset_decoder, parsers::MessageStream};
+use crate::{decoders::charsets::map::charset_decoder_or, parsers::MessageStream};
+use alloc::string::String;
 
 use super::DecodeWordFnc;
 
@@ -48,10 +49,8 @@ impl<'x> MessageStream<'x> {
                         }
                         state = Rfc2047State::Encoding;
                     }
-                    b'*' => {
-                        if charset_end == charset_start {
-                            charset_end = self.offset() - 1;
-                        }
+                    b'*' if charset_end == charset_start => {
+                        charset_end = self.offset() - 1;
                     }
                     b'\n' => {
                         return None;
@@ -81,7 +80,9 @@ impl<'x> MessageStream<'x> {
         }
 
         if let Some(bytes) = decode_fnc.and_then(|fnc| fnc(self)) {
-            if let Some(decoder) = charset_decoder(self.bytes(charset_start..charset_end)) {
+            if let Some(decoder) =
+                charset_decoder_or(self.bytes(charset_start..charset_end), self.default_charset)
+            {
                 decoder(&bytes).into()
             } else {
                 String::from_utf8(bytes)
@@ -154,6 +155,8 @@ mod tests {
                 "مرحبا بالعالم",
                 true,
             ),
+            ("?utf-7?q?+ZeVnLIqe-_+- 1?=", "日本語 + 1", true),
+            ("?UTF-7?B?K1plVm5MSXFlLQ==?=", "日本語", true),
             #[cfg(feature = "full_encoding")]
             (
                 "?shift_jis?B?g26DjYFbgUWDj4Fbg4uDaA==?=",
@@ -0,0 +1,130 @@
+/*
+ * Copyright Stalwart Labs Ltd. See the COPYING
+ * file at the top-level directory of this distribution.
+ *
+ * Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+ * https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+ * <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+ * option. This file may not be copied, modified, or distributed
+ * except according to those terms.
+ */
+
+use alloc::string::String;
+
+/// Decodes a RFC3676 `format=flowed` text body, joining soft line breaks
+/// into paragraphs while preserving the quote depth of each paragraph.
+///
+/// A line is flowed when it ends with a space once its quote markers and
+/// space-stuffing are removed, and it is only joined with the next line when
+/// both have the same quote depth. Otherwise, as well as at the end of the
+/// text, it is kept as a fixed line. Signature separators are never flowed.
+pub fn decode_flowed(text: &str, delsp: bool) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut paragraph: Option<(usize, &str)> = None;
+
+    for line in text.split_inclusive('\n') {
+        let (line, line_end) = if let Some(line) = line.strip_suffix("\r\n") {
+            (line, "\r\n")
+        } else if let Some(line) = line.strip_suffix('\n') {
+            (line, "\n")
+        } else {
+            (line, "")
+        };
+
+        // Quote depth and space-stuffing
+        let content = line.trim_start_matches('>');
+        let depth = line.len() - content.len();
+        let content = content.strip_prefix(' ').unwrap_or(content);
+        let is_signature = content == "-- ";
+        let is_flowed = content.ends_with(' ') && !is_signature;
+
+        match paragraph {
+            Some((paragraph_depth, _)) if paragraph_depth == depth && !is_signature => {
+                // The space is only deleted once the line is known to be joined
+                if delsp {
+                    result.pop();
+                }
+            }
+            _ => {
+                if let Some((_, paragraph_end)) = paragraph {
+                    result.push_str(paragraph_end);
+                }
+                if depth > 0 {
+                    result.extend(core::iter::repeat('>').take(depth));
+                    if !content.is_empty() {
+                        result.push(' ');
+                    }
+                }
+            }
+        }
+
+        if is_flowed {
+            result.push_str(content);
+            paragraph = Some((depth, line_end));
+        } else {
+            result.push_str(content);
+            result.push_str(line_end);
+            paragraph = None;
+        }
+    }
+
+    if let Some((_, paragraph_end)) = paragraph {
+        result.push_str(paragraph_end);
+    }
+
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::decoders::flowed::decode_flowed;
+
+    #[test]
+    fn decode_flowed_text() {
+        let inputs = [
+            (
+                "This is a \nflowed paragraph.\nFixed line.\n",
+                false,
+                "This is a flowed paragraph.\nFixed line.\n",
+            ),
+            (
+                "This is a \r\nflowed paragraph.\r\nFixed line.\r\n",
+                false,
+                "This is a flowed paragraph.\r\nFixed line.\r\n",
+            ),
+            ("Deleted \nspace\n", true, "Deletedspace\n"),
+            (
+                "> Kept \n>> space \nKept \n",
+                true,
+                "> Kept \n>> space \nKept \n",
+            ),
+            (
+                "Stuffed \n \n>  Indented \n> text\n",
+                true,
+                "Stuffed\n>  Indentedtext\n",
+            ),
+            ("Unterminated \nflowed ", false, "Unterminated flowed "),
+            (
+                "> Quoted \n> text\n>> Deeper \n>> quote\nNot quoted\n",
+                false,
+                "> Quoted text\n>> Deeper quote\nNot quoted\n",
+            ),
+            (
+                "> Depth \n>> changes\n>\nEnd\n",
+                false,
+                "> Depth \n>> changes\n>\nEnd\n",
+            ),
+            (
+                " From the stuffed line\n >Not a quote\n  Indented\n",
+                false,
+                "From the stuffed line\n>Not a quote\n Indented\n",
+            ),
+            ("Signature \n-- \nJohn\n", false, "Signature \n-- \nJohn\n"),
+            ("Body\n-- \nJohn\n", false, "Body\n-- \nJohn\n"),
+        ];
+
+        for (input, delsp, expected) in inputs {
+            assert_eq!(decode_flowed(input, delsp), expected, "{input:?}");
+        }
+    }
+}
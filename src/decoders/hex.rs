@@ -10,6 +10,7 @@
  */
 
 use super::quoted_printable::HEX_MAP;
+use alloc::vec::Vec;
 
 #[derive(PartialEq, Debug)]
 enum HexState {
@@ -81,7 +82,7 @@ mod tests {
 
             assert!(success, "Failed for '{:?}'", input.0);
 
-            let result_str = std::str::from_utf8(&result).unwrap();
+            let result_str = core::str::from_utf8(&result).unwrap();
 
             /*println!(
                 "Decoded '{}'\n -> to ->\n'{}'\n{}",
@@ -9,7 +9,8 @@
  * except according to those terms.
  */
 
-use std::char::REPLACEMENT_CHARACTER;
+use alloc::{borrow::Cow, string::String, vec::Vec};
+use core::char::REPLACEMENT_CHARACTER;
 
 pub fn add_html_token(result: &mut String, token: &[u8], add_space: bool) {
     if add_space {
@@ -25,7 +26,7 @@ pub fn add_html_token(result: &mut String, token: &[u8], add_space: bool) {
                 (code, 10)
             };
 
-            entity_code = std::str::from_utf8(code)
+            entity_code = core::str::from_utf8(code)
                 .map_or(0, |code| u32::from_str_radix(code, radix).unwrap_or(0));
         } else if (2..=31).contains(&entity.len()) {
             let mut hash = entity.len() as u32;
@@ -107,12 +108,22 @@ pub fn add_html_token(result: &mut String, token: &[u8], add_space: bool) {
         }
     }
 
-    result.push_str(std::str::from_utf8(token).unwrap());
+    result.push_str(core::str::from_utf8(token).unwrap());
 }
 
 pub fn html_to_text(input: &str) -> String {
+    html_to_text_(input, false)
+}
+
+/// Converts HTML to plain text, rendering links as `label (target)`.
+pub fn html_to_text_with_links(input: &str) -> String {
+    html_to_text_(input, true)
+}
+
+fn html_to_text_(input: &str, annotate_links: bool) -> String {
     let mut result = String::with_capacity(input.len());
     let input = input.as_bytes();
+    let mut link: Option<(usize, String)> = None;
 
     let mut in_tag = false;
     let mut in_head = false;
@@ -127,6 +138,7 @@ pub fn html_to_text(input: &str) -> String {
     let mut token_end = 0;
 
     let mut tag_token_pos = 0;
+    let mut tag_start = 0;
     let mut comment_pos = 0;
 
     for (pos, ch) in input.iter().enumerate() {
@@ -143,27 +155,69 @@ pub fn html_to_text(input: &str) -> String {
                     }
 
                     tag_token_pos = 0;
+                    tag_start = pos + 1;
                     in_tag = true;
                     is_token_start = true;
                     is_tag_close = false;
                     continue;
                 }
                 b'>' if in_tag => {
-                    if tag_token_pos == 1 {
-                        match input.get(token_start..token_end + 1) {
-                            Some(tag)
-                                if tag.eq_ignore_ascii_case(b"br")
-                                    || (tag.eq_ignore_ascii_case(b"p") && is_tag_close) =>
-                            {
+                    let tag = &input[tag_start..pos];
+                    match tag_name(tag) {
+                        Some(name)
+                            if name.eq_ignore_ascii_case(b"br")
+                                || (name.eq_ignore_ascii_case(b"p") && is_tag_close) =>
+                        {
+                            result.push('\n');
+                            is_after_space = false;
+                            is_new_line = true;
+                        }
+                        Some(name) if name.eq_ignore_ascii_case(b"head") => {
+                            in_head = !is_tag_close;
+                        }
+                        Some(name) if name.eq_ignore_ascii_case(b"li") && !in_head => {
+                            if !is_tag_close {
+                                if !result.is_empty() && !result.ends_with('\n') {
+                                    result.push('\n');
+                                }
+                                result.push_str("• ");
+                            }
+                            is_after_space = false;
+                            is_new_line = true;
+                        }
+                        Some(name)
+                            if (name.eq_ignore_ascii_case(b"ul")
+                                || name.eq_ignore_ascii_case(b"ol"))
+                                && is_tag_close
+                                && !in_head =>
+                        {
+                            if !result.is_empty() && !result.ends_with('\n') {
                                 result.push('\n');
-                                is_after_space = false;
-                                is_new_line = true;
                             }
-                            Some(tag) if tag.eq_ignore_ascii_case(b"head") => {
-                                in_head = !is_tag_close;
+                            is_after_space = false;
+                            is_new_line = true;
+                        }
+                        Some(name) if name.eq_ignore_ascii_case(b"a") && annotate_links => {
+                            if is_tag_close {
+                                if let Some((label_start, href)) = link.take() {
+                                    let label = result[label_start..].trim();
+                                    if !label.is_empty()
+                                        && label != href
+                                        && href.strip_prefix("mailto:") != Some(label)
+                                    {
+                                        result.push_str(" (");
+                                        result.push_str(&href);
+                                        result.push(')');
+                                    }
+                                }
+                            } else {
+                                link = tag_attribute(tag, b"href")
+                                    .map(|href| decode_html_entities(href.trim()))
+                                    .filter(|href| !href.is_empty() && !href.starts_with('#'))
+                                    .map(|href| (result.len(), href.into_owned()));
                             }
-                            _ => (),
                         }
+                        _ => (),
                     }
 
                     in_tag = false;
@@ -257,6 +311,130 @@ pub fn html_to_text(input: &str) -> String {
     result
 }
 
+/// Returns the name of a tag, without the leading slash of closing tags.
+fn tag_name(tag: &[u8]) -> Option<&[u8]> {
+    let start = tag
+        .iter()
+        .position(|ch| !ch.is_ascii_whitespace() && *ch != b'/')?;
+    let end = tag[start..]
+        .iter()
+        .position(|ch| ch.is_ascii_whitespace() || *ch == b'/')
+        .map_or(tag.len(), |end| start + end);
+    Some(&tag[start..end])
+}
+
+/// Returns the value of an attribute of a tag.
+pub(crate) fn tag_attribute<'x>(tag: &'x [u8], name: &[u8]) -> Option<&'x str> {
+    TagAttributes::new(tag, 0)
+        .find(|(attr_name, _)| attr_name.eq_ignore_ascii_case(name))
+        .and_then(|(_, value)| core::str::from_utf8(value).ok())
+}
+
+/// Iterator over the names and values of the attributes of a tag, starting
+/// with its name. Attributes without a value are skipped and the iteration
+/// stops at the closing `>`, where `pos` is left.
+pub(crate) struct TagAttributes<'x> {
+    tag: &'x [u8],
+    pub(crate) pos: usize,
+}
+
+impl<'x> TagAttributes<'x> {
+    pub(crate) fn new(tag: &'x [u8], pos: usize) -> Self {
+        TagAttributes { tag, pos }
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self
+            .tag
+            .get(self.pos)
+            .is_some_and(|ch| ch.is_ascii_whitespace())
+        {
+            self.pos += 1;
+        }
+    }
+}
+
+impl<'x> Iterator for TagAttributes<'x> {
+    type Item = (&'x [u8], &'x [u8]);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let tag = self.tag;
+
+        loop {
+            self.skip_whitespace();
+            let name_start = self.pos;
+            while tag
+                .get(self.pos)
+                .is_some_and(|&ch| !ch.is_ascii_whitespace() && !b"=>/".contains(&ch))
+            {
+                self.pos += 1;
+            }
+            let attr_name = &tag[name_start..self.pos];
+            self.skip_whitespace();
+
+            match tag.get(self.pos) {
+                Some(b'=') => {
+                    self.pos += 1;
+                    self.skip_whitespace();
+                    let value = match tag.get(self.pos) {
+                        Some(&quote @ (b'"' | b'\'')) => {
+                            let value_start = self.pos + 1;
+                            self.pos = tag[value_start..]
+                                .iter()
+                                .position(|&ch| ch == quote)
+                                .map_or(tag.len(), |end| value_start + end);
+                            let value = &tag[value_start..self.pos];
+                            self.pos = (self.pos + 1).min(tag.len());
+                            value
+                        }
+                        _ => {
+                            let value_start = self.pos;
+                            while tag
+                                .get(self.pos)
+                                .is_some_and(|&ch| !ch.is_ascii_whitespace() && ch != b'>')
+                            {
+                                self.pos += 1;
+                            }
+                            &tag[value_start..self.pos]
+                        }
+                    };
+                    return Some((attr_name, value));
+                }
+                Some(b'>') | None => return None,
+                Some(_) if attr_name.is_empty() => self.pos += 1,
+                Some(_) => (),
+            }
+        }
+    }
+}
+
+/// Decodes the HTML entities of an attribute value.
+pub(crate) fn decode_html_entities(value: &str) -> Cow<'_, str> {
+    if !value.contains('&') {
+        return value.into();
+    }
+
+    let bytes = value.as_bytes();
+    let mut result = String::with_capacity(value.len());
+    let mut pos = 0;
+    while let Some(amp) = bytes[pos..].iter().position(|&ch| ch == b'&') {
+        result.push_str(&value[pos..pos + amp]);
+        pos += amp;
+        match bytes[pos..].iter().take(32).position(|&ch| ch == b';') {
+            Some(end) => {
+                add_html_token(&mut result, &bytes[pos..pos + end + 1], false);
+                pos += end + 1;
+            }
+            None => {
+                result.push('&');
+                pos += 1;
+            }
+        }
+    }
+    result.push_str(&value[pos..]);
+    result.into()
+}
+
 pub fn text_to_html(input: &str) -> String {
     let input = input.as_bytes();
     let mut result = Vec::with_capacity(input.len() + 26);
@@ -1000,7 +1178,9 @@ static ENTITY_MAP: &[u32; 18016] = &[
 #[cfg(test)]
 mod tests {
 
-    use crate::decoders::html::{add_html_token, html_to_text, text_to_html};
+    use crate::decoders::html::{
+        add_html_token, html_to_text, html_to_text_with_links, text_to_html,
+    };
 
     #[test]
     fn convert_text_to_html() {
@@ -1061,6 +1241,46 @@ mod tests {
         }
     }
 
+    #[test]
+    fn convert_html_to_text_with_links() {
+        let inputs = [
+            (
+                "<p>Read <a href=\"https://example.com/?a=1&amp;b=2\">the docs</a>.</p>",
+                "Read the docs (https://example.com/?a=1&b=2).\n",
+                "Read the docs.\n",
+            ),
+            (
+                "<A HREF='https://example.com/'>https://example.com/</A> <a href=#top>top</a>",
+                "https://example.com/ top",
+                "https://example.com/ top",
+            ),
+            (
+                "Mail <a href=\"mailto:john@example.com\">john@example.com</a><br>",
+                "Mail john@example.com\n",
+                "Mail john@example.com\n",
+            ),
+            (
+                "<ul><li>one</li><li> two <b>items</b></li></ul>after",
+                "• one\n• two items\nafter",
+                "• one\n• two items\nafter",
+            ),
+            (
+                "<p class=\"x\">Caf&#233;&nbsp;crème<br class=\"y\">ok</p>",
+                "Café\u{a0}crème\nok\n",
+                "Café\u{a0}crème\nok\n",
+            ),
+        ];
+
+        for (input, with_links, without_links) in inputs {
+            assert_eq!(
+                html_to_text_with_links(input),
+                with_links,
+                "Failed for {input:?}"
+            );
+            assert_eq!(html_to_text(input), without_links, "Failed for {input:?}");
+        }
+    }
+
     #[test]
     fn convert_html_entity() {
         let inputs = [
@@ -9,16 +9,24 @@
  * except according to those terms.
  */
 
-use std::borrow::Cow;
+use alloc::{borrow::Cow, vec::Vec};
 
 use crate::parsers::MessageStream;
 
 pub mod base64;
+#[cfg(feature = "simd")]
+pub mod base64_simd;
+#[cfg(feature = "binhex")]
+pub mod binhex;
 pub mod charsets;
 pub mod encoded_word;
+pub mod flowed;
 pub mod hex;
 pub mod html;
+#[cfg(feature = "domain")]
+pub mod punycode;
 pub mod quoted_printable;
+pub mod uuencode;
 
 pub type DecodeFnc<'x> = fn(&mut MessageStream<'x>, &[u8]) -> (usize, Cow<'x, [u8]>);
 pub type DecodeWordFnc<'x> = fn(&mut MessageStream<'x>) -> Option<Vec<u8>>;
@@ -0,0 +1,156 @@
+/*
+ * Copyright Stalwart Labs Ltd. See the COPYING
+ * file at the top-level directory of this distribution.
+ *
+ * Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+ * https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+ * <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+ * option. This file may not be copied, modified, or distributed
+ * except according to those terms.
+ */
+
+use alloc::{borrow::Cow, string::String, vec::Vec};
+
+const BASE: u32 = 36;
+const T_MIN: u32 = 1;
+const T_MAX: u32 = 26;
+const SKEW: u32 = 38;
+const DAMP: u32 = 700;
+const INITIAL_BIAS: u32 = 72;
+const INITIAL_N: u32 = 128;
+
+/// Decodes an RFC3492 Punycode string, without the `xn--` prefix.
+pub fn decode_punycode(input: &str) -> Option<String> {
+    let (basic, extended) = input
+        .rfind('-')
+        .map_or(("", input), |pos| (&input[..pos], &input[pos + 1..]));
+    if !basic.is_ascii() {
+        return None;
+    }
+
+    let mut output: Vec<char> = basic.chars().collect();
+    let mut n = INITIAL_N;
+    let mut i: u32 = 0;
+    let mut bias = INITIAL_BIAS;
+    let mut digits = extended.bytes().peekable();
+
+    while digits.peek().is_some() {
+        let old_i = i;
+        let mut w: u32 = 1;
+        let mut k = BASE;
+
+        loop {
+            let digit = match digits.next()? {
+                ch @ b'a'..=b'z' => ch - b'a',
+                ch @ b'A'..=b'Z' => ch - b'A',
+                ch @ b'0'..=b'9' => ch - b'0' + 26,
+                _ => return None,
+            } as u32;
+            i = i.checked_add(digit.checked_mul(w)?)?;
+            let t = if k <= bias {
+                T_MIN
+            } else if k >= bias + T_MAX {
+                T_MAX
+            } else {
+                k - bias
+            };
+            if digit < t {
+                break;
+            }
+            w = w.checked_mul(BASE - t)?;
+            k += BASE;
+        }
+
+        let len = output.len() as u32 + 1;
+        bias = adapt(i - old_i, len, old_i == 0);
+        n = n.checked_add(i / len)?;
+        i %= len;
+        output.insert(i as usize, char::from_u32(n)?);
+        i += 1;
+    }
+
+    Some(output.into_iter().collect())
+}
+
+/// Converts the `xn--` labels of a domain name to Unicode, labels
+/// that are not valid Punycode are returned unchanged.
+pub fn domain_to_unicode(domain: &str) -> Cow<'_, str> {
+    if !domain.split('.').any(is_ace_label) {
+        return domain.into();
+    }
+
+    let mut result = String::with_capacity(domain.len());
+    for (pos, label) in domain.split('.').enumerate() {
+        if pos > 0 {
+            result.push('.');
+        }
+        match is_ace_label(label)
+            .then(|| decode_punycode(&label[4..]))
+            .flatten()
+        {
+            Some(label) => result.push_str(&label),
+            None => result.push_str(label),
+        }
+    }
+    result.into()
+}
+
+/// Returns `true` if the label is an ASCII-compatible encoded IDN label.
+pub fn is_ace_label(label: &str) -> bool {
+    label
+        .get(..4)
+        .is_some_and(|prefix| prefix.eq_ignore_ascii_case("xn--"))
+}
+
+fn adapt(delta: u32, num_points: u32, is_first: bool) -> u32 {
+    let mut delta = delta / if is_first { DAMP } else { 2 };
+    delta += delta / num_points;
+    let mut k = 0;
+    while delta > ((BASE - T_MIN) * T_MAX) / 2 {
+        delta /= BASE - T_MIN;
+        k += BASE;
+    }
+    k + (((BASE - T_MIN + 1) * delta) / (delta + SKEW))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{decode_punycode, domain_to_unicode};
+
+    #[test]
+    fn decode_punycode_labels() {
+        for (input, expected) in [
+            ("bcher-kva", Some("bücher")),
+            ("mnchen-3ya", Some("münchen")),
+            ("r8jz45g", Some("例え")),
+            ("ihqwcrb4cv8a8dqg056pqjye", Some("他们为什么不说中文")),
+            (
+                "Proprostnemluvesky-uyb24dma41a",
+                Some("Pročprostěnemluvíčesky"),
+            ),
+            ("-> $1.00 <--", Some("-> $1.00 <-")),
+            ("ls8h", Some("💩")),
+            ("abc!", None),
+            ("zzzzzzzzzzzzzzzzzzzz", None),
+        ] {
+            assert_eq!(
+                decode_punycode(input).as_deref(),
+                expected,
+                "failed for {input:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn decode_domains() {
+        for (input, expected) in [
+            ("example.com", "example.com"),
+            ("xn--bcher-kva.example", "bücher.example"),
+            ("mail.XN--R8JZ45G.jp", "mail.例え.jp"),
+            ("xn--invalid!.com", "xn--invalid!.com"),
+            ("ünïcode.xn--bcher-kva", "ünïcode.bücher"),
+        ] {
+            assert_eq!(domain_to_unicode(input), expected, "failed for {input:?}");
+        }
+    }
+}
@@ -9,14 +9,15 @@
  * except according to those terms.
  */
 
-use std::borrow::Cow;
+use alloc::{borrow::Cow, vec::Vec};
 
 use crate::parsers::MessageStream;
 
-#[derive(PartialEq, Debug)]
+#[derive(PartialEq, Debug, Clone, Copy)]
 enum QuotedPrintableState {
     None,
     Eq,
+    EqSpace,
     Hex1,
 }
 
@@ -28,73 +29,79 @@ pub fn quoted_printable_decode(bytes: &[u8]) -> Option<Vec<u8>> {
     let mut ws_count = 0;
     let mut crlf = b"\n".as_ref();
 
-    for &ch in bytes {
-        match ch {
-            b'=' => {
-                if let QuotedPrintableState::None = state {
-                    state = QuotedPrintableState::Eq
-                } else {
-                    return None;
-                }
+    for (pos, &ch) in bytes.iter().enumerate() {
+        let hex = if state != QuotedPrintableState::None {
+            #[cfg(feature = "ludicrous_mode")]
+            unsafe {
+                *HEX_MAP.get_unchecked(ch as usize)
             }
-            b'\n' => {
-                if QuotedPrintableState::Eq == state {
-                    state = QuotedPrintableState::None;
-                } else {
-                    if ws_count > 0 {
-                        buf.truncate(buf.len() - ws_count);
-                    }
-                    buf.extend_from_slice(crlf);
-                }
+            #[cfg(not(feature = "ludicrous_mode"))]
+            HEX_MAP[ch as usize]
+        } else {
+            -1
+        };
+
+        match state {
+            QuotedPrintableState::Eq if hex != -1 => {
+                hex1 = hex;
+                state = QuotedPrintableState::Hex1;
+            }
+            QuotedPrintableState::Hex1 if hex != -1 => {
+                buf.push(((hex1 as u8) << 4) | hex as u8);
+                state = QuotedPrintableState::None;
                 ws_count = 0;
             }
-            b'\r' => {
-                crlf = b"\r\n".as_ref();
+            QuotedPrintableState::Eq | QuotedPrintableState::EqSpace
+                if matches!(ch, b' ' | b'\t') =>
+            {
+                state = QuotedPrintableState::EqSpace;
             }
-            _ => match state {
-                QuotedPrintableState::None => {
-                    if ch.is_ascii_whitespace() {
-                        ws_count += 1;
-                    } else {
-                        ws_count = 0;
-                    }
-                    buf.push(ch);
+            QuotedPrintableState::Hex1 => return None,
+            QuotedPrintableState::Eq | QuotedPrintableState::EqSpace
+                if !matches!(ch, b'\r' | b'\n') =>
+            {
+                return None;
+            }
+            _ => match ch {
+                b'\r' if bytes.get(pos + 1) == Some(&b'\n') => {
+                    crlf = b"\r\n".as_ref();
                 }
-                QuotedPrintableState::Eq => {
-                    hex1 = {
-                        #[cfg(feature = "ludicrous_mode")]
-                        unsafe {
-                            *HEX_MAP.get_unchecked(ch as usize)
+                b'\n' | b'\r' => {
+                    // A line break following '=' is a soft line break
+                    if state == QuotedPrintableState::None {
+                        if ws_count > 0 {
+                            buf.truncate(buf.len() - ws_count);
                         }
-                        #[cfg(not(feature = "ludicrous_mode"))]
-                        HEX_MAP[ch as usize]
-                    };
-
-                    if hex1 != -1 {
-                        state = QuotedPrintableState::Hex1;
-                    } else if !ch.is_ascii_whitespace() {
-                        return None;
+                        if ch == b'\r' {
+                            crlf = b"\r\n".as_ref();
+                        }
+                        buf.extend_from_slice(crlf);
+                    } else {
+                        state = QuotedPrintableState::None;
                     }
+                    ws_count = 0;
                 }
-                QuotedPrintableState::Hex1 => {
-                    #[cfg(feature = "ludicrous_mode")]
-                    let hex2 = unsafe { *HEX_MAP.get_unchecked(ch as usize) };
-                    #[cfg(not(feature = "ludicrous_mode"))]
-                    let hex2 = HEX_MAP[ch as usize];
-
-                    state = QuotedPrintableState::None;
-                    if hex2 != -1 {
-                        buf.push(((hex1 as u8) << 4) | hex2 as u8);
-                        ws_count = 0;
+                b'=' => {
+                    state = QuotedPrintableState::Eq;
+                }
+                _ => {
+                    if ch.is_ascii_whitespace() {
+                        ws_count += 1;
                     } else {
-                        return None;
+                        ws_count = 0;
                     }
+                    buf.push(ch);
                 }
             },
         }
     }
 
-    buf.into()
+    // A dangling '=' at the end of the input is a soft line break
+    if state != QuotedPrintableState::Hex1 {
+        Some(buf)
+    } else {
+        None
+    }
 }
 
 #[inline(always)]
@@ -127,92 +134,109 @@ impl<'x> MessageStream<'x> {
 
         let mut state = QuotedPrintableState::None;
         let mut hex1 = 0;
+        let mut eq_start = 0;
         let mut last_ch = 0;
         let mut before_last_ch = 0;
         let mut ws_count = 0;
         let mut end_pos = self.offset();
         let mut crlf = b"\n".as_ref();
+        let mut line_break = 0..0;
 
         self.checkpoint();
 
         while let Some(&ch) = self.next() {
-            match ch {
-                b'=' => {
-                    if let QuotedPrintableState::None = state {
-                        state = QuotedPrintableState::Eq
-                    } else {
-                        self.restore();
-                        return (usize::MAX, b""[..].into());
-                    }
+            let hex = if state != QuotedPrintableState::None {
+                #[cfg(feature = "ludicrous_mode")]
+                unsafe {
+                    *HEX_MAP.get_unchecked(ch as usize)
                 }
-                b'\n' => {
-                    end_pos = if last_ch == b'\r' {
-                        self.offset() - 2
-                    } else {
-                        self.offset() - 1
-                    };
-                    if QuotedPrintableState::Eq == state {
-                        state = QuotedPrintableState::None;
-                    } else {
-                        if ws_count > 0 {
-                            buf.truncate(buf.len() - ws_count);
-                        }
-                        buf.extend_from_slice(crlf);
-                    }
+                #[cfg(not(feature = "ludicrous_mode"))]
+                HEX_MAP[ch as usize]
+            } else {
+                -1
+            };
+
+            // Invalid escape sequences are kept as found in the message
+            let is_valid = match state {
+                QuotedPrintableState::None => true,
+                QuotedPrintableState::Eq => hex != -1 || matches!(ch, b' ' | b'\t' | b'\r' | b'\n'),
+                QuotedPrintableState::EqSpace => matches!(ch, b' ' | b'\t' | b'\r' | b'\n'),
+                QuotedPrintableState::Hex1 => hex != -1,
+            };
+            if !is_valid {
+                buf.extend_from_slice(self.bytes(eq_start..self.offset() - 1));
+                self.has_decode_errors = true;
+                state = QuotedPrintableState::None;
+                ws_count = 0;
+            }
+
+            match state {
+                QuotedPrintableState::Eq if hex != -1 => {
+                    hex1 = hex;
+                    state = QuotedPrintableState::Hex1;
+                }
+                QuotedPrintableState::Hex1 => {
+                    buf.push(((hex1 as u8) << 4) | hex as u8);
+                    state = QuotedPrintableState::None;
                     ws_count = 0;
                 }
-                b'\r' => {
-                    crlf = b"\r\n".as_ref();
+                QuotedPrintableState::Eq | QuotedPrintableState::EqSpace
+                    if matches!(ch, b' ' | b'\t') =>
+                {
+                    state = QuotedPrintableState::EqSpace;
                 }
-                b'-' if !boundary.is_empty() && last_ch == b'-' && self.try_skip(boundary) => {
-                    if before_last_ch == b'\n' {
-                        buf.truncate(buf.len() - (crlf.len() + 1));
-                    } else {
-                        buf.truncate(buf.len() - 1);
-                        end_pos = self.offset() - boundary.len() - 2;
+                _ => match ch {
+                    b'\r' if self.peek() == Some(&&b'\n') => {
+                        crlf = b"\r\n".as_ref();
                     }
-
-                    return (end_pos, buf.into());
-                }
-                _ => match state {
-                    QuotedPrintableState::None => {
-                        if ch.is_ascii_whitespace() {
-                            ws_count += 1;
+                    b'\n' | b'\r' => {
+                        end_pos = if ch == b'\n' && last_ch == b'\r' {
+                            self.offset() - 2
                         } else {
-                            ws_count = 0;
-                        }
-                        buf.push(ch);
-                    }
-                    QuotedPrintableState::Eq => {
-                        hex1 = {
-                            #[cfg(feature = "ludicrous_mode")]
-                            unsafe {
-                                *HEX_MAP.get_unchecked(ch as usize)
-                            }
-                            #[cfg(not(feature = "ludicrous_mode"))]
-                            HEX_MAP[ch as usize]
+                            self.offset() - 1
                         };
-                        if hex1 != -1 {
-                            state = QuotedPrintableState::Hex1;
-                        } else if !ch.is_ascii_whitespace() {
-                            self.restore();
-                            return (usize::MAX, b""[..].into());
+                        // A line break following '=' is a soft line break
+                        if state == QuotedPrintableState::None {
+                            if ws_count > 0 {
+                                buf.truncate(buf.len() - ws_count);
+                            }
+                            if ch == b'\r' {
+                                crlf = b"\r\n".as_ref();
+                            }
+                            line_break = buf.len()..buf.len() + crlf.len();
+                            buf.extend_from_slice(crlf);
+                        } else {
+                            state = QuotedPrintableState::None;
                         }
+                        ws_count = 0;
                     }
-                    QuotedPrintableState::Hex1 => {
-                        #[cfg(feature = "ludicrous_mode")]
-                        let hex2 = unsafe { *HEX_MAP.get_unchecked(ch as usize) };
-                        #[cfg(not(feature = "ludicrous_mode"))]
-                        let hex2 = HEX_MAP[ch as usize];
+                    b'=' => {
+                        state = QuotedPrintableState::Eq;
+                        eq_start = self.offset() - 1;
+                    }
+                    b'-' if !boundary.is_empty() && last_ch == b'-' && self.try_skip(boundary) => {
+                        if matches!(before_last_ch, b'\n' | b'\r') {
+                            // Remove the line break that belongs to the boundary, unless
+                            // the previous line ended with a soft line break
+                            buf.truncate(if line_break.end + 1 == buf.len() {
+                                line_break.start
+                            } else {
+                                buf.len() - 1
+                            });
+                        } else {
+                            buf.truncate(buf.len() - 1);
+                            end_pos = self.offset() - boundary.len() - 2;
+                        }
 
-                        state = QuotedPrintableState::None;
-                        if hex2 != -1 {
-                            buf.push(((hex1 as u8) << 4) | hex2 as u8);
-                            ws_count = 0;
+                        return (end_pos, buf.into());
+                    }
+                    _ => {
+                        if ch.is_ascii_whitespace() {
+                            ws_count += 1;
                         } else {
-                            self.restore();
-                            return (usize::MAX, b""[..].into());
+                            ws_count = 0;
                         }
+                        buf.push(ch);
                     }
                 },
             }
@@ -221,15 +245,17 @@ impl<'x> MessageStream<'x> {
             last_ch = ch;
         }
 
-        (
-            if boundary.is_empty() {
-                self.offset()
-            } else {
-                self.restore();
-                usize::MAX
-            },
-            buf.into(),
-        )
+        if boundary.is_empty() {
+            // A dangling '=' at the end of the input is a soft line break
+            if state == QuotedPrintableState::Hex1 {
+                buf.extend_from_slice(self.bytes(eq_start..self.offset()));
+                self.has_decode_errors = true;
+            }
+            (self.offset(), buf.into())
+        } else {
+            self.restore();
+            (usize::MAX, b""[..].into())
+        }
     }
 
     pub fn decode_quoted_printable_word(&mut self) -> Option<Vec<u8>> {
@@ -275,7 +301,7 @@ impl<'x> MessageStream<'x> {
                     QuotedPrintableState::None => {
                         buf.push(ch);
                     }
-                    QuotedPrintableState::Eq => {
+                    QuotedPrintableState::Eq | QuotedPrintableState::EqSpace => {
                         hex1 = {
                             #[cfg(feature = "ludicrous_mode")]
                             unsafe {
@@ -383,6 +409,10 @@ mod tests {
                 "hello\r\nbar\r\nfoo\tbar\r\nfoo\t \tb\r\nfoo bar\r\nfoo b\r\nfoo\r\nbar\r\nfoo_bar\r\n",
             ),
             ("\n\n", "\n\n"),
+            ("dangling=", "dangling"),
+            ("dangling= \t", "dangling"),
+            ("soft=\r\nbreaks=\nare=\requal", "softbreaksareequal"),
+            ("bare\rcr\r\nlines  \r", "bare\r\ncr\r\nlines\r\n"),
         ] {
             assert_eq!(
                 String::from_utf8(super::quoted_printable_decode(encoded_str.as_bytes()).unwrap_or_default()).unwrap(),
@@ -390,6 +420,14 @@ mod tests {
                 "Failed for {encoded_str:?}",
             );
         }
+
+        for encoded_str in ["=XY", "=4", "a = b", "==41"] {
+            assert_eq!(
+                super::quoted_printable_decode(encoded_str.as_bytes()),
+                None,
+                "Failed for {encoded_str:?}",
+            );
+        }
     }
 
     #[test]
@@ -454,13 +492,55 @@ mod tests {
             let (bytes_read, result) = s.decode_quoted_printable_mime(b"boundary");
             assert_ne!(bytes_read, usize::MAX);
             assert_eq!(
-                std::str::from_utf8(result.as_ref()).unwrap(),
+                core::str::from_utf8(result.as_ref()).unwrap(),
                 expected_result,
                 "Failed for {encoded_str:?}",
             );
         }
     }
 
+    #[test]
+    fn decode_quoted_printable_mime_malformed() {
+        for (encoded_str, boundary, expected_result, expected_end, has_errors) in [
+            ("dangling=", "", "dangling", 9, false),
+            ("dangling= \t", "", "dangling", 11, false),
+            (
+                "soft=\r\nbreaks=\nare=\requal",
+                "",
+                "softbreaksareequal",
+                25,
+                false,
+            ),
+            (
+                "bare\rcr\r\nlines\r",
+                "",
+                "bare\r\ncr\r\nlines\r\n",
+                15,
+                false,
+            ),
+            ("soft=\n--boundary", "boundary", "soft", 5, false),
+            ("bare cr\r--boundary", "boundary", "bare cr", 7, false),
+            ("invalid=XYhex", "", "invalid=XYhex", 13, true),
+            ("truncated=4", "", "truncated=4", 11, true),
+            ("truncated=4\nline", "", "truncated=4\nline", 16, true),
+            ("not = escaped", "", "not = escaped", 13, true),
+            ("==41=\n", "", "=A", 6, true),
+        ] {
+            let mut s = MessageStream::new(encoded_str.as_bytes());
+            let (bytes_read, result) = s.decode_quoted_printable_mime(boundary.as_bytes());
+            assert_eq!(
+                core::str::from_utf8(result.as_ref()).unwrap(),
+                expected_result,
+                "Failed for {encoded_str:?}",
+            );
+            assert_eq!(bytes_read, expected_end, "Failed for {encoded_str:?}");
+            assert_eq!(
+                s.has_decode_errors, has_errors,
+                "Failed for {encoded_str:?}"
+            );
+        }
+    }
+
     #[test]
     fn decode_quoted_printable_word() {
         for (encoded_str, expected_result) in [
@@ -0,0 +1,200 @@
+/*
+ * Copyright Stalwart Labs Ltd. See the COPYING
+ * file at the top-level directory of this distribution.
+ *
+ * Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+ * https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+ * <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+ * option. This file may not be copied, modified, or distributed
+ * except according to those terms.
+ */
+
+use alloc::{string::String, vec::Vec};
+
+/// A file embedded in a text body as a `begin`/`end` uuencoded block.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde_support",
+    derive(serde::Serialize, serde::Deserialize)
+)]
+pub struct UuFile {
+    pub mode: u32,
+    pub filename: String,
+    pub data: Vec<u8>,
+}
+
+/// Extracts the uuencoded blocks found in a text body, returning the text
+/// with the blocks removed along with the decoded files, or `None` when the
+/// text contains no valid block. Blocks without an `end` line or containing
+/// characters outside the uuencode alphabet are left in the text.
+pub fn uudecode(text: &str) -> Option<(String, Vec<UuFile>)> {
+    let mut files = Vec::new();
+    let mut remaining = String::new();
+    let mut text_pos = 0;
+    let mut lines = text.split_inclusive('\n');
+    let mut line_start = 0;
+
+    while let Some(line) = lines.next() {
+        let block_start = line_start;
+        line_start += line.len();
+
+        let (mode, filename) = match parse_begin(line.trim_end()) {
+            Some(begin) => begin,
+            None => continue,
+        };
+
+        let mut data = Vec::new();
+        let mut block_end = None;
+        let mut block_lines = lines.clone();
+        let mut pos = line_start;
+        for line in block_lines.by_ref() {
+            pos += line.len();
+            let line = line.trim_end_matches(['\n', '\r']);
+            if line.trim_end() == "end" {
+                block_end = Some(pos);
+                break;
+            } else if !uudecode_line(line.as_bytes(), &mut data) {
+                break;
+            }
+        }
+
+        if let Some(block_end) = block_end {
+            remaining.push_str(&text[text_pos..block_start]);
+            text_pos = block_end;
+            line_start = block_end;
+            lines = block_lines;
+            files.push(UuFile {
+                mode,
+                filename: filename.into(),
+                data,
+            });
+        }
+    }
+
+    if !files.is_empty() {
+        remaining.push_str(&text[text_pos..]);
+        Some((remaining, files))
+    } else {
+        None
+    }
+}
+
+/// Parses a `begin <mode> <filename>` line.
+fn parse_begin(line: &str) -> Option<(u32, &str)> {
+    let (mode, filename) = line.strip_prefix("begin ")?.trim_start().split_once(' ')?;
+    let filename = filename.trim();
+    if (1..=4).contains(&mode.len()) && !filename.is_empty() {
+        Some((u32::from_str_radix(mode, 8).ok()?, filename))
+    } else {
+        None
+    }
+}
+
+/// Decodes a length-prefixed uuencoded line. Both spaces and backticks are
+/// accepted as zero and characters missing at the end of the line, usually
+/// trailing spaces removed in transit, are also decoded as zero.
+fn uudecode_line(line: &[u8], data: &mut Vec<u8>) -> bool {
+    let (len, line) = match line.split_first() {
+        Some((&len, line)) => (len, line),
+        None => return true,
+    };
+    let len = match uudecode_char(len) {
+        Some(len) => len as usize,
+        None => return false,
+    };
+
+    let mut decoded = Vec::with_capacity(len + 2);
+    let mut chars = line.iter();
+    while decoded.len() < len {
+        let mut group = [0u8; 4];
+        for value in group.iter_mut() {
+            if let Some(&ch) = chars.next() {
+                *value = match uudecode_char(ch) {
+                    Some(value) => value,
+                    None => return false,
+                };
+            }
+        }
+        decoded.push((group[0] << 2) | (group[1] >> 4));
+        decoded.push((group[1] << 4) | (group[2] >> 2));
+        decoded.push((group[2] << 6) | group[3]);
+    }
+
+    data.extend_from_slice(&decoded[..len]);
+    true
+}
+
+#[inline(always)]
+fn uudecode_char(ch: u8) -> Option<u8> {
+    if (b' '..=b'`').contains(&ch) {
+        Some((ch - b' ') & 0x3f)
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{uudecode, UuFile};
+
+    #[test]
+    fn decode_uuencoded() {
+        for (input, expected_text, expected_files) in [
+            (
+                concat!(
+                    "See attached.\n\n",
+                    "begin 644 cat.txt\n",
+                    "#0V%T\n",
+                    "`\n",
+                    "end\n",
+                    "Bye.\n"
+                ),
+                "See attached.\n\nBye.\n",
+                vec![(0o644, "cat.txt", &b"Cat"[..])],
+            ),
+            (
+                // Space padding, trailing spaces removed and CRLF line endings
+                concat!(
+                    "begin 600 a b.bin\r\n",
+                    "$     \r\n",
+                    "!80\r\n",
+                    " \r\n",
+                    "end\r\n",
+                    "begin 755 run.sh\r\n",
+                    "&(R$O8FEN\r\n",
+                    "end"
+                ),
+                "",
+                vec![
+                    (0o600, "a b.bin", &b"\0\0\0\0a"[..]),
+                    (0o755, "run.sh", &b"#!/bin"[..]),
+                ],
+            ),
+        ] {
+            let (text, files) = uudecode(input).unwrap();
+            assert_eq!(text, expected_text, "failed for {input:?}");
+            assert_eq!(
+                files,
+                expected_files
+                    .into_iter()
+                    .map(|(mode, filename, data)| UuFile {
+                        mode,
+                        filename: filename.into(),
+                        data: data.to_vec(),
+                    })
+                    .collect::<Vec<_>>(),
+                "failed for {input:?}"
+            );
+        }
+
+        for input in [
+            "No blocks here.\n",
+            "begin 644 truncated.txt\n#0V%T\n",
+            "begin 644 invalid.txt\n#0V~T\nend\n",
+            "begin abc notes.txt\n#0V%T\nend\n",
+            "begin 644\n#0V%T\nend\n",
+        ] {
+            assert_eq!(uudecode(input), None, "failed for {input:?}");
+        }
+    }
+}
@@ -157,13 +157,10 @@
 //!    let message = MessageParser::default().parse(input).unwrap();
 //!
 //!    // Parses addresses (including comments), lists and groups
-//!    assert_eq!(
-//!        message.from().unwrap().first().unwrap(),
-//!        &Addr::new(
-//!            "Art Vandelay (Vandelay Industries)".into(),
-//!            "art@vandelay.com"
-//!        )
-//!    );
+//!    let from = message.from().unwrap().first().unwrap();
+//!    assert_eq!(from.name(), Some("Art Vandelay"));
+//!    assert_eq!(from.address(), Some("art@vandelay.com"));
+//!    assert_eq!(from.comment(), Some("Vandelay Industries"));
 //!    
 //!    assert_eq!(
 //!        message.to().unwrap().as_group().unwrap(),
@@ -247,12 +244,18 @@
 //!    // Integrates with Serde
 //!    println!("{}", serde_json::to_string_pretty(&message).unwrap());
 //!```
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
+
+extern crate alloc;
+
 pub mod core;
 pub mod decoders;
+#[cfg(feature = "std")]
 pub mod mailbox;
 pub mod parsers;
 
-use std::{borrow::Cow, collections::HashMap, hash::Hash, net::IpAddr};
+use ::core::{hash::Hash, net::IpAddr};
+use alloc::{borrow::Cow, boxed::Box, collections::BTreeMap, string::String, vec::Vec};
 
 use parsers::MessageStream;
 #[cfg(feature = "serde_support")]
@@ -260,9 +263,59 @@ use serde::{Deserialize, Serialize};
 
 /// RFC5322/RFC822 message parser.
 #[derive(Debug, PartialEq, Eq, Clone)]
+#[allow(unpredictable_function_pointer_comparisons)]
 pub struct MessageParser {
-    pub(crate) header_map: HashMap<HeaderName<'static>, HdrParseFnc>,
-    pub(crate) def_hdr_parse_fnc: HdrParseFnc,
+    pub(crate) header_map: BTreeMap<HeaderName<'static>, HdrParseFnc>,
+    pub(crate) def_hdr_parse_fnc: Option<HdrParseFnc>,
+    pub(crate) max_nested_messages: usize,
+    pub(crate) max_multipart_depth: usize,
+    pub(crate) decode_flowed: bool,
+    pub(crate) default_charset: Option<decoders::charsets::DecoderFnc>,
+    pub(crate) preserve_raw_headers: bool,
+    pub(crate) raw_bodies: bool,
+    pub(crate) header_index: bool,
+    pub(crate) max_headers: usize,
+    pub(crate) max_header_bytes: usize,
+    pub(crate) max_size: usize,
+    pub(crate) uudecode: bool,
+    pub(crate) preserve_whitespace: bool,
+    #[cfg(feature = "binhex")]
+    pub(crate) binhex: bool,
+    #[cfg(feature = "detect_charset")]
+    pub(crate) detect_charset: bool,
+}
+
+/// Error returned by [`MessageParser::try_parse`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ParseError {
+    /// The message is larger than the limit set with [`MessageParser::with_max_size`]
+    TooLarge,
+    /// The message is empty or only contains whitespace
+    Empty,
+    /// No header fields were found in the message
+    NoHeaders,
+}
+
+/// Pool of vectors reused across calls to [`MessageParser::parse_into`].
+///
+/// Parsed messages own their vectors, so no borrow of the pool outlives a
+/// call: once a message is no longer needed, handing it to
+/// [`MessageBuffers::recycle`] returns its vectors to the pool so that the
+/// next message parsed can reuse their allocations.
+#[derive(Debug, Default)]
+pub struct MessageBuffers {
+    pub(crate) parts: Vec<Vec<MessagePart<'static>>>,
+    pub(crate) headers: Vec<Vec<Header<'static>>>,
+    pub(crate) part_ids: Vec<Vec<MessagePartId>>,
+}
+
+/// Positions of each header name in `MessagePart::headers`, built when
+/// [`MessageParser::with_header_index`] is enabled.
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct HeaderIndex<'x> {
+    pub(crate) len: usize,
+    pub(crate) positions: BTreeMap<HeaderName<'x>, Vec<usize>>,
 }
 
 pub(crate) type HdrParseFnc = for<'x> fn(&mut MessageStream<'x>) -> crate::HeaderValue<'x>;
@@ -301,6 +354,18 @@ pub struct MessagePart<'x> {
     pub offset_header: usize,
     pub offset_body: usize,
     pub offset_end: usize,
+    #[cfg_attr(feature = "serde_support", serde(default))]
+    pub line_count: usize,
+    #[cfg_attr(feature = "serde_support", serde(skip))]
+    pub header_index: Option<Box<HeaderIndex<'x>>>,
+    #[cfg_attr(feature = "serde_support", serde(skip))]
+    pub decoded_charset: Option<Cow<'static, str>>,
+    #[cfg_attr(feature = "serde_support", serde(skip))]
+    pub is_header_overflow: bool,
+    #[cfg_attr(feature = "serde_support", serde(skip))]
+    pub uuencode_mode: Option<u32>,
+    #[cfg_attr(feature = "serde_support", serde(skip))]
+    pub binhex: Option<Box<BinHexHeader>>,
 }
 
 /// MIME Part encoding type
@@ -344,12 +409,22 @@ pub enum PartType<'x> {
     Html(Cow<'x, str>),
 
     /// Any other part type that is not text.
-    #[cfg_attr(feature = "serde_support", serde(borrow))]
-    Binary(Cow<'x, [u8]>),
+    Binary(
+        #[cfg_attr(
+            feature = "serde_support",
+            serde(borrow, deserialize_with = "core::body::deserialize_bytes")
+        )]
+        Cow<'x, [u8]>,
+    ),
 
     /// Any inline binary data that.
-    #[cfg_attr(feature = "serde_support", serde(borrow))]
-    InlineBinary(Cow<'x, [u8]>),
+    InlineBinary(
+        #[cfg_attr(
+            feature = "serde_support",
+            serde(borrow, deserialize_with = "core::body::deserialize_bytes")
+        )]
+        Cow<'x, [u8]>,
+    ),
 
     /// Nested RFC5322 message.
     Message(Message<'x>),
@@ -368,13 +443,20 @@ impl<'x> Default for PartType<'x> {
 #[derive(Debug, PartialEq, Eq, Clone)]
 #[cfg_attr(feature = "serde_support", derive(Serialize, Deserialize))]
 pub struct Addr<'x> {
-    /// The address name including comments
+    /// The display name, or the comment when there is no display name
     #[cfg_attr(feature = "serde_support", serde(default))]
     pub name: Option<Cow<'x, str>>,
 
     /// An e-mail address (RFC5322/RFC2369) or URL (RFC2369)
     #[cfg_attr(feature = "serde_support", serde(default))]
     pub address: Option<Cow<'x, str>>,
+
+    /// The text of the comments found in the address, such as `the boss`
+    /// in `John Doe (the boss) <john@example.org>`. When the address has no
+    /// display name, as in `john@example.org (John Doe)`, the comment is
+    /// also copied into `name`.
+    #[cfg_attr(feature = "serde_support", serde(default))]
+    pub comment: Option<Cow<'x, str>>,
 }
 
 /// An RFC5322 address group.
@@ -399,10 +481,12 @@ pub struct Header<'x> {
     pub offset_field: usize,
     pub offset_start: usize,
     pub offset_end: usize,
+    #[cfg_attr(feature = "serde_support", serde(skip))]
+    pub raw: Option<Cow<'x, [u8]>>,
 }
 
 /// A header field
-#[derive(Debug, Clone, PartialOrd, Ord)]
+#[derive(Debug, Clone)]
 #[cfg_attr(feature = "serde_support", derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "serde_support", serde(rename_all = "snake_case"))]
 pub enum HeaderName<'x> {
@@ -492,6 +576,30 @@ pub enum HeaderForm {
     Date,
     URLs,
 }
+
+/// Header field parser selected with [`MessageParser::with_header_parser`]
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub enum HeaderParser {
+    /// Text decoding RFC 2047 encoded words
+    Text,
+    /// Raw string, no RFC 2047 decoding is done
+    Raw,
+    /// Address list or group
+    Address,
+    /// RFC 5322 date
+    Date,
+    /// Message ID or list of IDs
+    Id,
+    /// MIME `Content-Type` or `Content-Disposition` type
+    ContentType,
+    /// Comma-separated list of values
+    CommaSeparated,
+    /// Received trace field
+    Received,
+    /// Skip the header without parsing its value
+    Ignore,
+}
+
 /// An RFC2047 Content-Type or RFC2183 Content-Disposition MIME header field.
 #[derive(Debug, PartialEq, Eq, Clone)]
 #[cfg_attr(feature = "serde_support", derive(Serialize, Deserialize))]
@@ -503,6 +611,56 @@ pub struct ContentType<'x> {
     pub attributes: Option<Vec<(Cow<'x, str>, Cow<'x, str>)>>,
 }
 
+/// The disposition type of an RFC2183 Content-Disposition field.
+#[derive(Debug, PartialEq, Eq, Clone)]
+#[cfg_attr(feature = "serde_support", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde_support", serde(rename_all = "snake_case"))]
+pub enum DispositionType<'x> {
+    Inline,
+    Attachment,
+    Other(&'x str),
+}
+
+/// A structured view of an RFC2183 Content-Disposition field.
+#[derive(Debug, PartialEq, Eq, Clone)]
+#[cfg_attr(feature = "serde_support", derive(Serialize, Deserialize))]
+pub struct ContentDisposition<'x> {
+    #[cfg_attr(feature = "serde_support", serde(borrow))]
+    pub disposition_type: DispositionType<'x>,
+    #[cfg_attr(feature = "serde_support", serde(default))]
+    pub filename: Option<&'x str>,
+    #[cfg_attr(feature = "serde_support", serde(default))]
+    pub creation_date: Option<DateTime>,
+    #[cfg_attr(feature = "serde_support", serde(default))]
+    pub modification_date: Option<DateTime>,
+    #[cfg_attr(feature = "serde_support", serde(default))]
+    pub read_date: Option<DateTime>,
+    #[cfg_attr(feature = "serde_support", serde(default))]
+    pub size: Option<usize>,
+}
+
+/// The Macintosh file information of a BinHex 4.0 encoded part.
+#[derive(Debug, PartialEq, Eq, Clone)]
+#[cfg_attr(feature = "serde_support", derive(Serialize, Deserialize))]
+pub struct BinHexHeader {
+    pub filename: String,
+    pub file_type: [u8; 4],
+    pub creator: [u8; 4],
+    pub flags: u16,
+}
+
+/// A Microsoft Thread-Index header field.
+#[derive(Debug, PartialEq, Eq, Clone)]
+#[cfg_attr(feature = "serde_support", derive(Serialize, Deserialize))]
+pub struct ThreadIndex {
+    /// Time at which the conversation was started
+    pub date: DateTime,
+    /// Identifier shared by all messages of the conversation
+    pub guid: [u8; 16],
+    /// Number of child blocks, one for each reply or forward in the conversation
+    pub child_blocks: usize,
+}
+
 /// An RFC5322 datetime.
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 #[cfg_attr(feature = "serde_support", derive(Serialize, Deserialize))]
@@ -518,6 +676,169 @@ pub struct DateTime {
     pub tz_minute: u8,
 }
 
+/// Error returned when a [`DateTime`] cannot be represented by the
+/// `chrono` or `time` date types, either because it is not valid or
+/// because its fields or offset are out of range.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InvalidDateTime;
+
+/// An RFC2919 List-Id header.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde_support", derive(Serialize, Deserialize))]
+pub struct ListId<'x> {
+    /// The human-readable description of the list, if any
+    #[cfg_attr(feature = "serde_support", serde(default))]
+    pub description: Option<Cow<'x, str>>,
+    /// The list identifier, without the angle brackets
+    pub id: Cow<'x, str>,
+}
+
+/// The priority of a message, as signalled by its `Importance`,
+/// `X-Priority` or `Priority` header fields.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde_support", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde_support", serde(rename_all = "snake_case"))]
+pub enum Priority {
+    High,
+    #[default]
+    Normal,
+    Low,
+}
+
+/// A block of RFC5322 Resent-* header fields added by a single resending.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct ResentBlock<'x> {
+    pub date: Option<&'x DateTime>,
+    pub from: Option<&'x Address<'x>>,
+    pub sender: Option<&'x Address<'x>>,
+    pub to: Option<&'x Address<'x>>,
+    pub cc: Option<&'x Address<'x>>,
+    pub bcc: Option<&'x Address<'x>>,
+    pub message_id: Option<&'x str>,
+}
+
+/// An RFC3464 `message/delivery-status` body. Field values are returned
+/// unfolded and include their type prefix, such as `dns; mx.example.com`.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde_support", derive(Serialize, Deserialize))]
+pub struct DeliveryStatus<'x> {
+    #[cfg_attr(feature = "serde_support", serde(default))]
+    pub reporting_mta: Option<Cow<'x, str>>,
+    #[cfg_attr(feature = "serde_support", serde(default))]
+    pub original_envelope_id: Option<Cow<'x, str>>,
+    #[cfg_attr(feature = "serde_support", serde(default))]
+    pub recipients: Vec<RecipientStatus<'x>>,
+}
+
+/// The per-recipient fields of an RFC3464 delivery status notification.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde_support", derive(Serialize, Deserialize))]
+pub struct RecipientStatus<'x> {
+    #[cfg_attr(feature = "serde_support", serde(default))]
+    pub original_recipient: Option<Cow<'x, str>>,
+    #[cfg_attr(feature = "serde_support", serde(default))]
+    pub final_recipient: Option<Cow<'x, str>>,
+    #[cfg_attr(feature = "serde_support", serde(default))]
+    pub action: Option<Cow<'x, str>>,
+    #[cfg_attr(feature = "serde_support", serde(default))]
+    pub status: Option<Cow<'x, str>>,
+    #[cfg_attr(feature = "serde_support", serde(default))]
+    pub remote_mta: Option<Cow<'x, str>>,
+    #[cfg_attr(feature = "serde_support", serde(default))]
+    pub diagnostic_code: Option<Cow<'x, str>>,
+}
+
+/// An RFC6376 DKIM-Signature header.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde_support", derive(Serialize, Deserialize))]
+pub struct DkimSignature<'x> {
+    /// Tags and their values in the order they appear, with all whitespace removed
+    pub tags: Vec<(Cow<'x, str>, Cow<'x, str>)>,
+    /// The header field names listed in the `h=` tag
+    #[cfg_attr(feature = "serde_support", serde(default))]
+    pub signed_headers: Vec<Cow<'x, str>>,
+}
+
+/// An Autocrypt Level 1 header.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde_support", derive(Serialize, Deserialize))]
+pub struct Autocrypt<'x> {
+    /// The e-mail address the key belongs to (`addr=` attribute)
+    pub addr: Cow<'x, str>,
+    /// The encryption preference (`prefer-encrypt=` attribute)
+    #[cfg_attr(feature = "serde_support", serde(default))]
+    pub prefer_encrypt: Option<Cow<'x, str>>,
+    /// The base64 encoded OpenPGP key (`keydata=` attribute), with all whitespace removed
+    pub keydata: Cow<'x, str>,
+}
+
+/// An RFC8601 Authentication-Results header.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde_support", derive(Serialize, Deserialize))]
+pub struct AuthenticationResults<'x> {
+    pub authserv_id: Cow<'x, str>,
+    #[cfg_attr(feature = "serde_support", serde(default))]
+    pub version: Option<u32>,
+    #[cfg_attr(feature = "serde_support", serde(default))]
+    pub results: Vec<AuthenticationResult<'x>>,
+}
+
+/// The result of an authentication method, such as `spf=pass`.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde_support", derive(Serialize, Deserialize))]
+pub struct AuthenticationResult<'x> {
+    pub method: Cow<'x, str>,
+    #[cfg_attr(feature = "serde_support", serde(default))]
+    pub method_version: Option<u32>,
+    pub result: Cow<'x, str>,
+    #[cfg_attr(feature = "serde_support", serde(default))]
+    pub reason: Option<Cow<'x, str>>,
+    #[cfg_attr(feature = "serde_support", serde(default))]
+    pub properties: Vec<AuthenticationProperty<'x>>,
+}
+
+/// A `ptype.property=value` pair of an authentication method result,
+/// such as `smtp.mailfrom=example.com`.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde_support", derive(Serialize, Deserialize))]
+pub struct AuthenticationProperty<'x> {
+    pub ptype: Cow<'x, str>,
+    pub property: Cow<'x, str>,
+    pub value: Cow<'x, str>,
+}
+
+/// A problem found while parsing a message, returned by
+/// [`MessageParser::parse_with_diagnostics`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde_support", derive(Serialize, Deserialize))]
+pub struct Diagnostic<'x> {
+    /// Index of the part in `Message::parts`, or of the part containing
+    /// the nested message where the problem was found
+    pub part: usize,
+    /// The malformed header, or `None` for problems in the part body
+    pub header: Option<HeaderName<'x>>,
+    /// Byte offset of the problem in the raw message
+    pub offset: usize,
+    pub reason: DiagnosticReason,
+}
+
+/// The reason of a [`Diagnostic`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde_support", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde_support", serde(rename_all = "snake_case"))]
+pub enum DiagnosticReason {
+    InvalidDate,
+    InvalidAddress,
+    InvalidMessageId,
+    InvalidContentType,
+    UnterminatedEncodedWord,
+    InvalidBase64,
+    InvalidQuotedPrintable,
+    InvalidNestedMessage,
+    MissingBoundary,
+    MissingBody,
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Default)]
 #[cfg_attr(feature = "serde_support", derive(Serialize, Deserialize))]
 pub struct Received<'x> {
@@ -616,21 +937,40 @@ pub enum Protocol {
 
 /// MIME Header field access trait
 pub trait MimeHeaders<'x> {
-    /// Returns the Content-Description field
+    /// Returns the Content-Description field, with RFC2047 encoded-words
+    /// decoded and any folding removed
     fn content_description(&self) -> Option<&str>;
     /// Returns the Content-Disposition field
-    fn content_disposition(&self) -> Option<&ContentType>;
+    fn content_disposition(&self) -> Option<&ContentType<'_>>;
+    /// Returns the Content-Disposition field as a structured value, with its
+    /// date parameters parsed and its size parameter converted to an integer
+    fn disposition(&self) -> Option<ContentDisposition<'_>> {
+        self.content_disposition()
+            .map(ContentType::to_content_disposition)
+    }
     /// Returns the Content-ID field
     fn content_id(&self) -> Option<&str>;
     /// Returns the Content-Encoding field
     fn content_transfer_encoding(&self) -> Option<&str>;
     /// Returns the Content-Type field
-    fn content_type(&self) -> Option<&ContentType>;
+    fn content_type(&self) -> Option<&ContentType<'_>>;
     /// Returns the Content-Language field
-    fn content_language(&self) -> &HeaderValue;
+    fn content_language(&self) -> &HeaderValue<'_>;
+    /// Returns the language tags listed in the Content-Language field
+    fn content_languages(&self) -> &[Cow<'_, str>] {
+        match self.content_language() {
+            HeaderValue::Text(language) => ::core::slice::from_ref(language),
+            HeaderValue::TextList(languages) => languages,
+            _ => &[],
+        }
+    }
     /// Returns the Content-Location field
     fn content_location(&self) -> Option<&str>;
     /// Returns the attachment name, if any.
+    ///
+    /// The `filename` parameter of the Content-Disposition field is used first,
+    /// followed by the `name` parameter of the Content-Type field. RFC2231 parameters
+    /// take precedence over regular ones and both RFC2231 and RFC2047 values are decoded.
     fn attachment_name(&self) -> Option<&str> {
         self.content_disposition()
             .and_then(|cd| cd.attribute("filename"))
@@ -638,19 +978,19 @@ pub trait MimeHeaders<'x> {
     }
     // Returns true is the content type matches
     fn is_content_type(&self, type_: &str, subtype: &str) -> bool {
-        self.content_type().map_or(false, |ct| {
+        self.content_type().is_some_and(|ct| {
             ct.c_type.eq_ignore_ascii_case(type_)
                 && ct
                     .c_subtype
                     .as_ref()
-                    .map_or(false, |st| st.eq_ignore_ascii_case(subtype))
+                    .is_some_and(|st| st.eq_ignore_ascii_case(subtype))
         })
     }
 }
 
 pub trait GetHeader<'x> {
-    fn header_value(&self, name: &HeaderName) -> Option<&HeaderValue>;
-    fn header(&self, name: impl Into<HeaderName<'x>>) -> Option<&Header>;
+    fn header_value(&self, name: &HeaderName) -> Option<&HeaderValue<'_>>;
+    fn header(&self, name: impl Into<HeaderName<'x>>) -> Option<&Header<'_>>;
 }
 
 #[doc(hidden)]
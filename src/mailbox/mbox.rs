@@ -10,7 +10,10 @@
  */
 
 use crate::DateTime;
-use std::io::{BufRead, BufReader, Read};
+use std::{
+    borrow::Cow,
+    io::{BufRead, BufReader, Read},
+};
 
 /// Parses an Mbox mailbox from a `Read` stream, returning each message as a
 /// `Vec<u8>`.
@@ -31,6 +34,33 @@ pub struct Message {
 #[derive(Debug)]
 pub struct ParseError {}
 
+/// Splits an Mbox mailbox held in memory, lazily yielding each message
+/// as a [`MessageSlice`] that borrows from the mailbox contents.
+pub struct MessageSlices<'x> {
+    data: &'x [u8],
+    pos: usize,
+    quoting: Quoting,
+}
+
+/// Convention used to quote `From ` lines inside message bodies
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub enum Quoting {
+    /// Only `>From ` lines are unquoted
+    Mboxo,
+    /// Any number of `>` followed by `From ` is unquoted by one level
+    #[default]
+    Mboxrd,
+}
+
+/// Mbox message contents and metadata borrowed from the mailbox contents
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct MessageSlice<'x> {
+    internal_date: u64,
+    from: &'x str,
+    raw: &'x [u8],
+    contents: Cow<'x, [u8]>,
+}
+
 impl<T> MessageIterator<T>
 where
     T: Read,
@@ -105,98 +135,215 @@ where
     }
 }
 
-impl Message {
-    fn new(hdr: &str) -> Self {
-        let (internal_date, from) = if let Some((from, date)) = hdr
-            .strip_prefix("From ")
-            .and_then(|hdr| hdr.split_once(' '))
-        {
-            let mut dt = DateTime {
-                year: u16::MAX,
-                month: u8::MAX,
-                day: u8::MAX,
-                hour: u8::MAX,
-                minute: u8::MAX,
-                second: u8::MAX,
-                tz_before_gmt: false,
-                tz_hour: 0,
-                tz_minute: 0,
-            };
-
-            for (pos, part) in date.split_whitespace().enumerate() {
-                match pos {
-                    1 => {
-                        dt.month = if part.eq_ignore_ascii_case("jan") {
-                            1
-                        } else if part.eq_ignore_ascii_case("feb") {
-                            2
-                        } else if part.eq_ignore_ascii_case("mar") {
-                            3
-                        } else if part.eq_ignore_ascii_case("apr") {
-                            4
-                        } else if part.eq_ignore_ascii_case("may") {
-                            5
-                        } else if part.eq_ignore_ascii_case("jun") {
-                            6
-                        } else if part.eq_ignore_ascii_case("jul") {
-                            7
-                        } else if part.eq_ignore_ascii_case("aug") {
-                            8
-                        } else if part.eq_ignore_ascii_case("sep") {
-                            9
-                        } else if part.eq_ignore_ascii_case("oct") {
-                            10
-                        } else if part.eq_ignore_ascii_case("nov") {
-                            11
-                        } else if part.eq_ignore_ascii_case("dec") {
-                            12
-                        } else {
-                            u8::MAX
-                        };
-                    }
-                    2 => {
-                        dt.day = part.parse().unwrap_or(u8::MAX);
-                    }
-                    3 => {
-                        for (pos, part) in part.split(':').enumerate() {
-                            match pos {
-                                0 => {
-                                    dt.hour = part.parse().unwrap_or(u8::MAX);
-                                }
-                                1 => {
-                                    dt.minute = part.parse().unwrap_or(u8::MAX);
-                                }
-                                2 => {
-                                    dt.second = part.parse().unwrap_or(u8::MAX);
-                                }
-                                _ => {
-                                    break;
-                                }
+impl<'x> MessageSlices<'x> {
+    pub fn new(data: &'x [u8]) -> MessageSlices<'x> {
+        MessageSlices {
+            data,
+            pos: 0,
+            quoting: Quoting::default(),
+        }
+    }
+
+    /// Sets the convention used to unquote `From ` lines, defaults to [`Quoting::Mboxrd`]
+    pub fn with_quoting(mut self, quoting: Quoting) -> Self {
+        self.quoting = quoting;
+        self
+    }
+
+    fn next_line(&self, pos: usize) -> usize {
+        self.data[pos..]
+            .iter()
+            .position(|&ch| ch == b'\n')
+            .map_or(self.data.len(), |end| pos + end + 1)
+    }
+}
+
+impl<'x> Iterator for MessageSlices<'x> {
+    type Item = MessageSlice<'x>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        // Skip any contents before the first separator
+        while !self.data[self.pos..].starts_with(b"From ") {
+            if self.pos >= self.data.len() {
+                return None;
+            }
+            self.pos = self.next_line(self.pos);
+        }
+
+        let hdr_end = self.next_line(self.pos);
+        let (internal_date, from) =
+            parse_separator(std::str::from_utf8(&self.data[self.pos..hdr_end]).unwrap_or(""));
+
+        let start = hdr_end;
+        let mut end = start;
+        while end < self.data.len() && !self.data[end..].starts_with(b"From ") {
+            end = self.next_line(end);
+        }
+        self.pos = end;
+
+        let raw = &self.data[start..end];
+        Some(MessageSlice {
+            internal_date,
+            from,
+            raw,
+            contents: unquote(raw, self.quoting),
+        })
+    }
+}
+
+impl<'x> MessageSlice<'x> {
+    /// Returns the message creation date in UTC seconds since UNIX epoch
+    pub fn internal_date(&self) -> u64 {
+        self.internal_date
+    }
+
+    /// Returns the message sender address
+    pub fn from(&self) -> &'x str {
+        self.from
+    }
+
+    /// Returns the message contents with `From ` lines unquoted
+    pub fn contents(&self) -> &[u8] {
+        &self.contents
+    }
+
+    /// Returns the message contents as they appear in the mailbox
+    pub fn raw_contents(&self) -> &'x [u8] {
+        self.raw
+    }
+
+    /// Unwraps the message contents
+    pub fn into_contents(self) -> Cow<'x, [u8]> {
+        self.contents
+    }
+}
+
+fn is_quoted_from(line: &[u8], quoting: Quoting) -> bool {
+    match quoting {
+        Quoting::Mboxo => line.starts_with(b">From "),
+        Quoting::Mboxrd => {
+            line.first() == Some(&b'>')
+                && line
+                    .iter()
+                    .position(|&ch| ch != b'>')
+                    .is_some_and(|pos| line[pos..].starts_with(b"From "))
+        }
+    }
+}
+
+fn unquote(raw: &[u8], quoting: Quoting) -> Cow<'_, [u8]> {
+    let mut lines = raw.split_inclusive(|&ch| ch == b'\n');
+    if !lines.clone().any(|line| is_quoted_from(line, quoting)) {
+        return Cow::Borrowed(raw);
+    }
+
+    let mut contents = Vec::with_capacity(raw.len());
+    for line in &mut lines {
+        if is_quoted_from(line, quoting) {
+            contents.extend_from_slice(&line[1..]);
+        } else {
+            contents.extend_from_slice(line);
+        }
+    }
+    Cow::Owned(contents)
+}
+
+fn parse_separator(hdr: &str) -> (u64, &str) {
+    if let Some((from, date)) = hdr
+        .strip_prefix("From ")
+        .and_then(|hdr| hdr.split_once(' '))
+    {
+        let mut dt = DateTime {
+            year: u16::MAX,
+            month: u8::MAX,
+            day: u8::MAX,
+            hour: u8::MAX,
+            minute: u8::MAX,
+            second: u8::MAX,
+            tz_before_gmt: false,
+            tz_hour: 0,
+            tz_minute: 0,
+        };
+
+        for (pos, part) in date.split_whitespace().enumerate() {
+            match pos {
+                1 => {
+                    dt.month = if part.eq_ignore_ascii_case("jan") {
+                        1
+                    } else if part.eq_ignore_ascii_case("feb") {
+                        2
+                    } else if part.eq_ignore_ascii_case("mar") {
+                        3
+                    } else if part.eq_ignore_ascii_case("apr") {
+                        4
+                    } else if part.eq_ignore_ascii_case("may") {
+                        5
+                    } else if part.eq_ignore_ascii_case("jun") {
+                        6
+                    } else if part.eq_ignore_ascii_case("jul") {
+                        7
+                    } else if part.eq_ignore_ascii_case("aug") {
+                        8
+                    } else if part.eq_ignore_ascii_case("sep") {
+                        9
+                    } else if part.eq_ignore_ascii_case("oct") {
+                        10
+                    } else if part.eq_ignore_ascii_case("nov") {
+                        11
+                    } else if part.eq_ignore_ascii_case("dec") {
+                        12
+                    } else {
+                        u8::MAX
+                    };
+                }
+                2 => {
+                    dt.day = part.parse().unwrap_or(u8::MAX);
+                }
+                3 => {
+                    for (pos, part) in part.split(':').enumerate() {
+                        match pos {
+                            0 => {
+                                dt.hour = part.parse().unwrap_or(u8::MAX);
+                            }
+                            1 => {
+                                dt.minute = part.parse().unwrap_or(u8::MAX);
+                            }
+                            2 => {
+                                dt.second = part.parse().unwrap_or(u8::MAX);
+                            }
+                            _ => {
+                                break;
                             }
                         }
                     }
-                    4 => {
-                        dt.year = part.parse().unwrap_or(u16::MAX);
-                    }
-                    _ => (),
                 }
+                4 => {
+                    dt.year = part.parse().unwrap_or(u16::MAX);
+                }
+                _ => (),
             }
+        }
 
-            (
-                if dt.is_valid() {
-                    dt.to_timestamp() as u64
-                } else {
-                    0
-                },
-                from.trim().to_string(),
-            )
-        } else {
-            (0, "".to_string())
-        };
+        (
+            if dt.is_valid() {
+                dt.to_timestamp() as u64
+            } else {
+                0
+            },
+            from.trim(),
+        )
+    } else {
+        (0, "")
+    }
+}
+
+impl Message {
+    fn new(hdr: &str) -> Self {
+        let (internal_date, from) = parse_separator(hdr);
 
         Self {
             internal_date,
-            from,
+            from: from.to_string(),
             contents: Vec::with_capacity(1024),
         }
     }
@@ -224,9 +371,11 @@ impl Message {
 
 #[cfg(test)]
 mod tests {
+    use std::borrow::Cow;
+
     use crate::mailbox::mbox::Message;
 
-    use super::MessageIterator;
+    use super::{MessageIterator, MessageSlices, Quoting};
 
     #[test]
     fn parse_mbox() {
@@ -276,4 +425,55 @@ Message 4
             assert_eq!(message.unwrap(), expected_messages);
         }
     }
+
+    #[test]
+    fn parse_mbox_slices() {
+        let mailbox = br#"Preamble
+From god@heaven.af.mil Sat Jan  3 01:05:34 1996
+Message 1
+Fromage
+
+From test@test.com Tue Aug  6 13:34:34 2002
+Message 2
+>From hello
+>>From world
+>>>From test
+ From
+"#;
+
+        for (quoting, expected_contents) in [
+            (
+                Quoting::Mboxrd,
+                &b"Message 2\nFrom hello\n>From world\n>>From test\n From\n"[..],
+            ),
+            (
+                Quoting::Mboxo,
+                &b"Message 2\nFrom hello\n>>From world\n>>>From test\n From\n"[..],
+            ),
+        ] {
+            let messages = MessageSlices::new(&mailbox[..])
+                .with_quoting(quoting)
+                .collect::<Vec<_>>();
+            assert_eq!(messages.len(), 2);
+
+            assert_eq!(messages[0].internal_date(), 820631134);
+            assert_eq!(messages[0].from(), "god@heaven.af.mil");
+            assert_eq!(messages[0].contents(), b"Message 1\nFromage\n\n");
+            assert!(matches!(
+                messages[0].clone().into_contents(),
+                Cow::Borrowed(_)
+            ));
+
+            assert_eq!(messages[1].internal_date(), 1028640874);
+            assert_eq!(messages[1].from(), "test@test.com");
+            assert_eq!(messages[1].contents(), expected_contents);
+            assert_eq!(
+                messages[1].raw_contents(),
+                b"Message 2\n>From hello\n>>From world\n>>>From test\n From\n"
+            );
+        }
+
+        assert_eq!(MessageSlices::new(b"").count(), 0);
+        assert_eq!(MessageSlices::new(b"no separator\n").count(), 0);
+    }
 }
@@ -0,0 +1,468 @@
+/*
+ * Copyright Stalwart Labs Ltd. See the COPYING
+ * file at the top-level directory of this distribution.
+ *
+ * Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+ * https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+ * <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+ * option. This file may not be copied, modified, or distributed
+ * except according to those terms.
+ */
+
+use alloc::vec::Vec;
+use core::fmt::{self, Display};
+
+use crate::{
+    Diagnostic, DiagnosticReason, Encoding, Header, HeaderName, HeaderValue, Message,
+    MessageParser, MimeHeaders, PartType,
+};
+
+use super::{message::IntoByteSlice, MessageStream};
+
+impl MessageParser {
+    /// Parses a byte slice containing the RFC5322 raw message and returns the
+    /// parsed message along with the problems found while parsing it, such as
+    /// malformed header fields or badly encoded parts.
+    ///
+    /// The message is parsed exactly as with [`MessageParser::parse`].
+    pub fn parse_with_diagnostics<'x>(
+        &self,
+        raw_message: impl IntoByteSlice<'x>,
+    ) -> Option<(Message<'x>, Vec<Diagnostic<'x>>)> {
+        let message = self.parse(raw_message)?;
+        let mut diagnostics = Vec::new();
+        self.collect_diagnostics(&message, None, 0, &mut diagnostics);
+        Some((message, diagnostics))
+    }
+
+    /// Walks the parts of a message, `container` holds the id of the top-level part
+    /// containing a nested message and, for encoded messages, the offset to report.
+    fn collect_diagnostics<'x>(
+        &self,
+        message: &Message<'x>,
+        container: Option<(usize, Option<usize>)>,
+        depth: usize,
+        diagnostics: &mut Vec<Diagnostic<'x>>,
+    ) {
+        for (pos, part) in message.parts.iter().enumerate() {
+            let part_id = container.map_or(pos, |(part_id, _)| part_id);
+            let fixed_offset = container.and_then(|(_, offset)| offset);
+            let offset = |offset: usize| fixed_offset.unwrap_or(offset);
+
+            for header in part.headers.iter() {
+                if let Some((reason, header_offset)) =
+                    header_diagnostic(self, header, message.raw_message.as_ref())
+                {
+                    diagnostics.push(Diagnostic {
+                        part: part_id,
+                        header: Some(header.name.clone()),
+                        offset: offset(header_offset),
+                        reason,
+                    });
+                }
+            }
+
+            if let PartType::Message(nested) = &part.body {
+                let fixed_offset = fixed_offset
+                    .or_else(|| (part.encoding != Encoding::None).then_some(part.offset_body));
+                self.collect_diagnostics(
+                    nested,
+                    Some((part_id, fixed_offset)),
+                    depth + 1,
+                    diagnostics,
+                );
+            } else if part.is_encoding_problem {
+                let reason = if pos == 0
+                    && message.parts.len() == 1
+                    && part.offset_body == message.raw_message.len()
+                {
+                    DiagnosticReason::MissingBody
+                } else if part.is_content_type("message", "rfc822") {
+                    if depth >= self.max_nested_messages {
+                        // Nesting limit reached, the message is not malformed
+                        continue;
+                    }
+                    DiagnosticReason::InvalidNestedMessage
+                } else if part
+                    .content_type()
+                    .is_some_and(|ct| ct.ctype() == "multipart")
+                {
+                    // Multipart depth limit reached
+                    continue;
+                } else if part.encoding == Encoding::Base64
+                    && message
+                        .raw_message
+                        .get(part.offset_body..part.offset_end)
+                        .is_some_and(|bytes| {
+                            bytes.iter().any(|&ch| {
+                                !ch.is_ascii_alphanumeric()
+                                    && !matches!(ch, b'+' | b'/' | b'=')
+                                    && !ch.is_ascii_whitespace()
+                            })
+                        })
+                {
+                    // Bytes outside the base64 alphabet were skipped
+                    DiagnosticReason::InvalidBase64
+                } else if part.encoding == Encoding::QuotedPrintable
+                    && message
+                        .raw_message
+                        .get(part.offset_body..part.offset_end)
+                        .is_some_and(has_invalid_qp_escape)
+                {
+                    // Invalid escape sequences were kept as found
+                    DiagnosticReason::InvalidQuotedPrintable
+                } else if part.encoding != Encoding::None {
+                    // The part was decoded up to the end of the message
+                    DiagnosticReason::MissingBoundary
+                } else {
+                    match part.content_transfer_encoding() {
+                        Some(cte) if cte.eq_ignore_ascii_case("base64") => {
+                            DiagnosticReason::InvalidBase64
+                        }
+                        Some(cte) if cte.eq_ignore_ascii_case("quoted-printable") => {
+                            DiagnosticReason::InvalidQuotedPrintable
+                        }
+                        _ => DiagnosticReason::MissingBoundary,
+                    }
+                };
+                diagnostics.push(Diagnostic {
+                    part: part_id,
+                    header: None,
+                    offset: offset(part.offset_body),
+                    reason,
+                });
+            }
+        }
+    }
+}
+
+/// Parses a header again with diagnostics enabled, returning the reason and
+/// offset of the first problem reported by its field parser.
+fn header_diagnostic(
+    conf: &MessageParser,
+    header: &Header<'_>,
+    raw_message: &[u8],
+) -> Option<(DiagnosticReason, usize)> {
+    let raw = raw_message.get(header.offset_start..header.offset_end)?;
+    if raw.iter().all(|ch| ch.is_ascii_whitespace()) {
+        return None;
+    }
+
+    let diagnostic = match &header.name {
+        HeaderName::Date
+        | HeaderName::ResentDate
+        | HeaderName::From
+        | HeaderName::To
+        | HeaderName::Cc
+        | HeaderName::Bcc
+        | HeaderName::ReplyTo
+        | HeaderName::Sender
+        | HeaderName::ResentTo
+        | HeaderName::ResentFrom
+        | HeaderName::ResentBcc
+        | HeaderName::ResentCc
+        | HeaderName::ResentSender
+        | HeaderName::MessageId
+        | HeaderName::References
+        | HeaderName::InReplyTo
+        | HeaderName::ResentMessageId
+        | HeaderName::ContentId
+        | HeaderName::ContentType
+        | HeaderName::ContentDisposition => {
+            let mut stream = MessageStream::new(&raw_message[..header.offset_end]);
+            stream.skip_bytes(header.offset_start);
+            stream.diagnostics = Some(Vec::new());
+            stream.parse_header_field(conf, &header.name);
+            stream
+                .diagnostics
+                .and_then(|diagnostics| diagnostics.into_iter().next())
+        }
+        // List fields hold URLs rather than mailboxes
+        HeaderName::ListArchive
+        | HeaderName::ListHelp
+        | HeaderName::ListId
+        | HeaderName::ListOwner
+        | HeaderName::ListPost
+        | HeaderName::ListSubscribe
+        | HeaderName::ListUnsubscribe
+            if matches!(header.value, HeaderValue::Empty) =>
+        {
+            Some((DiagnosticReason::InvalidAddress, header.offset_start))
+        }
+        _ => None,
+    };
+    if diagnostic.is_some() {
+        return diagnostic;
+    }
+
+    // RFC2047 encoded-words are only decoded in unstructured text and phrases
+    match &header.name {
+        HeaderName::Subject
+        | HeaderName::Comments
+        | HeaderName::ContentDescription
+        | HeaderName::From
+        | HeaderName::To
+        | HeaderName::Cc
+        | HeaderName::Bcc
+        | HeaderName::ReplyTo
+        | HeaderName::Sender
+        | HeaderName::ResentTo
+        | HeaderName::ResentFrom
+        | HeaderName::ResentBcc
+        | HeaderName::ResentCc
+        | HeaderName::ResentSender => find_unterminated_encoded_word(raw).map(|pos| {
+            (
+                DiagnosticReason::UnterminatedEncodedWord,
+                header.offset_start + pos,
+            )
+        }),
+        _ => None,
+    }
+}
+
+/// Returns `true` if a `=` is not followed by two hexadecimal digits
+/// or by optional whitespace and a line break.
+fn has_invalid_qp_escape(bytes: &[u8]) -> bool {
+    bytes.iter().enumerate().any(|(pos, &ch)| {
+        ch == b'='
+            && match bytes.get(pos + 1) {
+                Some(ch) if ch.is_ascii_hexdigit() => {
+                    !bytes.get(pos + 2).is_some_and(|ch| ch.is_ascii_hexdigit())
+                }
+                Some(b' ' | b'\t' | b'\r' | b'\n') => bytes[pos + 1..]
+                    .iter()
+                    .find(|&&ch| !matches!(ch, b' ' | b'\t'))
+                    .is_some_and(|&ch| !matches!(ch, b'\r' | b'\n')),
+                Some(_) => true,
+                None => false,
+            }
+    })
+}
+
+/// Returns the position of the first `=?` that does not start
+/// a complete `=?charset?encoding?text?=` encoded-word.
+fn find_unterminated_encoded_word(raw: &[u8]) -> Option<usize> {
+    let mut pos = 0;
+    while let Some(start) = raw[pos..]
+        .windows(2)
+        .position(|w| w == b"=?")
+        .map(|p| p + pos)
+    {
+        let mut parts = 0;
+        let mut end = None;
+        let mut iter = raw[start + 2..].iter().enumerate().peekable();
+        while let Some((idx, &ch)) = iter.next() {
+            match ch {
+                b'?' if parts == 2 && matches!(iter.peek(), Some((_, b'='))) => {
+                    end = Some(start + 2 + idx + 2);
+                    break;
+                }
+                b'?' if parts < 2 => parts += 1,
+                b'?' => break,
+                _ if ch.is_ascii_whitespace() => break,
+                _ => (),
+            }
+        }
+        match end {
+            Some(end) => pos = end,
+            None => return Some(start),
+        }
+    }
+    None
+}
+
+impl Display for DiagnosticReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            DiagnosticReason::InvalidDate => "invalid date",
+            DiagnosticReason::InvalidAddress => "invalid address",
+            DiagnosticReason::InvalidMessageId => "invalid message id",
+            DiagnosticReason::InvalidContentType => "invalid content type",
+            DiagnosticReason::UnterminatedEncodedWord => "unterminated encoded-word",
+            DiagnosticReason::InvalidBase64 => "bad base64",
+            DiagnosticReason::InvalidQuotedPrintable => "bad quoted-printable",
+            DiagnosticReason::InvalidNestedMessage => "invalid nested message",
+            DiagnosticReason::MissingBoundary => "missing closing boundary",
+            DiagnosticReason::MissingBody => "missing body",
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{DiagnosticReason, HeaderName, MessageParser};
+
+    #[test]
+    fn parse_with_diagnostics() {
+        for (raw, expected) in [
+            (
+                concat!(
+                    "From: jdoe@example.com\r\n",
+                    "Date: Sat, 20 Nov 2021 14:22:01 -0800\r\n",
+                    "Subject: =?utf-8?q?hello?=\r\n",
+                    "Message-ID: <1234@example.com>\r\n\r\n",
+                    "body\r\n"
+                ),
+                vec![],
+            ),
+            (
+                concat!(
+                    "From: <<<>>>\r\n",
+                    "Date: not a date\r\n",
+                    "Subject: =?utf-8?q?hello?= =?utf-8?q?broken\r\n",
+                    "Message-ID: ???\r\n",
+                    "Content-Type: /\r\n\r\n",
+                    "body\r\n"
+                ),
+                vec![
+                    (
+                        0,
+                        Some(HeaderName::From),
+                        " <<<",
+                        DiagnosticReason::InvalidAddress,
+                    ),
+                    (
+                        0,
+                        Some(HeaderName::Date),
+                        " not a",
+                        DiagnosticReason::InvalidDate,
+                    ),
+                    (
+                        0,
+                        Some(HeaderName::Subject),
+                        "=?utf-8?q?broken",
+                        DiagnosticReason::UnterminatedEncodedWord,
+                    ),
+                    (
+                        0,
+                        Some(HeaderName::MessageId),
+                        "???",
+                        DiagnosticReason::InvalidMessageId,
+                    ),
+                    (
+                        0,
+                        Some(HeaderName::ContentType),
+                        " /",
+                        DiagnosticReason::InvalidContentType,
+                    ),
+                ],
+            ),
+            (
+                concat!(
+                    "Content-Type: multipart/mixed; boundary=x\r\n\r\n",
+                    "--x\r\n",
+                    "Content-Transfer-Encoding: base64\r\n\r\n",
+                    "!!!!\r\n",
+                    "--x\r\n",
+                    "Content-Type: message/rfc822\r\n",
+                    "Content-Transfer-Encoding: base64\r\n\r\n",
+                    "RGF0ZTogbm90IGEgZGF0ZQ0KDQpib2R5DQo=\r\n",
+                    "--x\r\n",
+                    "Content-Type: message/rfc822\r\n\r\n",
+                    "Date: nope\r\n\r\n",
+                    "body\r\n",
+                    "--x\r\n",
+                    "Content-Transfer-Encoding: base64\r\n\r\n",
+                    "aGVsbG8=\r\n"
+                ),
+                vec![
+                    (1, None, "!!!!", DiagnosticReason::InvalidBase64),
+                    (
+                        2,
+                        Some(HeaderName::Date),
+                        "RGF0",
+                        DiagnosticReason::InvalidDate,
+                    ),
+                    (
+                        3,
+                        Some(HeaderName::Date),
+                        " nope",
+                        DiagnosticReason::InvalidDate,
+                    ),
+                    (4, None, "aGVsbG8=", DiagnosticReason::MissingBoundary),
+                ],
+            ),
+            (
+                concat!(
+                    "From: Joe\r\n",
+                    "To: \"Joe <joe@example.com>\r\n",
+                    "Cc: joe@example.com, jane\r\n",
+                    "Date: Mon, 32 Jan 2001 10:00:00 +0000\r\n",
+                    "Resent-Date: 1 2 3\r\n",
+                    "Content-Type: multipart/mixed; boundary=x\r\n\r\n",
+                    "--x\r\n",
+                    "Content-Type: multipart/alternative; boundary=y\r\n\r\n",
+                    "--y\r\n",
+                    "Content-Type: text/plain\r\n\r\n",
+                    "text\r\n",
+                    "--x--\r\n"
+                ),
+                vec![
+                    (
+                        0,
+                        Some(HeaderName::From),
+                        " Joe",
+                        DiagnosticReason::InvalidAddress,
+                    ),
+                    (
+                        0,
+                        Some(HeaderName::To),
+                        " \"Joe",
+                        DiagnosticReason::InvalidAddress,
+                    ),
+                    (
+                        0,
+                        Some(HeaderName::Cc),
+                        " joe@",
+                        DiagnosticReason::InvalidAddress,
+                    ),
+                    (
+                        0,
+                        Some(HeaderName::Date),
+                        " Mon, 32",
+                        DiagnosticReason::InvalidDate,
+                    ),
+                    (
+                        0,
+                        Some(HeaderName::ResentDate),
+                        " 1 2 3",
+                        DiagnosticReason::InvalidDate,
+                    ),
+                    (2, None, "text", DiagnosticReason::MissingBoundary),
+                ],
+            ),
+            (
+                concat!(
+                    "Content-Type: multipart/mixed; boundary=x\r\n\r\n",
+                    "--x\r\n",
+                    "Content-Transfer-Encoding: quoted-printable\r\n\r\n",
+                    "caf=C3=A9 =\r\n",
+                    "au lait\r\n",
+                    "--x\r\n",
+                    "Content-Transfer-Encoding: quoted-printable\r\n\r\n",
+                    "price =ZZ\r\n",
+                    "--x--\r\n",
+                ),
+                vec![(2, None, "price", DiagnosticReason::InvalidQuotedPrintable)],
+            ),
+            (
+                "Subject: headers only\r\n",
+                vec![(0, None, "", DiagnosticReason::MissingBody)],
+            ),
+        ] {
+            let (_, diagnostics) = MessageParser::default()
+                .parse_with_diagnostics(raw)
+                .unwrap();
+            assert_eq!(diagnostics.len(), expected.len(), "failed for {raw:?}");
+            for (diagnostic, (part, header, text, reason)) in diagnostics.iter().zip(expected) {
+                assert_eq!(diagnostic.part, part, "failed for {raw:?}");
+                assert_eq!(diagnostic.header, header, "failed for {raw:?}");
+                assert_eq!(diagnostic.reason, reason, "failed for {raw:?}");
+                assert!(
+                    raw[diagnostic.offset..].starts_with(text),
+                    "failed for {raw:?}: {diagnostic:?}"
+                );
+            }
+        }
+    }
+}
@@ -9,9 +9,9 @@
  * except according to those terms.
  */
 
-use std::borrow::Cow;
+use alloc::{borrow::Cow, borrow::ToOwned, format, string::String, vec::Vec};
 
-use crate::{parsers::MessageStream, Addr, Address, Group, HeaderValue};
+use crate::{parsers::MessageStream, Addr, Address, DiagnosticReason, Group, HeaderValue};
 
 #[derive(PartialEq, Clone, Copy, Debug)]
 enum AddressState {
@@ -28,6 +28,7 @@ pub struct AddressParser<'x> {
     is_token_email: bool,
     is_token_start: bool,
     is_escaped: bool,
+    is_token_split: bool,
 
     name_tokens: Vec<Cow<'x, str>>,
     mail_tokens: Vec<Cow<'x, str>>,
@@ -40,6 +41,9 @@ pub struct AddressParser<'x> {
     group_name: Option<Cow<'x, str>>,
     group_comment: Option<Cow<'x, str>>,
     result: Vec<Group<'x>>,
+
+    /// Set when a mailbox has no address or an address without a domain
+    is_invalid: bool,
 }
 
 impl<'x> AddressParser<'x> {
@@ -59,7 +63,7 @@ impl<'x> AddressParser<'x> {
                 }
                 AddressState::Quote => &mut self.name_tokens,
                 AddressState::Comment => {
-                    add_space = true;
+                    add_space = !self.is_token_split;
                     &mut self.comment_tokens
                 }
             };
@@ -78,59 +82,64 @@ impl<'x> AddressParser<'x> {
             self.is_token_email = false;
             self.is_token_start = true;
             self.is_escaped = false;
+            self.is_token_split = false;
         }
     }
 
     pub fn add_address(&mut self) {
         let has_mail = !self.mail_tokens.is_empty();
         let has_name = !self.name_tokens.is_empty();
-        let has_comment = !self.comment_tokens.is_empty();
+        let comment = if !self.comment_tokens.is_empty() {
+            Some(concat_tokens(&mut self.comment_tokens))
+        } else {
+            None
+        };
 
-        self.addresses.push(if has_mail && has_name && has_comment {
-            Addr {
-                name: Some(
-                    format!(
-                        "{} ({})",
-                        concat_tokens(&mut self.name_tokens),
-                        concat_tokens(&mut self.comment_tokens)
-                    )
-                    .into(),
-                ),
-                address: concat_tokens(&mut self.mail_tokens).into(),
-            }
-        } else if has_name && has_mail {
+        // Without a display name, the comment is used as the name, as in
+        // the legacy `address (Name)` form.
+        let addr = if has_name && has_mail {
             Addr {
                 name: concat_tokens(&mut self.name_tokens).into(),
-                address: concat_tokens(&mut self.mail_tokens).into(),
-            }
-        } else if has_mail && has_comment {
-            Addr {
-                name: concat_tokens(&mut self.comment_tokens).into(),
-                address: concat_tokens(&mut self.mail_tokens).into(),
+                address: strip_source_route(concat_tokens(&mut self.mail_tokens)).into(),
+                comment,
             }
         } else if has_mail {
             Addr {
-                name: None,
-                address: concat_tokens(&mut self.mail_tokens).into(),
+                name: comment.clone(),
+                address: strip_source_route(concat_tokens(&mut self.mail_tokens)).into(),
+                comment,
             }
-        } else if has_name && has_comment {
+        } else if has_name && comment.is_some() {
             Addr {
-                name: concat_tokens(&mut self.comment_tokens).into(),
+                name: comment.clone(),
                 address: concat_tokens(&mut self.name_tokens).into(),
+                comment,
             }
         } else if has_name {
             Addr {
                 name: concat_tokens(&mut self.name_tokens).into(),
                 address: None,
+                comment: None,
             }
-        } else if has_comment {
+        } else if comment.is_some() {
             Addr {
-                name: concat_tokens(&mut self.comment_tokens).into(),
+                name: comment.clone(),
                 address: None,
+                comment,
             }
         } else {
             return;
-        });
+        };
+
+        if addr
+            .address
+            .as_deref()
+            .and_then(parse_address_domain)
+            .is_none()
+        {
+            self.is_invalid = true;
+        }
+        self.addresses.push(addr);
     }
 
     pub fn add_group_details(&mut self) {
@@ -172,17 +181,17 @@ impl<'x> AddressParser<'x> {
                         )
                         .into(),
                     ),
-                    addresses: std::mem::take(&mut self.addresses),
+                    addresses: core::mem::take(&mut self.addresses),
                 }
             } else if has_addresses && has_name {
                 Group {
                     name: self.group_name.take(),
-                    addresses: std::mem::take(&mut self.addresses),
+                    addresses: core::mem::take(&mut self.addresses),
                 }
             } else if has_addresses {
                 Group {
                     name: self.group_comment.take(),
-                    addresses: std::mem::take(&mut self.addresses),
+                    addresses: core::mem::take(&mut self.addresses),
                 }
             } else if has_name {
                 Group {
@@ -197,6 +206,7 @@ impl<'x> AddressParser<'x> {
 
 impl<'x> MessageStream<'x> {
     pub fn parse_address(&mut self) -> HeaderValue<'x> {
+        let offset_start = self.offset();
         let mut parser = AddressParser {
             token_start: 0,
             token_end: 0,
@@ -204,6 +214,7 @@ impl<'x> MessageStream<'x> {
             is_token_email: false,
             is_token_start: true,
             is_escaped: false,
+            is_token_split: false,
 
             name_tokens: Vec::with_capacity(3),
             mail_tokens: Vec::with_capacity(3),
@@ -216,6 +227,8 @@ impl<'x> MessageStream<'x> {
             group_name: None,
             group_comment: None,
             result: Vec::new(),
+
+            is_invalid: false,
         };
 
         while let Some(ch) = self.next() {
@@ -232,6 +245,9 @@ impl<'x> MessageStream<'x> {
                     }
                 }
                 b'\\' if parser.state != AddressState::Name && !parser.is_escaped => {
+                    // An escape splits the token, rejoin it unless preceded by a space
+                    let is_token_split =
+                        parser.token_start > 0 && parser.token_end + 1 == self.offset();
                     if parser.token_start > 0 {
                         if parser.state == AddressState::Quote {
                             parser.token_end = self.offset() - 1;
@@ -239,6 +255,7 @@ impl<'x> MessageStream<'x> {
                         parser.add_token(self, false);
                     }
                     parser.is_escaped = true;
+                    parser.is_token_split = is_token_split;
                     continue;
                 }
                 b',' if parser.state == AddressState::Name => {
@@ -357,12 +374,18 @@ impl<'x> MessageStream<'x> {
 
         parser.add_address();
 
+        if parser.is_invalid || parser.state != AddressState::Name {
+            // Unterminated quotes, comments or angle brackets are also reported
+            self.add_diagnostic(DiagnosticReason::InvalidAddress, offset_start);
+        }
+
         if parser.group_name.is_some() || !parser.result.is_empty() {
             parser.add_group();
             HeaderValue::Address(Address::Group(parser.result))
         } else if !parser.addresses.is_empty() {
             HeaderValue::Address(Address::List(parser.addresses))
         } else {
+            self.add_diagnostic(DiagnosticReason::InvalidAddress, offset_start);
             HeaderValue::Empty
         }
     }
@@ -378,13 +401,42 @@ fn concat_tokens<'x>(tokens: &mut Vec<Cow<'x, str>>) -> Cow<'x, str> {
     }
 }
 
+/// Removes the obsolete source route (RFC5322, section 4.4) from an address
+/// such as `@a.example,@b.example:user@c.example`.
+fn strip_source_route(addr: Cow<'_, str>) -> Cow<'_, str> {
+    if !addr.starts_with('@') {
+        return addr;
+    }
+
+    let mut in_literal = false;
+    let route_end = addr.bytes().position(|ch| match ch {
+        b'[' => {
+            in_literal = true;
+            false
+        }
+        b']' => {
+            in_literal = false;
+            false
+        }
+        b':' => !in_literal,
+        _ => false,
+    });
+    match route_end {
+        Some(pos) if !addr[pos + 1..].trim().is_empty() => match addr {
+            Cow::Borrowed(addr) => addr[pos + 1..].trim().into(),
+            Cow::Owned(addr) => addr[pos + 1..].trim().to_owned().into(),
+        },
+        _ => addr,
+    }
+}
+
 pub fn parse_address_local_part(addr: &str) -> Option<&str> {
     let addr = addr.as_bytes();
     let mut iter = addr.iter().enumerate();
     while let Some((pos, &ch)) = iter.next() {
         if ch == b'@' {
             return if pos > 0 && iter.next().is_some() {
-                std::str::from_utf8(addr.get(..pos)?).ok()
+                core::str::from_utf8(addr.get(..pos)?).ok()
             } else {
                 None
             };
@@ -401,7 +453,7 @@ pub fn parse_address_domain(addr: &str) -> Option<&str> {
     for (pos, &ch) in addr.iter().enumerate() {
         if ch == b'@' {
             return if pos > 0 && pos + 1 < addr.len() {
-                std::str::from_utf8(addr.get(pos + 1..)?).ok()
+                core::str::from_utf8(addr.get(pos + 1..)?).ok()
             } else {
                 None
             };
@@ -422,14 +474,14 @@ pub fn parse_address_user_part(addr: &str) -> Option<&str> {
             if pos > 0 {
                 while let Some((_, &ch)) = iter.next() {
                     if ch == b'@' && iter.next().is_some() {
-                        return std::str::from_utf8(addr.get(..pos)?).ok();
+                        return core::str::from_utf8(addr.get(..pos)?).ok();
                     }
                 }
             }
             return None;
         } else if ch == b'@' {
             return if pos > 0 && iter.next().is_some() {
-                std::str::from_utf8(addr.get(..pos)?).ok()
+                core::str::from_utf8(addr.get(..pos)?).ok()
             } else {
                 None
             };
@@ -451,7 +503,7 @@ pub fn parse_address_detail_part(addr: &str) -> Option<&str> {
             plus_pos = pos + 1;
         } else if ch == b'@' {
             if plus_pos != usize::MAX && iter.next().is_some() {
-                return std::str::from_utf8(addr.get(plus_pos..pos)?).ok();
+                return core::str::from_utf8(addr.get(plus_pos..pos)?).ok();
             } else {
                 return None;
             }
@@ -0,0 +1,238 @@
+/*
+ * Copyright Stalwart Labs Ltd. See the COPYING
+ * file at the top-level directory of this distribution.
+ *
+ * Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+ * https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+ * <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+ * option. This file may not be copied, modified, or distributed
+ * except according to those terms.
+ */
+
+use alloc::{borrow::Cow, string::String, vec::Vec};
+
+use crate::{
+    parsers::MessageStream, AuthenticationProperty, AuthenticationResult, AuthenticationResults,
+};
+
+const STOP_METHOD: &[u8] = b";=/(";
+const STOP_RESULT: &[u8] = b";(";
+const STOP_PTYPE: &[u8] = b";=.(";
+const STOP_VALUE: &[u8] = b";(";
+
+impl<'x> MessageStream<'x> {
+    /// Parses an RFC8601 Authentication-Results header value.
+    pub fn parse_authentication_results(&mut self) -> Option<AuthenticationResults<'x>> {
+        if !self.skip_cfws() {
+            return None;
+        }
+        let authserv_id = self.auth_value(STOP_RESULT)?;
+        let mut results = AuthenticationResults {
+            authserv_id,
+            version: None,
+            results: Vec::new(),
+        };
+
+        if self.skip_cfws() && !self.peek_char(b';') {
+            results.version = self.auth_word(STOP_RESULT).and_then(|v| v.parse().ok());
+        }
+
+        while self.skip_cfws() {
+            if !self.try_skip_char(b';') {
+                // Invalid token, skip it
+                if self.auth_value(STOP_RESULT).is_none() {
+                    self.next();
+                }
+                continue;
+            }
+
+            // Method
+            if !self.skip_cfws() {
+                break;
+            }
+            let method = if let Some(method) = self.auth_word(STOP_METHOD) {
+                method
+            } else {
+                continue;
+            };
+            let mut method_version = None;
+            if self.skip_cfws() && self.try_skip_char(b'/') {
+                self.skip_cfws();
+                method_version = self.auth_word(STOP_METHOD).and_then(|v| v.parse().ok());
+                self.skip_cfws();
+            }
+            if !self.try_skip_char(b'=') {
+                // No results or an invalid method
+                continue;
+            }
+            self.skip_cfws();
+            let result = if let Some(result) = self.auth_word(STOP_RESULT) {
+                result
+            } else {
+                continue;
+            };
+
+            let mut result = AuthenticationResult {
+                method,
+                method_version,
+                result,
+                reason: None,
+                properties: Vec::new(),
+            };
+
+            // Reason and properties
+            while self.skip_cfws() && !self.peek_char(b';') {
+                let ptype = if let Some(ptype) = self.auth_word(STOP_PTYPE) {
+                    ptype
+                } else {
+                    self.next();
+                    continue;
+                };
+                self.skip_cfws();
+
+                if self.try_skip_char(b'.') {
+                    self.skip_cfws();
+                    let property = self.auth_word(STOP_PTYPE);
+                    self.skip_cfws();
+                    if let (Some(property), true) = (property, self.try_skip_char(b'=')) {
+                        self.skip_cfws();
+                        if let Some(value) = self.auth_value(STOP_VALUE) {
+                            result.properties.push(AuthenticationProperty {
+                                ptype,
+                                property,
+                                value,
+                            });
+                        }
+                    }
+                } else if self.try_skip_char(b'=') {
+                    self.skip_cfws();
+                    let value = self.auth_value(STOP_VALUE);
+                    if ptype.eq_ignore_ascii_case("reason") {
+                        result.reason = value;
+                    }
+                }
+            }
+
+            results.results.push(result);
+        }
+
+        Some(results)
+    }
+
+    /// Skips whitespace, folding and comments, returns `false` at the end of the header.
+    fn skip_cfws(&mut self) -> bool {
+        while let Some(&&ch) = self.peek() {
+            match ch {
+                b' ' | b'\t' | b'\r' => {
+                    self.next();
+                }
+                b'\n' => {
+                    self.next();
+                    if !self.peek_next_is_space() {
+                        self.seek_end();
+                        return false;
+                    }
+                }
+                b'(' => self.skip_comment(),
+                _ => return true,
+            }
+        }
+        false
+    }
+
+    fn skip_comment(&mut self) {
+        let mut depth = 0;
+        while let Some(&&ch) = self.peek() {
+            match ch {
+                b'(' => depth += 1,
+                b')' => {
+                    depth -= 1;
+                    if depth <= 0 {
+                        self.next();
+                        return;
+                    }
+                }
+                b'\\' => {
+                    self.next();
+                }
+                b'\n' if depth == 0 => return,
+                _ if depth == 0 => return,
+                _ => (),
+            }
+            self.next();
+        }
+    }
+
+    fn auth_word(&mut self, stop: &[u8]) -> Option<Cow<'x, str>> {
+        let start = self.offset();
+        while let Some(&&ch) = self.peek() {
+            if ch.is_ascii_whitespace() || ch == b'"' || stop.contains(&ch) {
+                break;
+            }
+            self.next();
+        }
+        let end = self.offset();
+
+        if end > start {
+            Some(String::from_utf8_lossy(self.bytes(start..end)))
+        } else {
+            None
+        }
+    }
+
+    fn auth_value(&mut self, stop: &[u8]) -> Option<Cow<'x, str>> {
+        if !self.try_skip_char(b'"') {
+            return self.auth_word(stop);
+        }
+
+        let start = self.offset();
+        let mut value: Option<Vec<u8>> = None;
+        while let Some(&ch) = self.next() {
+            match ch {
+                b'"' => {
+                    return Some(match value {
+                        Some(value) => String::from_utf8(value)
+                            .unwrap_or_else(|e| String::from_utf8_lossy(e.as_bytes()).into_owned())
+                            .into(),
+                        None => String::from_utf8_lossy(self.bytes(start..self.offset() - 1)),
+                    });
+                }
+                b'\\' => {
+                    let value =
+                        value.get_or_insert_with(|| self.bytes(start..self.offset() - 1).to_vec());
+                    if let Some(&ch) = self.next() {
+                        value.push(ch);
+                    }
+                }
+                b'\r' | b'\n' => {
+                    // Folded quoted string
+                    value.get_or_insert_with(|| self.bytes(start..self.offset() - 1).to_vec());
+                }
+                _ => {
+                    if let Some(value) = &mut value {
+                        value.push(ch);
+                    }
+                }
+            }
+        }
+
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parsers::{fields::load_tests, MessageStream};
+
+    #[test]
+    fn parse_authentication_results() {
+        for test in load_tests("authentication_results.json") {
+            assert_eq!(
+                MessageStream::new(test.header.as_bytes()).parse_authentication_results(),
+                test.expected,
+                "failed for {:?}",
+                test.header
+            );
+        }
+    }
+}
@@ -0,0 +1,72 @@
+/*
+ * Copyright Stalwart Labs Ltd. See the COPYING
+ * file at the top-level directory of this distribution.
+ *
+ * Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+ * https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+ * <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+ * option. This file may not be copied, modified, or distributed
+ * except according to those terms.
+ */
+
+use crate::{decoders::base64::base64_decode, parsers::MessageStream, Autocrypt};
+
+impl<'x> MessageStream<'x> {
+    /// Parses an Autocrypt Level 1 header. Unknown attributes starting with an
+    /// underscore are ignored, while any other unknown attribute is critical and
+    /// causes the whole header to be discarded. The header is also discarded
+    /// when `addr` or `keydata` are missing or when `keydata` is not valid base64.
+    pub fn parse_autocrypt(&mut self) -> Option<Autocrypt<'x>> {
+        let mut addr = None;
+        let mut prefer_encrypt = None;
+        let mut keydata = None;
+
+        loop {
+            let (name, has_value) = self.dkim_token(true);
+            if has_value {
+                let value = self.dkim_token(false).0.unwrap_or_default();
+                match name {
+                    Some(name) if name.eq_ignore_ascii_case("addr") => addr = Some(value),
+                    Some(name) if name.eq_ignore_ascii_case("prefer-encrypt") => {
+                        prefer_encrypt = Some(value)
+                    }
+                    Some(name) if name.eq_ignore_ascii_case("keydata") => keydata = Some(value),
+                    Some(name) if name.starts_with('_') => (),
+                    _ => return None,
+                }
+            }
+
+            if self.is_eof() {
+                break;
+            }
+        }
+
+        let addr = addr.filter(|addr| !addr.is_empty())?;
+        let keydata = keydata.filter(|keydata| {
+            base64_decode(keydata.as_bytes()).is_some_and(|key| !key.is_empty())
+        })?;
+
+        Some(Autocrypt {
+            addr,
+            prefer_encrypt,
+            keydata,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parsers::{fields::load_tests, MessageStream};
+
+    #[test]
+    fn parse_autocrypt() {
+        for test in load_tests("autocrypt.json") {
+            assert_eq!(
+                MessageStream::new(test.header.as_bytes()).parse_autocrypt(),
+                test.expected,
+                "failed for {:?}",
+                test.header
+            );
+        }
+    }
+}
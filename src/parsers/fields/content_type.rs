@@ -9,12 +9,12 @@
  * except according to those terms.
  */
 
-use std::borrow::Cow;
+use alloc::{borrow::Cow, string::String, vec::Vec};
 
 use crate::{
     decoders::{charsets::map::charset_decoder, hex::decode_hex},
     parsers::MessageStream,
-    ContentType, HeaderValue,
+    ContentType, DiagnosticReason, HeaderValue,
 };
 
 #[derive(Clone, Copy, PartialEq, Debug)]
@@ -27,7 +27,13 @@ enum ContentState {
     Comment,
 }
 
-type Continuation<'x> = (Cow<'x, str>, u32, Cow<'x, str>);
+struct Continuation<'x> {
+    name: Cow<'x, str>,
+    position: u32,
+    charset: Option<Cow<'x, str>>,
+    is_encoded: bool,
+    value: Cow<'x, str>,
+}
 
 struct ContentTypeParser<'x> {
     state: ContentState,
@@ -173,7 +179,7 @@ impl<'x> ContentTypeParser<'x> {
             ));
         } else {
             let attr_name = self.attr_name.take().unwrap();
-            let mut value = if let Some(value) = value {
+            let value = if let Some(value) = value {
                 if has_values {
                     Cow::from(self.values.concat()) + value
                 } else {
@@ -183,37 +189,34 @@ impl<'x> ContentTypeParser<'x> {
                 self.values.concat().into()
             };
 
-            if self.is_encoded_attribute {
-                if let (true, decoded_bytes) = decode_hex(value.as_bytes()) {
-                    value = if let Some(decoder) = self
-                        .attr_charset
-                        .as_ref()
-                        .and_then(|c| charset_decoder(c.as_bytes()))
-                    {
-                        decoder(&decoded_bytes).into()
-                    } else {
-                        String::from_utf8(decoded_bytes)
-                            .unwrap_or_else(|e| String::from_utf8_lossy(e.as_bytes()).into_owned())
-                            .into()
-                    }
-                }
-                self.is_encoded_attribute = false;
-            }
-
-            if self.attr_position > 0 {
-                let continuation = (attr_name, self.attr_position, value);
-                if let Some(continuations) = self.continuations.as_mut() {
-                    continuations.push(continuation);
+            if self.attr_position == 0 {
+                // Keep the position of the parameter, its value is set once all
+                // continuations are merged. RFC2231 parameters take precedence over
+                // regular ones.
+                if let Some((_, old_value)) = self
+                    .attributes
+                    .iter_mut()
+                    .find(|(name, _)| name == &attr_name)
+                {
+                    *old_value = value.clone();
                 } else {
-                    self.continuations = Some(vec![continuation]);
+                    self.attributes.push((attr_name.clone(), value.clone()));
                 }
-
-                self.attr_position = 0;
-            } else {
-                self.attributes.push((attr_name, value));
             }
+
+            self.continuations
+                .get_or_insert_with(Vec::new)
+                .push(Continuation {
+                    name: attr_name,
+                    position: self.attr_position,
+                    charset: self.attr_charset.take(),
+                    is_encoded: self.is_encoded_attribute,
+                    value,
+                });
+
+            self.attr_position = 0;
+            self.is_encoded_attribute = false;
             self.is_continuation = false;
-            self.attr_charset = None;
         }
 
         if has_values {
@@ -238,21 +241,56 @@ impl<'x> ContentTypeParser<'x> {
     }
 
     fn merge_continuations(&mut self) {
-        let continuations = self.continuations.as_mut().unwrap();
-        continuations.sort();
-        for (key, _, value) in continuations.drain(..) {
-            if let Some((_, old_value)) = self.attributes.iter_mut().find(|(name, _)| name == &key)
+        let mut continuations = self.continuations.take().unwrap();
+        continuations.sort_by(|a, b| (&a.name, a.position).cmp(&(&b.name, b.position)));
+
+        let mut continuations = continuations.into_iter().peekable();
+        while let Some(mut first) = continuations.next() {
+            // Segments are decoded together using the charset of the first segment
+            let charset = first.charset.take();
+            let mut bytes = Vec::new();
+            first.append_to(&mut bytes);
+            while let Some(next) = continuations.next_if(|next| next.name == first.name) {
+                next.append_to(&mut bytes);
+            }
+
+            let value: Cow<str> =
+                if let Some(decoder) = charset.and_then(|c| charset_decoder(c.as_bytes())) {
+                    decoder(&bytes).into()
+                } else {
+                    String::from_utf8(bytes)
+                        .unwrap_or_else(|e| String::from_utf8_lossy(e.as_bytes()).into_owned())
+                        .into()
+                };
+
+            if let Some((_, old_value)) = self
+                .attributes
+                .iter_mut()
+                .find(|(name, _)| name == &first.name)
             {
-                *old_value = format!("{old_value}{value}").into();
+                *old_value = value;
             } else {
-                self.attributes.push((key, value));
+                self.attributes.push((first.name, value));
             }
         }
     }
 }
 
+impl<'x> Continuation<'x> {
+    fn append_to(&self, bytes: &mut Vec<u8>) {
+        if self.is_encoded {
+            if let (true, decoded_bytes) = decode_hex(self.value.as_bytes()) {
+                bytes.extend_from_slice(&decoded_bytes);
+                return;
+            }
+        }
+        bytes.extend_from_slice(self.value.as_bytes());
+    }
+}
+
 impl<'x> MessageStream<'x> {
     pub fn parse_content_type(&mut self) -> HeaderValue<'x> {
+        let offset_start = self.offset();
         let mut parser = ContentTypeParser {
             state: ContentState::Type,
             state_stack: Vec::new(),
@@ -295,14 +333,12 @@ impl<'x> MessageStream<'x> {
                     }
                     continue;
                 }
-                b'A'..=b'Z' => {
-                    if parser.is_lower_case {
-                        if let ContentState::Type
-                        | ContentState::SubType
-                        | ContentState::AttributeName = parser.state
-                        {
-                            parser.is_lower_case = false;
-                        }
+                b'A'..=b'Z' if parser.is_lower_case => {
+                    if let ContentState::Type
+                    | ContentState::SubType
+                    | ContentState::AttributeName = parser.state
+                    {
+                        parser.is_lower_case = false;
                     }
                 }
                 b'\n' => {
@@ -351,6 +387,7 @@ impl<'x> MessageStream<'x> {
                                 },
                             })
                         } else {
+                            self.add_diagnostic(DiagnosticReason::InvalidContentType, offset_start);
                             HeaderValue::Empty
                         };
                     }
@@ -509,6 +546,7 @@ impl<'x> MessageStream<'x> {
             }
         }
 
+        self.add_diagnostic(DiagnosticReason::InvalidContentType, offset_start);
         HeaderValue::Empty
     }
 }
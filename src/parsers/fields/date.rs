@@ -9,9 +9,10 @@
  * except according to those terms.
  */
 
-use std::fmt;
+use alloc::{format, string::String};
+use core::fmt;
 
-use crate::{parsers::MessageStream, DateTime, HeaderValue};
+use crate::{parsers::MessageStream, DateTime, DiagnosticReason, HeaderValue, InvalidDateTime};
 
 pub static DOW: &[&str] = &["Sun", "Mon", "Tue", "Wed", "Thu", "Fri", "Sat"];
 pub static MONTH: &[&str] = &[
@@ -144,6 +145,30 @@ impl DateTime {
         )
     }
 
+    /// Returns an RFC5322 representation of the date, such as
+    /// `Tue, 1 Jul 2003 10:52:37 +0200`, or an empty string if the date is not valid.
+    ///
+    /// Unlike [`DateTime::to_rfc822`], unknown timezones are written as `-0000`.
+    pub fn to_rfc2822(&self) -> String {
+        if !self.is_valid() {
+            return String::new();
+        }
+
+        format!(
+            "{}, {} {} {:04} {:02}:{:02}:{:02} {}{:02}{:02}",
+            DOW[self.day_of_week() as usize],
+            self.day,
+            MONTH[self.month as usize - 1],
+            self.year,
+            self.hour,
+            self.minute,
+            self.second,
+            if self.tz_before_gmt { "-" } else { "+" },
+            self.tz_hour,
+            self.tz_minute
+        )
+    }
+
     /// Returns an RFC3339 representation of the parsed RFC5322 datetime field
     pub fn to_rfc3339(&self) -> String {
         if self.tz_hour != 0 || self.tz_minute != 0 {
@@ -183,16 +208,16 @@ impl DateTime {
             && (0..=59).contains(&self.second)
     }
 
-    /// Returns the numbers of seconds since 1970-01-01T00:00:00Z (Unix epoch)
-    /// or None if the date is invalid.
+    /// Returns the numbers of seconds since 1970-01-01T00:00:00Z (Unix epoch),
+    /// with the timezone offset applied so the result is in UTC.
     pub fn to_timestamp(&self) -> i64 {
         self.to_timestamp_local()
             + ((self.tz_hour as i64 * 3600 + self.tz_minute as i64 * 60)
                 * if self.tz_before_gmt { 1 } else { -1 })
     }
 
-    /// Returns the numbers of seconds since 1970-01-01T00:00:00Z (Unix epoch) in local time
-    /// or None if the date is invalid.
+    /// Returns the numbers of seconds since 1970-01-01T00:00:00Z (Unix epoch) in local time,
+    /// ignoring the timezone offset.
     pub fn to_timestamp_local(&self) -> i64 {
         // Ported from https://github.com/protocolbuffers/upb/blob/22182e6e/upb/json_decode.c#L982-L992
         let month = self.month as u32;
@@ -209,10 +234,14 @@ impl DateTime {
             + self.second as i64
     }
 
-    /// Creates a DateTime object from a timestamp
+    /// Creates a UTC DateTime object from the number of seconds since
+    /// 1970-01-01T00:00:00Z (Unix epoch)
     pub fn from_timestamp(timestamp: i64) -> Self {
         // Ported from http://howardhinnant.github.io/date_algorithms.html#civil_from_days
-        let (z, seconds) = ((timestamp / 86400) + 719468, timestamp % 86400);
+        let (z, seconds) = (
+            timestamp.div_euclid(86400) + 719468,
+            timestamp.rem_euclid(86400),
+        );
         let era: i64 = (if z >= 0 { z } else { z - 146096 }) / 146097;
         let doe: u64 = (z - era * 146097) as u64; // [0, 146096]
         let yoe: u64 = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365; // [0, 399]
@@ -238,7 +267,7 @@ impl DateTime {
 
     /// Returns the day of week where [0, 6] represents [Sun, Sat].
     pub fn day_of_week(&self) -> u8 {
-        (((self.to_timestamp_local() as f64 / 86400.0).floor() as i64 + 4).rem_euclid(7)) as u8
+        ((self.to_timestamp_local().div_euclid(86400) + 4).rem_euclid(7)) as u8
     }
 
     /// Returns the julian day
@@ -266,29 +295,131 @@ impl DateTime {
 }
 
 impl PartialOrd for DateTime {
-    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+    fn partial_cmp(&self, other: &Self) -> Option<core::cmp::Ordering> {
         Some(self.cmp(other))
     }
 }
 
 impl Ord for DateTime {
-    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
-        match self.to_timestamp() - other.to_timestamp() {
-            0 => std::cmp::Ordering::Equal,
-            x if x > 0 => std::cmp::Ordering::Greater,
-            _ => std::cmp::Ordering::Less,
+    /// Compares the UTC instants of both dates. Invalid dates sort before
+    /// any valid date, and dates representing the same instant in different
+    /// timezones are ordered by their fields to keep the order total.
+    fn cmp(&self, other: &Self) -> core::cmp::Ordering {
+        match (self.is_valid(), other.is_valid()) {
+            (true, true) => self.to_timestamp().cmp(&other.to_timestamp()),
+            (false, true) => core::cmp::Ordering::Less,
+            (true, false) => core::cmp::Ordering::Greater,
+            (false, false) => core::cmp::Ordering::Equal,
         }
+        .then_with(|| {
+            (
+                self.year,
+                self.month,
+                self.day,
+                self.hour,
+                self.minute,
+                self.second,
+                self.tz_before_gmt,
+                self.tz_hour,
+                self.tz_minute,
+            )
+                .cmp(&(
+                    other.year,
+                    other.month,
+                    other.day,
+                    other.hour,
+                    other.minute,
+                    other.second,
+                    other.tz_before_gmt,
+                    other.tz_hour,
+                    other.tz_minute,
+                ))
+        })
     }
 }
 
 impl fmt::Display for DateTime {
+    /// Formats the date as [`DateTime::to_rfc2822`] does, writing nothing
+    /// if the date is not valid.
     fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
-        fmt.write_str(&self.to_rfc3339())
+        fmt.write_str(&self.to_rfc2822())
+    }
+}
+
+impl fmt::Display for InvalidDateTime {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("invalid or out of range date")
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for InvalidDateTime {}
+
+#[cfg(feature = "chrono")]
+impl TryFrom<&DateTime> for chrono::DateTime<chrono::FixedOffset> {
+    type Error = InvalidDateTime;
+
+    fn try_from(datetime: &DateTime) -> Result<Self, Self::Error> {
+        use chrono::TimeZone;
+
+        if !datetime.is_valid() {
+            return Err(InvalidDateTime);
+        }
+
+        let offset = datetime.tz_hour as i32 * 3600 + datetime.tz_minute as i32 * 60;
+        if datetime.tz_before_gmt {
+            chrono::FixedOffset::west_opt(offset)
+        } else {
+            chrono::FixedOffset::east_opt(offset)
+        }
+        .ok_or(InvalidDateTime)?
+        .with_ymd_and_hms(
+            datetime.year as i32,
+            datetime.month as u32,
+            datetime.day as u32,
+            datetime.hour as u32,
+            datetime.minute as u32,
+            datetime.second as u32,
+        )
+        .single()
+        .ok_or(InvalidDateTime)
+    }
+}
+
+#[cfg(feature = "time")]
+impl TryFrom<&DateTime> for time::OffsetDateTime {
+    type Error = InvalidDateTime;
+
+    fn try_from(datetime: &DateTime) -> Result<Self, Self::Error> {
+        if !datetime.is_valid() {
+            return Err(InvalidDateTime);
+        }
+
+        let (tz_hour, tz_minute) = if datetime.tz_before_gmt {
+            (-(datetime.tz_hour as i8), -(datetime.tz_minute as i8))
+        } else {
+            (datetime.tz_hour as i8, datetime.tz_minute as i8)
+        };
+
+        Ok(time::PrimitiveDateTime::new(
+            time::Date::from_calendar_date(
+                datetime.year as i32,
+                time::Month::try_from(datetime.month).map_err(|_| InvalidDateTime)?,
+                datetime.day,
+            )
+            .map_err(|_| InvalidDateTime)?,
+            time::Time::from_hms(datetime.hour, datetime.minute, datetime.second)
+                .map_err(|_| InvalidDateTime)?,
+        )
+        .assume_offset(
+            time::UtcOffset::from_hms(tz_hour, tz_minute, 0).map_err(|_| InvalidDateTime)?,
+        ))
     }
 }
 
 impl<'x> MessageStream<'x> {
     pub fn parse_date(&mut self) -> HeaderValue<'x> {
+        let offset_start = self.offset();
         let mut pos = 0;
         let mut parts = [0u32; 7];
         let mut parts_sizes = [
@@ -302,6 +433,9 @@ impl<'x> MessageStream<'x> {
         ];
         let mut month_hash: usize = 0;
         let mut month_pos: usize = 0;
+        let mut month_name = [0u8; 3];
+        let mut zone = [0u8; 3];
+        let mut zone_len = 0;
 
         let mut is_plus = true;
         let mut is_new_token = true;
@@ -346,10 +480,8 @@ impl<'x> MessageStream<'x> {
                         is_new_token = false;
                     }
                 }
-                b':' => {
-                    if !is_new_token && !ignore && (pos == 3 || pos == 4) {
-                        next_part = true;
-                    }
+                b':' if !is_new_token && !ignore && (pos == 3 || pos == 4) => {
+                    next_part = true;
                 }
                 b'+' => {
                     pos = 6;
@@ -358,13 +490,26 @@ impl<'x> MessageStream<'x> {
                     is_plus = false;
                     pos = 6;
                 }
-                b' ' | b'\t' => {
-                    if !is_new_token && !ignore {
-                        next_part = true;
-                    }
+                b' ' | b'\t' if !is_new_token && !ignore => {
+                    next_part = true;
                 }
                 b'a'..=b'z' | b'A'..=b'Z' => {
-                    if pos == 1 {
+                    if pos == 5 && is_new_token && !ignore && parts_sizes[5] == 2 {
+                        // Obsolete zone name without seconds
+                        pos = 6;
+                    }
+                    if pos == 6 && parts_sizes[6] == 4 {
+                        if is_new_token {
+                            zone_len = 0;
+                        }
+                        if let Some(zone_ch) = zone.get_mut(zone_len) {
+                            *zone_ch = ch.to_ascii_uppercase();
+                        }
+                        zone_len += 1;
+                    } else if pos == 1 {
+                        if let Some(month_ch) = month_name.get_mut(month_pos) {
+                            *month_ch = ch.to_ascii_lowercase();
+                        }
                         if (1..=2).contains(&month_pos) {
                             month_hash += MONTH_HASH
                                 [(if *ch <= b'Z' { *ch + 32 } else { *ch }) as usize]
@@ -397,6 +542,7 @@ impl<'x> MessageStream<'x> {
                     ];
                     month_hash = 0;
                     month_pos = 0;
+                    zone_len = 0;
 
                     is_plus = true;
                     is_new_token = true;
@@ -415,29 +561,84 @@ impl<'x> MessageStream<'x> {
             }
         }
 
-        if pos >= 6 {
-            HeaderValue::DateTime(DateTime {
-                year: if (0..=49).contains(&parts[2]) {
-                    parts[2] + 2000
-                } else if (50..=99).contains(&parts[2]) {
-                    parts[2] + 1900
-                } else {
-                    parts[2]
-                } as u16,
-                month: if month_pos == 3 && month_hash <= 30 {
-                    MONTH_MAP[month_hash]
-                } else {
-                    parts[1] as u8
-                },
+        // A time without seconds or zone at the end of the field
+        if (pos == 4 || pos == 5) && !is_new_token && !ignore && comment_count == 0 {
+            parts[pos] /= u32::pow(10, parts_sizes[pos]);
+            pos += 1;
+        }
+
+        let year = match 4 - parts_sizes[2] {
+            // Obsolete two and three digit years (RFC5322, section 4.3)
+            2 if parts[2] <= 49 => parts[2] + 2000,
+            2 | 3 => parts[2] + 1900,
+            _ => parts[2],
+        } as u16;
+        let month_name = (month_pos == 3 && month_hash <= 30).then(|| {
+            // Unknown month names may share the hash of a valid one
+            let month = MONTH_MAP[month_hash];
+            if MONTH
+                .get((month as usize).wrapping_sub(1))
+                .is_some_and(|name| name.as_bytes().eq_ignore_ascii_case(&month_name))
+            {
+                month
+            } else {
+                0
+            }
+        });
+
+        if pos >= 5 && parts_sizes[3] < 2 && parts_sizes[4] < 2 {
+            // Obsolete zone names (RFC5322, section 4.3), unknown or missing zones
+            // and military zones are considered to be "-0000"
+            let (tz, is_plus) = if parts_sizes[6] < 4 {
+                (parts[6], is_plus)
+            } else {
+                match zone.get(..zone_len).unwrap_or_default() {
+                    b"UT" | b"GMT" => (0, true),
+                    b"EDT" => (400, false),
+                    b"EST" | b"CDT" => (500, false),
+                    b"CST" | b"MDT" => (600, false),
+                    b"MST" | b"PDT" => (700, false),
+                    b"PST" => (800, false),
+                    _ => (0, false),
+                }
+            };
+
+            let date = DateTime {
+                year,
+                month: month_name.unwrap_or(parts[1] as u8),
                 day: parts[0] as u8,
                 hour: parts[3] as u8,
                 minute: parts[4] as u8,
                 second: parts[5] as u8,
-                tz_hour: (parts[6] / 100) as u8,
-                tz_minute: (parts[6] % 100) as u8,
+                tz_hour: (tz / 100) as u8,
+                tz_minute: (tz % 100) as u8,
                 tz_before_gmt: !is_plus,
+            };
+            if !date.is_valid() {
+                self.add_diagnostic(DiagnosticReason::InvalidDate, offset_start);
+            }
+            HeaderValue::DateTime(date)
+        } else if pos >= 2
+            && parts_sizes[0] < 2
+            && parts_sizes[2] < 4
+            && month_name.is_some_and(|month| month != 0)
+        {
+            // A day, month name and year without a time of day, the missing
+            // fields are set out of range so that the date is not valid
+            self.add_diagnostic(DiagnosticReason::InvalidDate, offset_start);
+            HeaderValue::DateTime(DateTime {
+                year,
+                month: month_name.unwrap_or_default(),
+                day: parts[0] as u8,
+                hour: u8::MAX,
+                minute: u8::MAX,
+                second: u8::MAX,
+                tz_hour: 0,
+                tz_minute: 0,
+                tz_before_gmt: true,
             })
         } else {
+            self.add_diagnostic(DiagnosticReason::InvalidDate, offset_start);
             HeaderValue::Empty
         }
     }
@@ -511,4 +712,200 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn timestamp_roundtrip() {
+        let mut timestamps = vec![
+            0,
+            -1,
+            86399,
+            86400,
+            -86400,
+            -86401,
+            951782400,  // 2000-02-29
+            951868799,  // 2000-02-29T23:59:59Z
+            4107542400, // 2100-03-01
+            -2208988800,
+        ];
+        timestamps.extend((-2208988800i64..32503680000).step_by(7654321));
+
+        for timestamp in timestamps {
+            let datetime = crate::DateTime::from_timestamp(timestamp);
+            assert_eq!(datetime.to_timestamp(), timestamp, "{datetime}");
+            assert_eq!(datetime.to_timestamp_local(), timestamp);
+            assert_eq!(
+                datetime.to_rfc3339(),
+                Utc.timestamp_opt(timestamp, 0)
+                    .unwrap()
+                    .to_rfc3339_opts(SecondsFormat::Secs, true)
+            );
+            assert!(!datetime.tz_before_gmt && datetime.tz_hour == 0 && datetime.tz_minute == 0);
+        }
+    }
+
+    #[test]
+    fn datetime_to_rfc2822() {
+        for (date, expected) in [
+            (
+                "Tue, 1 Jul 2003 10:52:37 +0200",
+                "Tue, 1 Jul 2003 10:52:37 +0200",
+            ),
+            ("1 jan 94 00:00 EST", "Sat, 1 Jan 1994 00:00:00 -0500"),
+            (
+                "29 Feb 2000 23:59:59 +0000",
+                "Tue, 29 Feb 2000 23:59:59 +0000",
+            ),
+            (
+                "1 Jan 1900 00:00:00 -0000",
+                "Mon, 1 Jan 1900 00:00:00 -0000",
+            ),
+            (
+                "31 Dec 1999 12:00:00 +0530",
+                "Fri, 31 Dec 1999 12:00:00 +0530",
+            ),
+            (
+                "1 Mar 2100 08:00:00 +0100",
+                "Mon, 1 Mar 2100 08:00:00 +0100",
+            ),
+            ("13 Foo 2000 08:00:00 +0100", ""),
+        ] {
+            let datetime = MessageStream::new(format!("{date}\n").as_bytes())
+                .parse_date()
+                .into_datetime()
+                .unwrap();
+            assert_eq!(datetime.to_rfc2822(), expected, "failed for {date:?}");
+            assert_eq!(datetime.to_string(), expected, "failed for {date:?}");
+        }
+
+        assert_eq!(
+            crate::DateTime::from_timestamp(0).to_rfc2822(),
+            "Thu, 1 Jan 1970 00:00:00 +0000"
+        );
+    }
+
+    #[test]
+    fn datetime_ordering() {
+        let parse = |date: &str| {
+            MessageStream::new(format!("{date}\n").as_bytes())
+                .parse_date()
+                .into_datetime()
+                .unwrap()
+        };
+        let invalid = crate::DateTime {
+            year: 2024,
+            month: 13,
+            day: 1,
+            hour: 0,
+            minute: 0,
+            second: 0,
+            tz_before_gmt: false,
+            tz_hour: 0,
+            tz_minute: 0,
+        };
+
+        let mut dates = [
+            parse("Mon, 1 Jan 2024 00:00:00 +0000"),
+            invalid,
+            parse("Sun, 31 Dec 2023 23:00:00 +0000"),
+            parse("Mon, 1 Jan 2024 00:00:00 +0100"),
+            parse("Sun, 31 Dec 2023 18:00:00 -0500"),
+        ];
+        dates.sort();
+        assert_eq!(
+            dates.iter().map(|d| d.to_rfc3339()).collect::<Vec<_>>(),
+            [
+                "2024-13-01T00:00:00Z",
+                "2023-12-31T18:00:00-05:00",
+                "2023-12-31T23:00:00Z",
+                "2024-01-01T00:00:00+01:00",
+                "2024-01-01T00:00:00Z",
+            ]
+        );
+
+        // Same instant in different timezones
+        assert!(dates[1] != dates[2]);
+        assert_ne!(dates[1].cmp(&dates[2]), core::cmp::Ordering::Equal);
+        assert_eq!(dates[1].to_timestamp(), dates[2].to_timestamp());
+
+        let map = dates
+            .iter()
+            .map(|d| (*d, d.to_rfc3339()))
+            .collect::<alloc::collections::BTreeMap<_, _>>();
+        assert_eq!(map.len(), dates.len());
+        assert_eq!(map.keys().next(), Some(&invalid));
+    }
+
+    #[cfg(feature = "chrono")]
+    #[test]
+    fn datetime_to_chrono() {
+        for (date, expected) in [
+            (
+                "Mon, 1 Jan 2024 10:30:00 +0000",
+                "2024-01-01T10:30:00+00:00",
+            ),
+            (
+                "Mon, 1 Jan 2024 10:30:00 +0130",
+                "2024-01-01T10:30:00+01:30",
+            ),
+            (
+                "Mon, 1 Jan 2024 10:30:00 -0500",
+                "2024-01-01T10:30:00-05:00",
+            ),
+            (
+                "Mon, 1 Jan 2024 10:30:00 -0030",
+                "2024-01-01T10:30:00-00:30",
+            ),
+        ] {
+            let datetime = MessageStream::new(format!("{date}\n").as_bytes())
+                .parse_date()
+                .into_datetime()
+                .unwrap();
+            let chrono_datetime = chrono::DateTime::<FixedOffset>::try_from(&datetime).unwrap();
+            assert_eq!(
+                chrono_datetime.to_rfc3339_opts(SecondsFormat::Secs, false),
+                expected
+            );
+            assert_eq!(chrono_datetime.timestamp(), datetime.to_timestamp());
+        }
+
+        let mut invalid = crate::DateTime::from_timestamp(0);
+        invalid.month = 13;
+        assert_eq!(
+            chrono::DateTime::<FixedOffset>::try_from(&invalid),
+            Err(crate::InvalidDateTime)
+        );
+        invalid.month = 2;
+        invalid.day = 31;
+        assert!(chrono::DateTime::<FixedOffset>::try_from(&invalid).is_err());
+    }
+
+    #[cfg(feature = "time")]
+    #[test]
+    fn datetime_to_time() {
+        for (date, expected_offset) in [
+            ("Mon, 1 Jan 2024 10:30:00 +0000", 0),
+            ("Mon, 1 Jan 2024 10:30:00 +0130", 5400),
+            ("Mon, 1 Jan 2024 10:30:00 -0500", -18000),
+            ("Mon, 1 Jan 2024 10:30:00 -0030", -1800),
+        ] {
+            let datetime = MessageStream::new(format!("{date}\n").as_bytes())
+                .parse_date()
+                .into_datetime()
+                .unwrap();
+            let time_datetime = time::OffsetDateTime::try_from(&datetime).unwrap();
+            assert_eq!(time_datetime.offset().whole_seconds(), expected_offset);
+            assert_eq!(time_datetime.unix_timestamp(), datetime.to_timestamp());
+            assert_eq!(
+                (time_datetime.hour(), time_datetime.minute()),
+                (datetime.hour, datetime.minute)
+            );
+        }
+
+        let mut invalid = crate::DateTime::from_timestamp(0);
+        invalid.month = 13;
+        assert!(time::OffsetDateTime::try_from(&invalid).is_err());
+        invalid.month = 2;
+        invalid.day = 31;
+        assert!(time::OffsetDateTime::try_from(&invalid).is_err());
+    }
 }
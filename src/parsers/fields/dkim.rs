@@ -0,0 +1,138 @@
+/*
+ * Copyright Stalwart Labs Ltd. See the COPYING
+ * file at the top-level directory of this distribution.
+ *
+ * Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+ * https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+ * <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+ * option. This file may not be copied, modified, or distributed
+ * except according to those terms.
+ */
+
+use alloc::{borrow::Cow, string::String, vec::Vec};
+
+use crate::{parsers::MessageStream, DkimSignature};
+
+impl<'x> MessageStream<'x> {
+    /// Parses an RFC6376 DKIM-Signature tag-list.
+    pub fn parse_dkim_signature(&mut self) -> Option<DkimSignature<'x>> {
+        let mut signature = DkimSignature::default();
+
+        loop {
+            // Tag name
+            let (name, has_value) = self.dkim_token(true);
+            if has_value {
+                let value = self.dkim_token(false).0.unwrap_or_default();
+                if let Some(name) = name {
+                    if name == "h" {
+                        signature.signed_headers = match &value {
+                            Cow::Borrowed(value) => value
+                                .split(':')
+                                .filter(|name| !name.is_empty())
+                                .map(Cow::Borrowed)
+                                .collect(),
+                            Cow::Owned(value) => value
+                                .split(':')
+                                .filter(|name| !name.is_empty())
+                                .map(|name| Cow::Owned(name.into()))
+                                .collect(),
+                        };
+                    }
+                    signature.tags.push((name, value));
+                }
+            }
+
+            if self.is_eof() {
+                break;
+            }
+        }
+
+        if !signature.tags.is_empty() {
+            Some(signature)
+        } else {
+            None
+        }
+    }
+
+    /// Returns the next tag name (terminated by `=`) or tag value (terminated by `;`),
+    /// with whitespace and folding removed, and whether the terminator was found.
+    pub(crate) fn dkim_token(&mut self, is_name: bool) -> (Option<Cow<'x, str>>, bool) {
+        let mut token_start = usize::MAX;
+        let mut token_end = 0;
+        let mut token: Option<Vec<u8>> = None;
+
+        while let Some(&ch) = self.next() {
+            match ch {
+                b'=' if is_name => return (self.dkim_value(token_start, token_end, token), true),
+                b';' if !is_name => return (self.dkim_value(token_start, token_end, token), true),
+                b';' => {
+                    // Tag without a value
+                    return (None, false);
+                }
+                b'\n' => {
+                    if !self.peek_next_is_space() {
+                        self.seek_end();
+                        break;
+                    }
+                }
+                b' ' | b'\t' | b'\r' => (),
+                _ => {
+                    let pos = self.offset() - 1;
+                    if token_start == usize::MAX {
+                        token_start = pos;
+                    } else if let Some(token) = &mut token {
+                        token.push(ch);
+                    } else if token_end != pos {
+                        // Internal whitespace, the value has to be copied
+                        let mut value = self.bytes(token_start..token_end).to_vec();
+                        value.push(ch);
+                        token = Some(value);
+                    }
+                    token_end = pos + 1;
+                }
+            }
+        }
+
+        if is_name {
+            (None, false)
+        } else {
+            (self.dkim_value(token_start, token_end, token), true)
+        }
+    }
+
+    fn dkim_value(
+        &self,
+        token_start: usize,
+        token_end: usize,
+        token: Option<Vec<u8>>,
+    ) -> Option<Cow<'x, str>> {
+        match token {
+            Some(token) => Some(
+                String::from_utf8(token)
+                    .unwrap_or_else(|e| String::from_utf8_lossy(e.as_bytes()).into_owned())
+                    .into(),
+            ),
+            None if token_start != usize::MAX => {
+                Some(String::from_utf8_lossy(self.bytes(token_start..token_end)))
+            }
+            None => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parsers::{fields::load_tests, MessageStream};
+
+    #[test]
+    fn parse_dkim_signature() {
+        for test in load_tests("dkim.json") {
+            assert_eq!(
+                MessageStream::new(test.header.as_bytes()).parse_dkim_signature(),
+                test.expected,
+                "failed for {:?}",
+                test.header
+            );
+        }
+    }
+}
@@ -9,75 +9,129 @@
  * except according to those terms.
  */
 
-use crate::{parsers::MessageStream, HeaderValue};
+use crate::{parsers::MessageStream, DiagnosticReason, HeaderValue};
+use alloc::{borrow::Cow, string::String, vec::Vec};
+use core::ops::Range;
 
 impl<'x> MessageStream<'x> {
     pub fn parse_id(&mut self) -> HeaderValue<'x> {
+        let offset_start = self.offset();
         let mut token_start: usize = 0;
         let mut token_end: usize = 0;
         let mut token_invalid_start: usize = 0; // Handle broken clients
         let mut token_invalid_end: usize = 0; // Handle broken clients
         let mut is_id_part = false;
         let mut ids = Vec::new();
+        let mut invalid_ids = Vec::new();
+
+        loop {
+            let ch = match self.next() {
+                Some(b'\n') if self.try_next_is_space() => b' ',
+                Some(b'\n') | None => break,
+                Some(&ch) => ch,
+            };
 
-        while let Some(&ch) = self.next() {
             match ch {
-                b'\n' => {
-                    if !self.try_next_is_space() {
-                        return match ids.len() {
-                            1 => HeaderValue::Text(ids.pop().unwrap()),
-                            0 => {
-                                if token_invalid_start > 0 {
-                                    HeaderValue::Text(String::from_utf8_lossy(
-                                        self.bytes(token_invalid_start - 1..token_invalid_end),
-                                    ))
-                                } else {
-                                    HeaderValue::Empty
-                                }
-                            }
-                            _ => HeaderValue::TextList(ids),
-                        };
-                    } else {
-                        continue;
-                    }
+                b'<' | b'>' | b' ' | b'\t' | b'\r' if !is_id_part && token_start > 0 => {
+                    // Ids without angle brackets are separated by whitespace
+                    invalid_ids.push(self.bytes(token_start - 1..token_end));
+                    token_start = 0;
                 }
-                b'<' => {
-                    is_id_part = true;
-                    continue;
+                b'<' | b'>' if is_id_part && token_start > 0 => {
+                    // A missing closing bracket is tolerated when a new id starts
+                    ids.push(self.id_token(token_start..token_end));
+                    token_start = 0;
                 }
-                b'>' => {
-                    is_id_part = false;
-                    if token_start > 0 {
-                        ids.push(String::from_utf8_lossy(
-                            self.bytes(token_start - 1..token_end),
-                        ));
-                        token_start = 0;
-                    } else {
-                        continue;
+                _ => {}
+            }
+
+            match ch {
+                b'<' => is_id_part = true,
+                b'>' => is_id_part = false,
+                b' ' | b'\t' | b'\r' => (),
+                _ => {
+                    if token_start == 0 {
+                        token_start = self.offset();
+                    }
+                    token_end = self.offset();
+                    if !is_id_part {
+                        if token_invalid_start == 0 {
+                            token_invalid_start = self.offset();
+                        }
+                        token_invalid_end = self.offset();
                     }
                 }
-                b' ' | b'\t' | b'\r' => continue,
-                _ => {}
             }
+        }
+
+        if token_start > 0 {
             if is_id_part {
-                if token_start == 0 {
-                    token_start = self.offset();
-                }
-                token_end = self.offset();
+                ids.push(self.id_token(token_start..token_end));
             } else {
-                if token_invalid_start == 0 {
-                    token_invalid_start = self.offset();
-                }
-                token_invalid_end = self.offset();
+                invalid_ids.push(self.bytes(token_start - 1..token_end));
             }
         }
 
-        HeaderValue::Empty
+        if ids.is_empty() && token_invalid_start > 0 {
+            self.add_diagnostic(DiagnosticReason::InvalidMessageId, token_invalid_start - 1);
+            if invalid_ids.len() > 1 && invalid_ids.iter().all(|id| id.contains(&b'@')) {
+                ids.extend(invalid_ids.into_iter().map(String::from_utf8_lossy));
+            } else {
+                ids.push(String::from_utf8_lossy(
+                    self.bytes(token_invalid_start - 1..token_invalid_end),
+                ));
+            }
+        }
+
+        if ids.is_empty() || ids.iter().any(|id| !id.contains('@')) {
+            self.add_diagnostic(DiagnosticReason::InvalidMessageId, offset_start);
+        }
+
+        match ids.len() {
+            1 => HeaderValue::Text(ids.pop().unwrap()),
+            0 => HeaderValue::Empty,
+            _ => HeaderValue::TextList(ids),
+        }
+    }
+
+    /// Returns the id between the angle brackets, removing any whitespace
+    /// and comments
+    fn id_token(&self, range: Range<usize>) -> Cow<'x, str> {
+        let id = self.bytes(range.start - 1..range.end);
+        if id.iter().any(|&ch| ch == b'(' || ch.is_ascii_whitespace()) {
+            let mut comment_depth = 0;
+            let mut is_escaped = false;
+            let mut token = Vec::with_capacity(id.len());
+            for &ch in id {
+                match ch {
+                    _ if is_escaped => is_escaped = false,
+                    b'\\' if comment_depth > 0 => is_escaped = true,
+                    b'(' => comment_depth += 1,
+                    b')' if comment_depth > 0 => comment_depth -= 1,
+                    _ if comment_depth > 0 || ch.is_ascii_whitespace() => (),
+                    _ => token.push(ch),
+                }
+            }
+            String::from_utf8_lossy(&token).into_owned().into()
+        } else {
+            String::from_utf8_lossy(id)
+        }
     }
 }
+
+/// Returns `true` if a bracket-stripped message id has a non-empty left and
+/// right part separated by `@`, and no brackets or whitespace.
+pub(crate) fn is_valid_message_id(id: &str) -> bool {
+    id.rsplit_once('@')
+        .is_some_and(|(left, right)| !left.is_empty() && !right.is_empty())
+        && !id
+            .bytes()
+            .any(|ch| matches!(ch, b'<' | b'>') || ch.is_ascii_whitespace())
+}
+
 #[cfg(test)]
 mod tests {
-    use std::borrow::Cow;
+    use alloc::borrow::Cow;
 
     use crate::parsers::{fields::load_tests, MessageStream};
 
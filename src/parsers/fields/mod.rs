@@ -10,15 +10,108 @@
  */
 
 pub mod address;
+pub mod authentication_results;
+pub mod autocrypt;
 pub mod content_type;
 pub mod date;
+pub mod dkim;
 pub mod id;
 pub mod list;
 pub mod raw;
 pub mod received;
 pub mod thread;
+pub mod thread_index;
 pub mod unstructured;
 
+use crate::{HdrParseFnc, HeaderValue};
+use alloc::{borrow::Cow, format, string::String};
+
+use super::MessageStream;
+
+impl<'x> HeaderValue<'x> {
+    /// Parses a header value as text decoding RFC 2047 encoded words.
+    pub fn parse_text(value: &'x str) -> Self {
+        parse_value(value, |s| s.parse_unstructured())
+    }
+
+    /// Parses a header value as a RFC 5322 date.
+    pub fn parse_date(value: &'x str) -> Self {
+        parse_value(value, |s| s.parse_date())
+    }
+
+    /// Parses a header value as an address list or group.
+    pub fn parse_address(value: &'x str) -> Self {
+        parse_value(value, |s| s.parse_address())
+    }
+
+    /// Parses a header value as a list of message IDs.
+    pub fn parse_id(value: &'x str) -> Self {
+        parse_value(value, |s| s.parse_id())
+    }
+
+    /// Parses a header value as a MIME `Content-Type` or `Content-Disposition` type.
+    pub fn parse_content_type(value: &'x str) -> Self {
+        parse_value(value, |s| s.parse_content_type())
+    }
+
+    /// Parses a header value as a comma-separated list of values.
+    pub fn parse_comma_separated(value: &'x str) -> Self {
+        parse_value(value, |s| s.parse_comma_separared())
+    }
+
+    /// Parses a header value as a `Received` header.
+    pub fn parse_received(value: &'x str) -> Self {
+        parse_value(value, |s| s.parse_received())
+    }
+
+    /// Parses a header value as a raw string, no RFC 2047 decoding is done.
+    pub fn parse_raw(value: &'x str) -> Self {
+        parse_value(value, |s| s.parse_raw())
+    }
+}
+
+/// Field parsers expect the value to be terminated by a line feed,
+/// append one when missing.
+fn parse_value(value: &str, parse_fnc: HdrParseFnc) -> HeaderValue<'_> {
+    if value.ends_with('\n') {
+        parse_fnc(&mut MessageStream::new(value.as_bytes()))
+    } else {
+        let value = format!("{value}\n");
+        parse_fnc(&mut MessageStream::new(value.as_bytes())).into_owned()
+    }
+}
+
+/// Replaces the RFC 5322 comments in a header value, which may be nested
+/// and contain escaped characters, with a single space.
+pub(crate) fn remove_comments(value: &str) -> Cow<'_, str> {
+    if !value.contains('(') {
+        return Cow::Borrowed(value);
+    }
+
+    let mut result = String::with_capacity(value.len());
+    let mut depth = 0;
+    let mut chars = value.chars();
+
+    while let Some(ch) = chars.next() {
+        match ch {
+            '(' => {
+                if depth == 0 {
+                    result.push(' ');
+                }
+                depth += 1;
+            }
+            ')' if depth > 0 => depth -= 1,
+            '\\' if depth > 0 => {
+                chars.next();
+            }
+            _ if depth > 0 => (),
+            _ => result.push(ch),
+        }
+    }
+
+    result.into()
+}
+
 #[cfg(test)]
 use serde::{Deserialize, Serialize};
 
@@ -75,3 +168,60 @@ impl<T: Serialize> TestBuilder<T> {
         .unwrap();
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::{Addr, Address, HeaderValue};
+
+    #[test]
+    fn parse_header_values() {
+        for value in [
+            "Jane Doe <jane@example.com>",
+            "Jane Doe <jane@example.com>\r\n",
+        ] {
+            assert_eq!(
+                HeaderValue::parse_address(value),
+                HeaderValue::Address(Address::List(vec![Addr::new(
+                    "Jane Doe".into(),
+                    "jane@example.com"
+                )]))
+            );
+        }
+
+        let ct = HeaderValue::parse_content_type("text/plain; charset=utf-8")
+            .into_content_type()
+            .unwrap();
+        assert_eq!(ct.ctype(), "text");
+        assert_eq!(ct.subtype(), Some("plain"));
+        assert_eq!(ct.attribute("charset"), Some("utf-8"));
+
+        assert_eq!(
+            HeaderValue::parse_date("Sat, 20 Nov 2021 14:22:01 -0800\n")
+                .as_datetime()
+                .unwrap()
+                .to_rfc3339(),
+            "2021-11-20T14:22:01-08:00"
+        );
+        assert_eq!(
+            HeaderValue::parse_text("=?utf-8?b?4pi6?= smile").as_text(),
+            Some("☺ smile")
+        );
+        assert_eq!(
+            HeaderValue::parse_id("<a@b> <c@d>").as_text_list(),
+            Some(vec!["a@b", "c@d"])
+        );
+
+        for value in ["", "\n", "\r\n"] {
+            assert_eq!(HeaderValue::parse_text(value), HeaderValue::Empty);
+            assert_eq!(HeaderValue::parse_address(value), HeaderValue::Empty);
+            assert_eq!(HeaderValue::parse_content_type(value), HeaderValue::Empty);
+            assert_eq!(HeaderValue::parse_date(value), HeaderValue::Empty);
+            assert_eq!(HeaderValue::parse_id(value), HeaderValue::Empty);
+            assert_eq!(
+                HeaderValue::parse_comma_separated(value),
+                HeaderValue::Empty
+            );
+            assert_eq!(HeaderValue::parse_raw(value), HeaderValue::Empty);
+        }
+    }
+}
@@ -10,6 +10,7 @@
  */
 
 use crate::{parsers::MessageStream, HeaderValue};
+use alloc::string::String;
 
 impl<'x> MessageStream<'x> {
     pub fn parse_raw(&mut self) -> HeaderValue<'x> {
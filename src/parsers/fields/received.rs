@@ -9,7 +9,8 @@
  * except according to those terms.
  */
 
-use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use alloc::boxed::Box;
+use core::net::{IpAddr, Ipv4Addr, Ipv6Addr};
 
 use crate::{
     parsers::MessageStream, DateTime, Greeting, HeaderValue, Host, Protocol, Received, TlsVersion,
@@ -96,7 +97,7 @@ enum State {
 
 impl<'x> MessageStream<'x> {
     pub fn parse_received(&mut self) -> HeaderValue<'x> {
-        //let c = print!("-> {}", std::str::from_utf8(self.data).unwrap());
+        //let c = print!("-> {}", core::str::from_utf8(self.data).unwrap());
 
         let mut tokenizer = Tokenizer::new(self).peekable();
         let mut received = Received::default();
@@ -268,43 +269,32 @@ impl<'x> MessageStream<'x> {
                         }
                     }
                 }
-                Token::IpAddr(ip) => {
+                Token::IpAddr(ip)
                     if state == State::From
                         && (token.bracket_depth > 0
-                            || (token.comment_depth > 0 && received.from_ip.is_none()))
-                    {
-                        received.from_ip = Some(ip);
-                    }
+                            || (token.comment_depth > 0 && received.from_ip.is_none())) =>
+                {
+                    received.from_ip = Some(ip);
                 }
-                Token::Domain => {
-                    if state == State::From && token.comment_depth > 0 {
-                        received.from_iprev = Some(token.text.into());
-                    }
+                Token::Domain if state == State::From && token.comment_depth > 0 => {
+                    received.from_iprev = Some(token.text.into());
                 }
-                Token::Email => {
-                    if state == State::From {
-                        received.ident =
-                            Some(token.text.strip_suffix('@').unwrap_or(token.text).into());
-                    }
+                Token::Email if state == State::From => {
+                    received.ident =
+                        Some(token.text.strip_suffix('@').unwrap_or(token.text).into());
                 }
-                Token::Integer(num) => {
-                    if state == State::Date {
-                        if let Some(part) = date_part.next() {
-                            *part = num;
-                        }
+                Token::Integer(num) if state == State::Date => {
+                    if let Some(part) = date_part.next() {
+                        *part = num;
                     }
                 }
-                Token::Month(month) => {
-                    if state == State::Date {
-                        if let Some(part) = date_part.next() {
-                            *part = month.to_number();
-                        }
+                Token::Month(month) if state == State::Date => {
+                    if let Some(part) = date_part.next() {
+                        *part = month.to_number();
                     }
                 }
-                Token::Cipher => {
-                    if token.comment_depth > 0 || received.tls_cipher.is_none() {
-                        received.tls_cipher = Some(token.text.into());
-                    }
+                Token::Cipher if (token.comment_depth > 0 || received.tls_cipher.is_none()) => {
+                    received.tls_cipher = Some(token.text.into());
                 }
                 Token::TlsVersion(tls)
                     if token.comment_depth > 0 && received.tls_version.is_none() =>
@@ -396,6 +386,7 @@ impl<'x, 'y> Iterator for Tokenizer<'x, 'y> {
         let bracket_depth = self.bracket_depth;
 
         let mut start_pos = self.stream.offset();
+        let mut end_pos = usize::MAX;
 
         while let Some(ch) = self.stream.next() {
             match ch {
@@ -449,10 +440,12 @@ impl<'x, 'y> Iterator for Tokenizer<'x, 'y> {
                     n_minus += 1;
                 }
                 b'\n' => {
+                    let lf_pos = self.stream.offset() - 1;
                     if !self.stream.try_next_is_space() {
                         self.eof = true;
                         break;
                     } else if n_total > 0 {
+                        end_pos = lf_pos;
                         break;
                     } else {
                         start_pos += 1;
@@ -563,8 +556,10 @@ impl<'x, 'y> Iterator for Tokenizer<'x, 'y> {
             return self.next_token.take();
         }
 
-        let text = std::str::from_utf8(self.stream.bytes(start_pos..self.stream.offset() - 1))
-            .unwrap_or_default();
+        if end_pos == usize::MAX {
+            end_pos = self.stream.offset() - 1;
+        }
+        let text = core::str::from_utf8(self.stream.bytes(start_pos..end_pos)).unwrap_or_default();
 
         /*println!(
             "({}, {}, {}, {}, {}, {}, {}, {}, {}, {}, 0x{:x}) => Token::{},",
@@ -842,7 +837,10 @@ impl Token {
 #[cfg(test)]
 mod tests {
 
-    use crate::parsers::{fields::load_tests, MessageStream};
+    use crate::{
+        parsers::{fields::load_tests, MessageStream},
+        Host, MessageParser,
+    };
 
     #[test]
     fn parse_received() {
@@ -857,4 +855,24 @@ mod tests {
             );
         }
     }
+
+    #[test]
+    fn newest_received() {
+        let message = MessageParser::default()
+            .parse(concat!(
+                "Received: from relay.example.org ([192.0.2.2]) by mx.example.com\n",
+                "    with ESMTPS id 1234; Tue, 2 Jan 2024 10:00:00 +0000\n",
+                "Received: from client.example.net ([192.0.2.1]) by relay.example.org\n",
+                "    with ESMTP id 5678; Tue, 2 Jan 2024 09:59:58 +0000\n",
+                "Subject: Hops\n\n",
+                "Body\n"
+            ))
+            .unwrap();
+
+        assert_eq!(
+            message.received().unwrap().by(),
+            Some(&Host::Name("mx.example.com".into()))
+        );
+        assert_eq!(message.header_values("Received").count(), 2);
+    }
 }
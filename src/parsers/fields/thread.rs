@@ -9,6 +9,8 @@
  * except according to those terms.
  */
 
+use alloc::{borrow::Cow, string::String};
+
 static RE_PREFIXES: &[&str] = &[
     "re", "res", "sv", "antw", "ref", "aw", "απ", "השב", "vá", "r", "rif", "bls", "odp", "ynt",
     "atb", "رد", "回复", "转发",
@@ -90,7 +92,7 @@ pub fn thread_name(text: &str) -> &str {
                 seen_blob_header = false;
                 in_blob_ignore = false;
             }
-            ':' if !in_blob => {
+            ':' | '：' if !in_blob => {
                 if (seen_header && token_found) || (!seen_header && !token_found) {
                     break;
                 } else if !seen_header {
@@ -106,10 +108,10 @@ pub fn thread_name(text: &str) -> &str {
                 } else {
                     seen_header = false;
                 }
-                thread_name_start = pos + 1;
+                thread_name_start = pos + ch.len_utf8();
                 token_found = false;
             }
-            ':' if in_blob && !in_blob_ignore => {
+            ':' | '：' if in_blob && !in_blob_ignore => {
                 if token_end == 0 {
                     token_end = pos;
                 }
@@ -160,6 +162,28 @@ pub fn thread_name(text: &str) -> &str {
     trim_trailing_fwd(&text[thread_name_start..])
 }
 
+/// Returns the thread name of a subject with any runs of whitespace
+/// collapsed into a single space.
+pub fn normalized_thread_name(text: &str) -> Cow<'_, str> {
+    let name = thread_name(text);
+    if name.is_empty()
+        || name
+            .split(' ')
+            .all(|word| !word.is_empty() && !word.contains(char::is_whitespace))
+    {
+        return name.into();
+    }
+
+    let mut result = String::with_capacity(name.len());
+    for word in name.split_whitespace() {
+        if !result.is_empty() {
+            result.push(' ');
+        }
+        result.push_str(word);
+    }
+    result.into()
+}
+
 pub fn trim_trailing_fwd(text: &str) -> &str {
     let mut in_parentheses = false;
     let mut trim_end = true;
@@ -220,7 +244,7 @@ pub fn trim_trailing_fwd(text: &str) -> &str {
 
 #[cfg(test)]
 mod tests {
-    use crate::parsers::fields::thread::{thread_name, trim_trailing_fwd};
+    use crate::parsers::fields::thread::{normalized_thread_name, thread_name, trim_trailing_fwd};
 
     #[test]
     fn parse_thread_name() {
@@ -272,6 +296,12 @@ mod tests {
                 "hello world",
             ),
             ("[fwd: re: re: (fwd)] fwd[6]:re:  fw: (fwd)", ""),
+            ("回复：你好", "你好"),
+            ("RE：Fwd： hello", "hello"),
+            ("[list] Sv: Aw: hello", "hello"),
+            ("Review: hello", "Review: hello"),
+            ("Review the draft", "Review the draft"),
+            ("Re\u{3000}: hello\u{3000}", "hello"),
         ];
 
         for (input, expected) in tests {
@@ -305,4 +335,19 @@ mod tests {
             assert_eq!(trim_trailing_fwd(input), expected, "{input:?}");
         }
     }
+
+    #[test]
+    fn parse_normalized_thread_name() {
+        let tests = [
+            ("Re: hello world", "hello world"),
+            ("Re:  hello \t world  ", "hello world"),
+            ("[list] Fwd: hello\u{3000}\u{3000}world", "hello world"),
+            ("hello\r\n world", "hello world"),
+            ("", ""),
+        ];
+
+        for (input, expected) in tests {
+            assert_eq!(normalized_thread_name(input), expected, "{input:?}");
+        }
+    }
 }
@@ -0,0 +1,91 @@
+/*
+ * Copyright Stalwart Labs Ltd. See the COPYING
+ * file at the top-level directory of this distribution.
+ *
+ * Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+ * https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+ * <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+ * option. This file may not be copied, modified, or distributed
+ * except according to those terms.
+ */
+
+use crate::{decoders::base64::base64_decode, DateTime, ThreadIndex};
+
+const HEADER_BLOCK_LEN: usize = 22;
+const CHILD_BLOCK_LEN: usize = 5;
+
+// Seconds between 1601-01-01 and 1970-01-01
+const FILETIME_UNIX_OFFSET: i64 = 11_644_473_600;
+
+impl ThreadIndex {
+    /// Parses a base64 encoded Thread-Index value, made of a 22-byte header
+    /// block holding the truncated FILETIME of the conversation and its GUID,
+    /// followed by one 5-byte block for each reply or forward.
+    pub fn parse(value: &str) -> Option<ThreadIndex> {
+        let bytes = base64_decode(value.trim().as_bytes())?;
+        let children_len = bytes.len().checked_sub(HEADER_BLOCK_LEN)?;
+        if children_len % CHILD_BLOCK_LEN != 0 {
+            return None;
+        }
+
+        // The header block stores the 48 most significant bits of the FILETIME
+        let mut filetime = [0u8; 8];
+        filetime[..6].copy_from_slice(&bytes[..6]);
+        let filetime = u64::from_be_bytes(filetime);
+
+        Some(ThreadIndex {
+            date: DateTime::from_timestamp((filetime / 10_000_000) as i64 - FILETIME_UNIX_OFFSET),
+            guid: bytes[6..HEADER_BLOCK_LEN].try_into().ok()?,
+            child_blocks: children_len / CHILD_BLOCK_LEN,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{DateTime, ThreadIndex};
+
+    #[test]
+    fn parse_thread_index() {
+        let guid = [
+            0x10, 0x11, 0x12, 0x13, 0x14, 0x15, 0x16, 0x17, 0x18, 0x19, 0x1a, 0x1b, 0x1c, 0x1d,
+            0x1e, 0x1f,
+        ];
+        let date = DateTime {
+            year: 2024,
+            month: 1,
+            day: 15,
+            hour: 10,
+            minute: 30,
+            second: 0,
+            tz_before_gmt: false,
+            tz_hour: 0,
+            tz_minute: 0,
+        };
+
+        for (input, child_blocks) in [
+            ("AdpHnct5EBESExQVFhcYGRobHB0eHw==", 0),
+            ("AdpHnct5EBESExQVFhcYGRobHB0eHwAAEjRW", 1),
+            (" AdpHnct5EBESExQVFhcYGRob\n HB0eHwAAEjRW\n", 1),
+        ] {
+            assert_eq!(
+                ThreadIndex::parse(input),
+                Some(ThreadIndex {
+                    date,
+                    guid,
+                    child_blocks
+                }),
+                "failed for {input:?}"
+            );
+        }
+
+        for input in [
+            "",
+            "AdpHnct5EBESExQVFhcYGRobHB0e",
+            "AdpHnct5EBESExQVFhcYGRobHB0eHwAA",
+            "AdpHnct5EBES!xQVFhcYGRobHB0eHw==",
+        ] {
+            assert_eq!(ThreadIndex::parse(input), None, "failed for {input:?}");
+        }
+    }
+}
@@ -9,7 +9,7 @@
  * except according to those terms.
  */
 
-use std::borrow::Cow;
+use alloc::{borrow::Cow, string::String, vec::Vec};
 
 use crate::{parsers::MessageStream, HeaderValue};
 struct UnstructuredParser<'x> {
@@ -17,12 +17,21 @@ struct UnstructuredParser<'x> {
     token_end: usize,
     tokens: Vec<Cow<'x, str>>,
     last_is_encoded: bool,
+    /// Whitespace was found after the last non-whitespace character
+    has_space: bool,
+    /// Whitespace was found before the current token
+    token_has_space: bool,
 }
 
 impl<'x> UnstructuredParser<'x> {
+    fn start_token(&mut self, offset: usize) {
+        self.token_start = offset;
+        self.token_has_space = self.has_space;
+    }
+
     fn add_token(&mut self, stream: &MessageStream<'x>) {
         if self.token_start > 0 {
-            if !self.tokens.is_empty() {
+            if !self.tokens.is_empty() && self.token_has_space {
                 self.tokens.push(" ".into());
             }
             self.tokens.push(String::from_utf8_lossy(
@@ -35,21 +44,29 @@ impl<'x> UnstructuredParser<'x> {
     }
 
     fn add_rfc2047(&mut self, token: String) {
-        if !self.last_is_encoded {
+        // Only the whitespace between two encoded-words is removed (RFC2047)
+        if !self.last_is_encoded && self.has_space {
             self.tokens.push(" ".into());
         }
         self.tokens.push(token.into());
         self.last_is_encoded = true;
+        self.has_space = false;
     }
 }
 
 impl<'x> MessageStream<'x> {
     pub fn parse_unstructured(&mut self) -> HeaderValue<'x> {
+        if self.preserve_whitespace {
+            return self.parse_unstructured_preserve();
+        }
+
         let mut parser = UnstructuredParser {
             token_start: 0,
             token_end: 0,
             tokens: Vec::new(),
             last_is_encoded: true,
+            has_space: false,
+            token_has_space: false,
         };
 
         while let Some(ch) = self.next() {
@@ -64,10 +81,15 @@ impl<'x> MessageStream<'x> {
                             _ => HeaderValue::Text(parser.tokens.concat().into()),
                         };
                     } else {
+                        parser.has_space = true;
                         continue;
                     }
                 }
-                b' ' | b'\t' | b'\r' => {
+                b' ' | b'\t' => {
+                    parser.has_space = true;
+                    continue;
+                }
+                b'\r' => {
                     continue;
                 }
                 b'=' if self.peek_char(b'?') => {
@@ -83,10 +105,90 @@ impl<'x> MessageStream<'x> {
             }
 
             if parser.token_start == 0 {
-                parser.token_start = self.offset();
+                parser.start_token(self.offset());
             }
 
             parser.token_end = self.offset();
+            parser.has_space = false;
+        }
+
+        HeaderValue::Empty
+    }
+
+    /// Parses an unstructured value removing only its line breaks, the
+    /// whitespace between tokens and around the value is kept as found.
+    /// As required by RFC2047, whitespace between encoded-words is removed.
+    fn parse_unstructured_preserve(&mut self) -> HeaderValue<'x> {
+        // Tokens along with whether they are decoded encoded-words
+        let mut tokens: Vec<(Cow<'x, str>, bool)> = Vec::new();
+        let mut token_start = self.offset();
+
+        while let Some(ch) = self.next() {
+            match ch {
+                b'\n' => {
+                    let mut token_end = self.offset() - 1;
+                    if token_end > token_start && self.bytes(token_end - 1..token_end) == b"\r" {
+                        token_end -= 1;
+                    }
+                    if token_end > token_start {
+                        tokens.push((
+                            String::from_utf8_lossy(self.bytes(token_start..token_end)),
+                            false,
+                        ));
+                    }
+
+                    if self.try_next_is_space() {
+                        token_start = self.offset() - 1;
+                        continue;
+                    }
+
+                    // Remove whitespace between encoded-words
+                    let mut pos = 0;
+                    while pos < tokens.len() {
+                        if tokens[pos].1 {
+                            let next = tokens[pos + 1..]
+                                .iter()
+                                .position(|(token, is_encoded)| {
+                                    *is_encoded || !token.trim().is_empty()
+                                })
+                                .map(|next| pos + 1 + next);
+                            if let Some(next) = next.filter(|&next| tokens[next].1) {
+                                tokens.drain(pos + 1..next);
+                            }
+                        }
+                        pos += 1;
+                    }
+
+                    return match tokens.len() {
+                        1 => HeaderValue::Text(tokens.pop().unwrap().0),
+                        0 => HeaderValue::Empty,
+                        _ => HeaderValue::Text(
+                            tokens
+                                .into_iter()
+                                .map(|(token, _)| token)
+                                .collect::<String>()
+                                .into(),
+                        ),
+                    };
+                }
+                b'=' if self.peek_char(b'?') => {
+                    let token_end = self.offset() - 1;
+                    self.checkpoint();
+                    if let Some(token) = self.decode_rfc2047() {
+                        if token_end > token_start {
+                            tokens.push((
+                                String::from_utf8_lossy(self.bytes(token_start..token_end)),
+                                false,
+                            ));
+                        }
+                        tokens.push((token.into(), true));
+                        token_start = self.offset();
+                        continue;
+                    }
+                    self.restore();
+                }
+                _ => (),
+            }
         }
 
         HeaderValue::Empty
@@ -95,7 +197,10 @@ impl<'x> MessageStream<'x> {
 
 #[cfg(test)]
 mod tests {
-    use crate::parsers::{fields::load_tests, MessageStream};
+    use crate::{
+        parsers::{fields::load_tests, MessageStream},
+        HeaderValue,
+    };
 
     #[test]
     fn parse_unstructured() {
@@ -110,4 +215,29 @@ mod tests {
             );
         }
     }
+
+    #[test]
+    fn parse_unstructured_preserve_whitespace() {
+        for (input, expected) in [
+            (" Hello   world \n", " Hello   world "),
+            (" Hello\r\n\t  world\r\n", " Hello\t  world"),
+            ("Tight\n", "Tight"),
+            (" =?utf-8?Q?a?=  =?utf-8?Q?b?=  c\n", " ab  c"),
+            (" =?utf-8?Q?a?= \n =?utf-8?Q?b?=\n", " ab"),
+            (" x =?utf-8?Q?caf=C3=A9?= \n", " x café "),
+            (" =? not encoded  \n", " =? not encoded  "),
+        ] {
+            let mut stream = MessageStream::new(input.as_bytes());
+            stream.preserve_whitespace = true;
+            assert_eq!(
+                stream.parse_unstructured().unwrap_text(),
+                expected,
+                "failed for {input:?}"
+            );
+        }
+
+        let mut stream = MessageStream::new(b"\n");
+        stream.preserve_whitespace = true;
+        assert_eq!(stream.parse_unstructured(), HeaderValue::Empty);
+    }
 }
@@ -9,14 +9,18 @@
  * except according to those terms.
  */
 
-use std::borrow::Cow;
+use alloc::{borrow::Cow, string::String, vec::Vec};
+use core::{convert::Infallible, str::FromStr};
 
-use crate::{Header, HeaderName, MessageParser};
+use crate::{Header, HeaderName, HeaderValue, MessageParser};
 
 use super::MessageStream;
 
 impl<'x> MessageStream<'x> {
     pub fn parse_headers(&mut self, conf: &MessageParser, headers: &mut Vec<Header<'x>>) -> bool {
+        let offset_start = self.offset();
+        self.has_header_overflow = false;
+
         loop {
             loop {
                 match self.peek() {
@@ -36,56 +40,14 @@ impl<'x> MessageStream<'x> {
 
             let offset_field = self.offset();
 
+            if headers.len() >= conf.max_headers {
+                self.has_header_overflow = true;
+                return self.skip_headers();
+            }
+
             if let Some(header_name) = self.parse_header_name() {
                 let from_offset = self.offset();
-                let value = if conf.header_map.is_empty() {
-                    match &header_name {
-                        HeaderName::Subject
-                        | HeaderName::Comments
-                        | HeaderName::ContentDescription
-                        | HeaderName::ContentLocation
-                        | HeaderName::ContentTransferEncoding => self.parse_unstructured(),
-                        HeaderName::From
-                        | HeaderName::To
-                        | HeaderName::Cc
-                        | HeaderName::Bcc
-                        | HeaderName::ReplyTo
-                        | HeaderName::Sender
-                        | HeaderName::ResentTo
-                        | HeaderName::ResentFrom
-                        | HeaderName::ResentBcc
-                        | HeaderName::ResentCc
-                        | HeaderName::ResentSender
-                        | HeaderName::ListArchive
-                        | HeaderName::ListHelp
-                        | HeaderName::ListId
-                        | HeaderName::ListOwner
-                        | HeaderName::ListPost
-                        | HeaderName::ListSubscribe
-                        | HeaderName::ListUnsubscribe => self.parse_address(),
-                        HeaderName::Date | HeaderName::ResentDate => self.parse_date(),
-                        HeaderName::MessageId
-                        | HeaderName::References
-                        | HeaderName::InReplyTo
-                        | HeaderName::ReturnPath
-                        | HeaderName::ContentId
-                        | HeaderName::ResentMessageId => self.parse_id(),
-                        HeaderName::Keywords | HeaderName::ContentLanguage => {
-                            self.parse_comma_separared()
-                        }
-                        HeaderName::Received => self.parse_received(),
-                        HeaderName::MimeVersion => self.parse_raw(),
-                        HeaderName::ContentType | HeaderName::ContentDisposition => {
-                            self.parse_content_type()
-                        }
-                        HeaderName::Other(_) => self.parse_raw(),
-                    }
-                } else {
-                    (conf
-                        .header_map
-                        .get(&header_name)
-                        .unwrap_or(&conf.def_hdr_parse_fnc))(self)
-                };
+                let value = self.parse_header_field(conf, &header_name);
 
                 headers.push(Header {
                     name: header_name,
@@ -93,13 +55,100 @@ impl<'x> MessageStream<'x> {
                     offset_field,
                     offset_start: from_offset,
                     offset_end: self.offset(),
+                    raw: conf
+                        .preserve_raw_headers
+                        .then(|| strip_line_break(self.bytes(from_offset..self.offset())).into()),
                 });
+
+                if self.offset() - offset_start > conf.max_header_bytes {
+                    headers.pop();
+                    self.has_header_overflow = true;
+                    return self.skip_headers();
+                }
             } else if self.is_eof() {
                 return false;
             }
         }
     }
 
+    /// Skips the remaining header lines up to and including the empty line
+    /// that starts the body, returning `false` if the end of the message is reached.
+    fn skip_headers(&mut self) -> bool {
+        let mut is_line_start = true;
+        for ch in self.by_ref() {
+            match ch {
+                b'\n' if is_line_start => return true,
+                b'\n' => is_line_start = true,
+                b'\r' => (),
+                _ => is_line_start = false,
+            }
+        }
+        false
+    }
+
+    /// Parses a header value using the parser configured for the header name.
+    pub(crate) fn parse_header_field(
+        &mut self,
+        conf: &MessageParser,
+        header_name: &HeaderName<'x>,
+    ) -> HeaderValue<'x> {
+        match conf
+            .header_map
+            .get(header_name)
+            .or(conf.def_hdr_parse_fnc.as_ref())
+        {
+            Some(parse_fnc) => parse_fnc(self),
+            None => self.parse_header_value(header_name),
+        }
+    }
+
+    /// Parses a header value using the parser that matches the header's semantics.
+    pub(crate) fn parse_header_value(&mut self, header_name: &HeaderName<'_>) -> HeaderValue<'x> {
+        match &header_name {
+            HeaderName::Subject
+            | HeaderName::Comments
+            | HeaderName::ContentDescription
+            | HeaderName::ContentLocation
+            | HeaderName::ContentTransferEncoding => self.parse_unstructured(),
+            HeaderName::From
+            | HeaderName::To
+            | HeaderName::Cc
+            | HeaderName::Bcc
+            | HeaderName::ReplyTo
+            | HeaderName::Sender
+            | HeaderName::ResentTo
+            | HeaderName::ResentFrom
+            | HeaderName::ResentBcc
+            | HeaderName::ResentCc
+            | HeaderName::ResentSender
+            | HeaderName::ListArchive
+            | HeaderName::ListHelp
+            | HeaderName::ListId
+            | HeaderName::ListOwner
+            | HeaderName::ListPost
+            | HeaderName::ListSubscribe
+            | HeaderName::ListUnsubscribe => self.parse_address(),
+            HeaderName::Date | HeaderName::ResentDate => self.parse_date(),
+            HeaderName::MessageId
+            | HeaderName::References
+            | HeaderName::InReplyTo
+            | HeaderName::ReturnPath
+            | HeaderName::ContentId
+            | HeaderName::ResentMessageId => self.parse_id(),
+            HeaderName::Keywords | HeaderName::ContentLanguage => self.parse_comma_separared(),
+            HeaderName::Received => self.parse_received(),
+            HeaderName::MimeVersion => self.parse_raw(),
+            HeaderName::ContentType | HeaderName::ContentDisposition => self.parse_content_type(),
+            HeaderName::Other(name)
+                if name.eq_ignore_ascii_case("Disposition-Notification-To")
+                    || name.eq_ignore_ascii_case("Return-Receipt-To") =>
+            {
+                self.parse_address()
+            }
+            HeaderName::Other(_) => self.parse_raw(),
+        }
+    }
+
     pub fn parse_header_name(&mut self) -> Option<HeaderName<'x>> {
         let mut token_start: usize = 0;
         let mut token_end: usize = 0;
@@ -173,6 +222,12 @@ impl<'x> MessageStream<'x> {
     }
 }
 
+/// Removes the line break that terminates a raw header value
+pub(crate) fn strip_line_break(bytes: &[u8]) -> &[u8] {
+    let bytes = bytes.strip_suffix(b"\n").unwrap_or(bytes);
+    bytes.strip_suffix(b"\r").unwrap_or(bytes)
+}
+
 impl<'x> HeaderName<'x> {
     /// Parse a header name
     pub fn parse(data: impl Into<Cow<'x, str>>) -> Option<HeaderName<'x>> {
@@ -226,6 +281,39 @@ impl<'x> HeaderName<'x> {
             None
         }
     }
+
+    /// Parses a raw header value with the parser that matches this header,
+    /// unknown headers are parsed as unstructured text.
+    pub fn parse_value<'y>(&self, value: &'y [u8]) -> HeaderValue<'y> {
+        fn parse<'y>(name: &HeaderName<'_>, value: &'y [u8]) -> HeaderValue<'y> {
+            let mut stream = MessageStream::new(value);
+            match name {
+                HeaderName::Other(_) => stream.parse_unstructured(),
+                _ => stream.parse_header_value(name),
+            }
+        }
+
+        if value.ends_with(b"\n") {
+            parse(self, value)
+        } else {
+            let mut value = value.to_vec();
+            value.push(b'\n');
+            parse(self, &value).into_owned()
+        }
+    }
+}
+
+impl FromStr for HeaderName<'static> {
+    type Err = Infallible;
+
+    /// Parses a header name into its known variant ignoring case, falling back
+    /// to [`HeaderName::Other`] with the original casing preserved.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(HeaderName::parse(s).map_or_else(
+            || HeaderName::Other(String::from(s).into()),
+            |name| name.into_owned(),
+        ))
+    }
 }
 
 static HDR_HASH: &[u8] = &[
@@ -388,7 +476,7 @@ static HDR_NAMES: &[&[u8]] = &[
 
 #[cfg(test)]
 mod tests {
-    use crate::{parsers::MessageStream, HeaderName};
+    use crate::{parsers::MessageStream, Addr, Address, HeaderName, HeaderValue};
 
     #[test]
     fn header_name_parse() {
@@ -415,4 +503,76 @@ mod tests {
             );
         }
     }
+
+    #[test]
+    fn header_name_from_str() {
+        for (input, expected, canonical) in [
+            ("message-id", HeaderName::MessageId, "Message-ID"),
+            ("MESSAGE-ID", HeaderName::MessageId, "Message-ID"),
+            ("mime-VERSION", HeaderName::MimeVersion, "MIME-Version"),
+            ("content-type", HeaderName::ContentType, "Content-Type"),
+            (
+                "X-Custom-Field",
+                HeaderName::Other("X-Custom-Field".into()),
+                "X-Custom-Field",
+            ),
+            ("x-lower", HeaderName::Other("x-lower".into()), "x-lower"),
+            (
+                "mal formed",
+                HeaderName::Other("mal formed".into()),
+                "mal formed",
+            ),
+        ] {
+            let name = input.parse::<HeaderName>().unwrap();
+            assert_eq!(name, expected, "failed for {input:?}");
+            assert_eq!(name.as_str(), canonical, "failed for {input:?}");
+        }
+    }
+
+    #[test]
+    fn header_name_parse_value() {
+        assert_eq!(
+            HeaderName::From.parse_value(b"Jane Doe <jane@example.com>"),
+            HeaderValue::Address(Address::List(vec![Addr::new(
+                "Jane Doe".into(),
+                "jane@example.com"
+            )]))
+        );
+        assert_eq!(
+            HeaderName::Date
+                .parse_value(b"Sat, 20 Nov 2021 14:22:01 -0800\r\n")
+                .as_datetime()
+                .unwrap()
+                .to_rfc3339(),
+            "2021-11-20T14:22:01-08:00"
+        );
+        assert_eq!(
+            HeaderName::MessageId
+                .parse_value(b" <a@example.com>")
+                .as_text(),
+            Some("a@example.com")
+        );
+        assert_eq!(
+            HeaderName::ContentType
+                .parse_value(b"text/plain; charset=utf-8")
+                .as_content_type()
+                .and_then(|ct| ct.attribute("charset")),
+            Some("utf-8")
+        );
+        assert_eq!(
+            HeaderName::Keywords.parse_value(b"one, two").as_text_list(),
+            Some(vec!["one", "two"])
+        );
+        assert_eq!(
+            HeaderName::MimeVersion.parse_value(b" 1.0").as_text(),
+            Some("1.0")
+        );
+        assert_eq!(
+            HeaderName::Other("X-Custom".into())
+                .parse_value(b" =?utf-8?b?4pi6?= smile")
+                .as_text(),
+            Some("☺ smile")
+        );
+        assert_eq!(HeaderName::Subject.parse_value(b""), HeaderValue::Empty);
+    }
 }
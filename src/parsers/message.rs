@@ -9,16 +9,36 @@
  * except according to those terms.
  */
 
-use std::borrow::Cow;
+use alloc::{borrow::Cow, boxed::Box, string::String, vec, vec::Vec};
+use core::{
+    fmt::{self, Display},
+    str::FromStr,
+};
 
 use crate::{
-    decoders::{charsets::map::charset_decoder, DecodeFnc},
-    ContentType, Encoding, GetHeader, HeaderName, HeaderValue, Message, MessageParser, MessagePart,
-    MessagePartId, PartType,
+    decoders::{
+        charsets::{map::charset_decoder_or, DecoderFnc},
+        flowed::decode_flowed,
+        uuencode::{uudecode, UuFile},
+        DecodeFnc,
+    },
+    ContentType, Encoding, GetHeader, Header, HeaderIndex, HeaderName, HeaderValue, Message,
+    MessageBuffers, MessageParser, MessagePart, MessagePartId, ParseError, PartType,
+};
+
+#[cfg(feature = "detect_charset")]
+use crate::decoders::charsets::{
+    detect::detect_charset,
+    map::{charset_decoder, is_utf8_or_ascii},
 };
 
+#[cfg(feature = "binhex")]
+use crate::decoders::binhex::binhex_decode;
+
 use super::MessageStream;
 
+/// Maximum number of nested messages with a transfer encoding, each of which
+/// requires a decoded copy of its contents
 const MAX_NESTED_ENCODED: usize = 3;
 
 #[derive(Debug, PartialEq, Default)]
@@ -36,6 +56,22 @@ enum MimeType {
     Other,
 }
 
+/// Returns the subtype assumed for a Content-Type without one, which for
+/// multiparts is "mixed" as required by RFC2046 for unrecognized subtypes.
+fn default_subtype(c_type: &str) -> Option<&'static str> {
+    if c_type.eq_ignore_ascii_case("text") {
+        Some("plain")
+    } else if c_type.eq_ignore_ascii_case("multipart") {
+        Some("mixed")
+    } else if c_type.eq_ignore_ascii_case("message") {
+        Some("rfc822")
+    } else if c_type.eq_ignore_ascii_case("application") {
+        Some("octet-stream")
+    } else {
+        None
+    }
+}
+
 #[inline(always)]
 fn mime_type(
     content_type: Option<&ContentType>,
@@ -111,10 +147,90 @@ impl MessageParser {
     /// `Message` struct.
     ///
     /// This function never panics, a best-effort is made to parse the message and
-    /// if no headers are found None is returned.
+    /// if no headers are found, or the message exceeds the maximum size, None is returned.
+    ///
+    /// Messages using lone CRs as line terminators are parsed as if each of them
+    /// was a LF, in which case the returned message owns a normalized copy of
+    /// the raw message.
     ///
     pub fn parse<'x>(&self, raw_message: impl IntoByteSlice<'x>) -> Option<Message<'x>> {
-        self.parse_(raw_message.into_byte_slice(), MAX_NESTED_ENCODED, false)
+        self.try_parse(raw_message).ok()
+    }
+
+    /// Parses a byte slice containing the RFC5322 raw message and returns a
+    /// `Message` struct, or the reason why the message could not be parsed.
+    ///
+    /// As with [`MessageParser::parse`], truncated messages are not an error:
+    /// whatever could be parsed is returned and the affected parts are flagged
+    /// with `is_encoding_problem`.
+    pub fn try_parse<'x>(
+        &self,
+        raw_message: impl IntoByteSlice<'x>,
+    ) -> Result<Message<'x>, ParseError> {
+        let raw_message = raw_message.into_byte_slice();
+        if raw_message.len() > self.max_size {
+            return Err(ParseError::TooLarge);
+        }
+        self.parse_(
+            raw_message,
+            self.max_nested_messages,
+            MAX_NESTED_ENCODED,
+            false,
+            &mut MessageBuffers::default(),
+        )
+        .ok_or_else(|| {
+            if raw_message.iter().all(|ch| ch.is_ascii_whitespace()) {
+                ParseError::Empty
+            } else {
+                ParseError::NoHeaders
+            }
+        })
+    }
+
+    /// Parses a byte slice containing the RFC5322 raw message and returns a
+    /// `Message` struct, exactly as [`MessageParser::parse`] would, but taking
+    /// the vectors that hold its parts, headers and part ids from `buffers`
+    /// instead of allocating new ones.
+    ///
+    /// The returned message only borrows from `raw_message`, it remains
+    /// usable after `buffers` is reused or dropped. Its vectors are returned
+    /// to the pool by passing it to [`MessageBuffers::recycle`], which makes
+    /// parsing large batches of messages allocate mostly for the decoded
+    /// contents only:
+    ///
+    /// ```
+    /// # use mail_parser::{MessageBuffers, MessageParser};
+    /// let parser = MessageParser::default();
+    /// let mut buffers = MessageBuffers::new();
+    /// for raw_message in ["Subject: first\n\nHi", "Subject: second\n\nBye"] {
+    ///     let message = parser.parse_into(raw_message, &mut buffers).unwrap();
+    ///     assert!(message.subject().is_some());
+    ///     buffers.recycle(message);
+    /// }
+    /// ```
+    pub fn parse_into<'x>(
+        &self,
+        raw_message: impl IntoByteSlice<'x>,
+        buffers: &mut MessageBuffers,
+    ) -> Option<Message<'x>> {
+        let raw_message = raw_message.into_byte_slice();
+        if raw_message.len() > self.max_size {
+            return None;
+        }
+        self.parse_(
+            raw_message,
+            self.max_nested_messages,
+            MAX_NESTED_ENCODED,
+            false,
+            buffers,
+        )
+    }
+
+    /// Parses a string containing the RFC5322 raw message and returns a
+    /// `Message` struct borrowing from it, exactly as [`MessageParser::parse`]
+    /// would on the same bytes.
+    pub fn parse_str<'x>(&self, raw_message: &'x str) -> Option<Message<'x>> {
+        self.parse(raw_message)
     }
 
     /// Parses a byte slice containing the RFC5322 raw message and returns a
@@ -123,23 +239,82 @@ impl MessageParser {
         &self,
         raw_message: impl IntoByteSlice<'x> + 'x,
     ) -> Option<Message<'x>> {
-        self.parse_(raw_message.into_byte_slice(), MAX_NESTED_ENCODED, true)
+        let raw_message = raw_message.into_byte_slice();
+        if raw_message.len() > self.max_size {
+            return None;
+        }
+        self.parse_(
+            raw_message,
+            self.max_nested_messages,
+            MAX_NESTED_ENCODED,
+            true,
+            &mut MessageBuffers::default(),
+        )
+    }
+
+    /// Reads an RFC5322 raw message from a `Read` stream and returns an
+    /// owned `Message` struct.
+    ///
+    /// The stream is read until EOF before parsing, so MIME boundaries
+    /// spanning multiple reads are handled transparently and all part offsets
+    /// are relative to the start of the stream.
+    ///
+    /// When a maximum size is set, reading stops once it is exceeded and an
+    /// error of kind `InvalidData` wrapping [`ParseError::TooLarge`] is returned.
+    #[cfg(feature = "std")]
+    pub fn parse_reader(
+        &self,
+        reader: impl std::io::Read,
+    ) -> std::io::Result<Option<Message<'static>>> {
+        use std::io::Read;
+
+        let mut raw_message = Vec::new();
+        reader
+            .take((self.max_size as u64).saturating_add(1))
+            .read_to_end(&mut raw_message)?;
+        if raw_message.len() > self.max_size {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                ParseError::TooLarge,
+            ));
+        }
+        Ok(self
+            .parse_(
+                &raw_message,
+                self.max_nested_messages,
+                MAX_NESTED_ENCODED,
+                false,
+                &mut MessageBuffers::default(),
+            )
+            .map(|message| message.into_owned()))
     }
 
     fn parse_<'x>(
         &self,
         raw_message: &'x [u8],
         depth: usize,
+        encoded_depth: usize,
         skip_body: bool,
+        buffers: &mut MessageBuffers,
     ) -> Option<Message<'x>> {
+        if has_bare_cr_line_endings(raw_message) {
+            // Lines terminated by a lone CR, parse as if each lone CR was a LF
+            let raw_message = normalize_bare_cr(raw_message);
+            return self
+                .parse_(&raw_message, depth, encoded_depth, skip_body, buffers)
+                .map(|message| message.into_owned());
+        }
+
         let mut stream = MessageStream::new(raw_message);
+        stream.default_charset = self.default_charset;
+        stream.preserve_whitespace = self.preserve_whitespace;
 
-        let mut message = Message::new();
+        let mut message = buffers.message();
 
         let mut state = MessageParserState::new();
-        let mut state_stack = Vec::with_capacity(4);
+        let mut state_stack: Vec<(MessageParserState, Option<Message<'x>>)> = Vec::with_capacity(4);
 
-        let mut part_headers = Vec::new();
+        let mut part_headers = buffers.headers();
 
         'outer: loop {
             // Parse headers
@@ -155,6 +330,16 @@ impl MessageParser {
             state.parts += 1;
             state.sub_part_ids.push(message.parts.len());
 
+            for header in part_headers.iter_mut() {
+                if let (HeaderName::ContentType, HeaderValue::ContentType(content_type)) =
+                    (&header.name, &mut header.value)
+                {
+                    if content_type.c_subtype.is_none() {
+                        content_type.c_subtype =
+                            default_subtype(&content_type.c_type).map(Cow::Borrowed);
+                    }
+                }
+            }
             let content_type = part_headers
                 .header_value(&HeaderName::ContentType)
                 .and_then(|c| c.as_content_type());
@@ -162,11 +347,23 @@ impl MessageParser {
             let (is_multipart, mut is_inline, mut is_text, mut mime_type) =
                 mime_type(content_type, &state.mime_type);
 
+            let mut is_too_deep = false;
+
             if is_multipart {
                 if let Some(mime_boundary) =
                     content_type.map_or_else(|| None, |f| f.attribute("boundary"))
                 {
-                    if stream.seek_next_part(mime_boundary.as_bytes()) {
+                    if state_stack
+                        .iter()
+                        .filter(|(_, message)| message.is_none())
+                        .count()
+                        >= self.max_multipart_depth
+                    {
+                        // Nesting limit reached, keep the remaining contents as a binary part
+                        mime_type = MimeType::Other;
+                        is_inline = false;
+                        is_too_deep = true;
+                    } else if stream.seek_next_part(mime_boundary.as_bytes()) {
                         let part_id = message.parts.len();
                         let new_state = MessageParserState {
                             in_alternative: state.in_alternative
@@ -178,14 +375,21 @@ impl MessageParser {
                             need_html_body: state.need_html_body,
                             need_text_body: state.need_text_body,
                             part_id,
+                            sub_part_ids: buffers.part_ids(),
                             ..Default::default()
                         };
                         //add_missing_type(&mut part_header, "text".into(), "plain".into());
                         message.parts.push(MessagePart {
-                            headers: std::mem::take(&mut part_headers),
+                            header_index: self.header_index(&part_headers),
+                            headers: core::mem::replace(&mut part_headers, buffers.headers()),
                             offset_header: state.offset_header,
                             offset_body: state.offset_body,
                             offset_end: 0,
+                            line_count: 0,
+                            decoded_charset: None,
+                            is_header_overflow: stream.has_header_overflow,
+                            uuencode_mode: None,
+                            binhex: None,
                             is_encoding_problem: false,
                             encoding: Encoding::None,
                             body: PartType::default(),
@@ -217,32 +421,52 @@ impl MessageParser {
                 }
                 _ => (Encoding::None, MessageStream::mime_part),
             };
+            let decode_fnc: DecodeFnc = if self.raw_bodies {
+                MessageStream::mime_part
+            } else {
+                decode_fnc
+            };
+
+            let nested_depth = depth.saturating_sub(
+                state_stack
+                    .iter()
+                    .filter(|(_, message)| message.is_some())
+                    .count(),
+            );
 
-            if mime_type == MimeType::Message && encoding == Encoding::None {
+            if mime_type == MimeType::Message && encoding == Encoding::None && nested_depth != 0 {
                 let new_state = MessageParserState {
                     mime_type: MimeType::Message,
                     mime_boundary: state.mime_boundary.take(),
                     need_html_body: true,
                     need_text_body: true,
                     part_id: message.parts.len(),
+                    sub_part_ids: buffers.part_ids(),
                     ..Default::default()
                 };
                 message.attachments.push(message.parts.len());
                 message.parts.push(MessagePart {
-                    headers: std::mem::take(&mut part_headers),
+                    header_index: self.header_index(&part_headers),
+                    headers: core::mem::replace(&mut part_headers, buffers.headers()),
                     encoding,
                     is_encoding_problem: false,
                     offset_header: state.offset_header,
                     offset_body: state.offset_body,
                     offset_end: 0,
+                    line_count: 0,
+                    decoded_charset: None,
+                    is_header_overflow: stream.has_header_overflow,
+                    uuencode_mode: None,
+                    binhex: None,
                     body: PartType::default(), // Temp value, will be replaced later.
                 });
                 state_stack.push((state, message.into()));
-                message = Message::new();
+                message = buffers.message();
                 state = new_state;
                 continue;
             }
 
+            stream.has_decode_errors = false;
             let (offset_end, mut bytes) = decode_fnc(
                 &mut stream,
                 state.mime_boundary.as_deref().unwrap_or(&b""[..]),
@@ -251,30 +475,51 @@ impl MessageParser {
             // Attempt to recover contents of an invalid message
             let mut is_encoding_problem = offset_end == usize::MAX;
             if is_encoding_problem {
-                encoding = Encoding::None;
-                mime_type = MimeType::TextOther;
-                is_inline = false;
-                is_text = true;
-
+                stream.checkpoint();
                 let (offset_end, boundary_found) =
                     stream.seek_part_end(state.mime_boundary.as_deref());
-                state.offset_end = offset_end;
-                bytes = stream.data[state.offset_body..state.offset_end].into();
 
-                if !boundary_found {
+                // The closing boundary is missing, decode the truncated part up to the end
+                let truncated = if !boundary_found {
                     state.mime_boundary = None;
+                    stream.restore();
+                    Some(decode_fnc(&mut stream, b""))
+                        .filter(|(offset_end, _)| *offset_end != usize::MAX)
+                } else {
+                    None
+                };
+
+                if let Some((offset_end, truncated_bytes)) = truncated {
+                    state.offset_end = offset_end;
+                    bytes = truncated_bytes;
+                } else {
+                    if !boundary_found {
+                        stream.seek_end();
+                    }
+                    encoding = Encoding::None;
+                    mime_type = MimeType::TextOther;
+                    is_inline = false;
+                    is_text = true;
+                    state.offset_end = offset_end;
+                    bytes = stream.data[state.offset_body..state.offset_end].into();
                 }
             } else {
                 state.offset_end = offset_end;
+                is_encoding_problem = is_too_deep || stream.has_decode_errors;
             }
 
+            #[cfg_attr(not(feature = "detect_charset"), allow(unused_mut))]
+            let mut decoded_charset = None;
+            let mut uu_files = Vec::new();
+            #[cfg_attr(not(feature = "binhex"), allow(unused_mut))]
+            let mut binhex = None;
             let body_part = if mime_type != MimeType::Message {
                 let is_inline = is_inline
                     && part_headers
                         .header_value(&HeaderName::ContentDisposition)
                         .map_or_else(
                             || true,
-                            |d| !d.as_content_type().map_or(false, |ct| ct.is_attachment()),
+                            |d| !d.as_content_type().is_some_and(|ct| ct.is_attachment()),
                         )
                     && (state.parts == 1
                         || (state.mime_type != MimeType::MultipartRelated
@@ -307,23 +552,6 @@ impl MessageParser {
                     };
 
                 if is_text {
-                    let text = match (
-                        bytes,
-                        content_type.and_then(|ct| {
-                            ct.attribute("charset")
-                                .and_then(|c| charset_decoder(c.as_bytes()))
-                        }),
-                    ) {
-                        (Cow::Owned(vec), Some(charset_decoder)) => charset_decoder(&vec).into(),
-                        (Cow::Owned(vec), None) => String::from_utf8(vec)
-                            .unwrap_or_else(|e| String::from_utf8_lossy(e.as_bytes()).into_owned())
-                            .into(),
-                        (Cow::Borrowed(bytes), Some(charset_decoder)) => {
-                            charset_decoder(bytes).into()
-                        }
-                        (Cow::Borrowed(bytes), None) => String::from_utf8_lossy(bytes),
-                    };
-
                     let is_html = mime_type == MimeType::TextHtml;
 
                     if add_to_html && !is_html {
@@ -340,10 +568,66 @@ impl MessageParser {
                         message.attachments.push(message.parts.len());
                     }
 
-                    if is_html {
-                        PartType::Html(text)
+                    if self.raw_bodies && core::str::from_utf8(&bytes).is_err() {
+                        // Raw text that is not valid UTF-8 is kept as bytes
+                        PartType::Binary(bytes)
                     } else {
-                        PartType::Text(text)
+                        let charset = content_type.and_then(|ct| ct.attribute("charset"));
+                        let charset_decoder = match charset {
+                            _ if self.raw_bodies => None,
+                            Some(charset) => {
+                                charset_decoder_or(charset.as_bytes(), self.default_charset)
+                            }
+                            None => self.default_charset,
+                        };
+                        #[cfg(feature = "detect_charset")]
+                        let text = if self.detect_charset && !self.raw_bodies {
+                            let (text, charset) = decode_with_detection(bytes, charset);
+                            decoded_charset = Some(charset);
+                            text
+                        } else {
+                            decode_text(bytes, charset_decoder)
+                        };
+                        #[cfg(not(feature = "detect_charset"))]
+                        let text = decode_text(bytes, charset_decoder);
+                        let text = match content_type {
+                            Some(ct)
+                                if self.decode_flowed
+                                    && !self.raw_bodies
+                                    && mime_type == MimeType::TextPlain
+                                    && ct
+                                        .attribute("format")
+                                        .is_some_and(|f| f.eq_ignore_ascii_case("flowed")) =>
+                            {
+                                decode_flowed(
+                                    &text,
+                                    ct.attribute("delsp")
+                                        .is_some_and(|d| d.eq_ignore_ascii_case("yes")),
+                                )
+                                .into()
+                            }
+                            _ => text,
+                        };
+                        let text = if self.uudecode
+                            && !self.raw_bodies
+                            && mime_type == MimeType::TextPlain
+                        {
+                            match uudecode(&text) {
+                                Some((remaining, files)) => {
+                                    uu_files = files;
+                                    remaining.into()
+                                }
+                                None => text,
+                            }
+                        } else {
+                            text
+                        };
+
+                        if is_html {
+                            PartType::Html(text)
+                        } else {
+                            PartType::Text(text)
+                        }
                     }
                 } else {
                     if add_to_html {
@@ -355,6 +639,29 @@ impl MessageParser {
 
                     message.attachments.push(message.parts.len());
 
+                    #[cfg(feature = "binhex")]
+                    let bytes = if self.binhex
+                        && !self.raw_bodies
+                        && content_type.is_some_and(|ct| {
+                            ct.ctype().eq_ignore_ascii_case("application")
+                                && ct
+                                    .subtype()
+                                    .is_some_and(|st| st.eq_ignore_ascii_case("mac-binhex40"))
+                        }) {
+                        match binhex_decode(&bytes) {
+                            Some((header, data)) => {
+                                binhex = Some(Box::new(header));
+                                data.into()
+                            }
+                            None => {
+                                is_encoding_problem = true;
+                                bytes
+                            }
+                        }
+                    } else {
+                        bytes
+                    };
+
                     if !is_inline {
                         PartType::Binary(bytes)
                     } else {
@@ -364,8 +671,17 @@ impl MessageParser {
             } else {
                 message.attachments.push(message.parts.len());
 
-                if depth != 0 {
-                    if let Some(nested_message) = self.parse_(bytes.as_ref(), depth - 1, false) {
+                if self.raw_bodies && encoding != Encoding::None {
+                    // Encoded messages can't be parsed without decoding them first
+                    PartType::Binary(bytes)
+                } else if nested_depth != 0 && encoded_depth != 0 {
+                    if let Some(nested_message) = self.parse_(
+                        bytes.as_ref(),
+                        nested_depth - 1,
+                        encoded_depth - 1,
+                        false,
+                        buffers,
+                    ) {
                         PartType::Message(Message {
                             html_body: nested_message.html_body,
                             text_body: nested_message.text_body,
@@ -388,15 +704,35 @@ impl MessageParser {
             };
 
             // Add part
+            let line_count = if matches!(body_part, PartType::Text(_) | PartType::Html(_)) {
+                stream
+                    .data
+                    .get(state.offset_body..state.offset_end)
+                    .map_or(0, count_lines)
+            } else {
+                0
+            };
             message.parts.push(MessagePart {
-                headers: std::mem::take(&mut part_headers),
+                header_index: self.header_index(&part_headers),
+                headers: core::mem::replace(&mut part_headers, buffers.headers()),
                 encoding,
                 is_encoding_problem,
+                decoded_charset,
+                is_header_overflow: stream.has_header_overflow,
+                uuencode_mode: None,
+                binhex,
                 body: body_part,
                 offset_header: state.offset_header,
                 offset_body: state.offset_body,
                 offset_end: state.offset_end,
+                line_count,
             });
+            for file in uu_files {
+                message.attachments.push(message.parts.len());
+                message
+                    .parts
+                    .push(uuencoded_part(file, state.offset_body, state.offset_end));
+            }
 
             if state.mime_boundary.is_some() {
                 // Currently processing a MIME part
@@ -466,7 +802,7 @@ impl MessageParser {
                         if let Some(part) = message.parts.get_mut(state.part_id) {
                             // Add headers and substructure to parent part
                             part.body =
-                                PartType::Multipart(std::mem::take(&mut state.sub_part_ids));
+                                PartType::Multipart(core::mem::take(&mut state.sub_part_ids));
 
                             // Restore ancestor's state
                             if let Some((prev_state, _)) = state_stack.pop() {
@@ -526,10 +862,12 @@ impl MessageParser {
 
         if !message.is_empty() {
             message.parts[0].offset_end = message.raw_message.len();
+            buffers.recycle_headers(part_headers);
             Some(message)
         } else if !part_headers.is_empty() {
             // Message without a body
             message.parts.push(MessagePart {
+                header_index: self.header_index(&part_headers),
                 headers: part_headers,
                 encoding: Encoding::None,
                 is_encoding_problem: true,
@@ -537,21 +875,74 @@ impl MessageParser {
                 offset_header: 0,
                 offset_body: message.raw_message.len(),
                 offset_end: message.raw_message.len(),
+                line_count: 0,
+                decoded_charset: None,
+                is_header_overflow: stream.has_header_overflow,
+                uuencode_mode: None,
+                binhex: None,
             });
             Some(message)
         } else {
+            buffers.recycle_headers(part_headers);
+            buffers.recycle(message);
             None
         }
     }
-}
 
-impl<'x> Message<'x> {
-    fn new() -> Message<'x> {
-        Message {
-            ..Default::default()
+    fn header_index<'x>(&self, headers: &[Header<'x>]) -> Option<Box<HeaderIndex<'x>>> {
+        if self.header_index {
+            let mut index = HeaderIndex {
+                len: headers.len(),
+                ..Default::default()
+            };
+            for (pos, header) in headers.iter().enumerate() {
+                index
+                    .positions
+                    .entry(header.name.clone())
+                    .or_default()
+                    .push(pos);
+            }
+            Some(Box::new(index))
+        } else {
+            None
         }
     }
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            ParseError::TooLarge => "message exceeds the maximum size",
+            ParseError::Empty => "empty message",
+            ParseError::NoHeaders => "no header fields found",
+        })
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ParseError {}
+
+impl FromStr for Message<'static> {
+    type Err = ParseError;
+
+    /// Parses a message with the default [`MessageParser`] and returns an owned copy.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        MessageParser::default()
+            .try_parse(s)
+            .map(|message| message.into_owned())
+    }
+}
 
+pub(crate) fn count_lines(bytes: &[u8]) -> usize {
+    let lines = bytes.iter().filter(|&&ch| ch == b'\n').count();
+    if bytes.last().is_some_and(|&ch| ch != b'\n') {
+        lines + 1
+    } else {
+        lines
+    }
+}
+
+impl<'x> Message<'x> {
     /// Returns `false` if at least one header field was successfully parsed.
     pub fn is_empty(&self) -> bool {
         self.parts.is_empty()
@@ -592,11 +983,135 @@ impl<'x> IntoByteSlice<'x> for &'x Vec<u8> {
     }
 }
 
+/// Builds an attachment from a file extracted from a uuencoded block, with
+/// the same offsets as the text part it was found in.
+fn uuencoded_part<'x>(file: UuFile, offset_body: usize, offset_end: usize) -> MessagePart<'x> {
+    let filename = Cow::<str>::Owned(file.filename);
+    let header = |name, c_type: &'static str, c_subtype, attribute| Header {
+        name,
+        value: HeaderValue::ContentType(ContentType {
+            c_type: c_type.into(),
+            c_subtype,
+            attributes: Some(vec![(Cow::Borrowed(attribute), filename.clone())]),
+        }),
+        offset_field: offset_body,
+        offset_start: offset_body,
+        offset_end: offset_body,
+        raw: None,
+    };
+
+    MessagePart {
+        headers: vec![
+            header(
+                HeaderName::ContentType,
+                "application",
+                Some("octet-stream".into()),
+                "name",
+            ),
+            header(
+                HeaderName::ContentDisposition,
+                "attachment",
+                None,
+                "filename",
+            ),
+        ],
+        is_encoding_problem: false,
+        body: PartType::Binary(file.data.into()),
+        encoding: Encoding::None,
+        offset_header: offset_body,
+        offset_body,
+        offset_end,
+        line_count: 0,
+        header_index: None,
+        decoded_charset: None,
+        is_header_overflow: false,
+        uuencode_mode: Some(file.mode),
+        binhex: None,
+    }
+}
+
+/// Returns `true` when a line of the message header is terminated by a lone CR,
+/// as written by some legacy Mac clients.
+fn has_bare_cr_line_endings(raw_message: &[u8]) -> bool {
+    for (pos, &ch) in raw_message.iter().enumerate() {
+        match ch {
+            b'\r' if raw_message.get(pos + 1) != Some(&b'\n') => return true,
+            b'\n'
+                if raw_message[pos + 1..].starts_with(b"\n")
+                    || raw_message[pos + 1..].starts_with(b"\r\n") =>
+            {
+                return false;
+            }
+            _ => (),
+        }
+    }
+    false
+}
+
+/// Replaces each lone CR with a LF, keeping all offsets unchanged.
+fn normalize_bare_cr(raw_message: &[u8]) -> Vec<u8> {
+    raw_message
+        .iter()
+        .enumerate()
+        .map(|(pos, &ch)| {
+            if ch == b'\r' && raw_message.get(pos + 1) != Some(&b'\n') {
+                b'\n'
+            } else {
+                ch
+            }
+        })
+        .collect()
+}
+
+fn decode_text(bytes: Cow<'_, [u8]>, charset_decoder: Option<DecoderFnc>) -> Cow<'_, str> {
+    match (bytes, charset_decoder) {
+        (Cow::Owned(vec), Some(charset_decoder)) => charset_decoder(&vec).into(),
+        (Cow::Owned(vec), None) => String::from_utf8(vec)
+            .unwrap_or_else(|e| String::from_utf8_lossy(e.as_bytes()).into_owned())
+            .into(),
+        (Cow::Borrowed(bytes), Some(charset_decoder)) => charset_decoder(bytes).into(),
+        (Cow::Borrowed(bytes), None) => String::from_utf8_lossy(bytes),
+    }
+}
+
+/// Decodes a text using its declared charset, or the detected one when the
+/// charset is missing, not supported or the text contains invalid sequences.
+#[cfg(feature = "detect_charset")]
+fn decode_with_detection<'x>(
+    bytes: Cow<'x, [u8]>,
+    charset: Option<&str>,
+) -> (Cow<'x, str>, Cow<'static, str>) {
+    if let Some(charset) = charset {
+        match charset_decoder(charset.as_bytes()) {
+            Some(charset_decoder) => {
+                let text = charset_decoder(&bytes);
+                if !text.contains(char::REPLACEMENT_CHARACTER) {
+                    return (text.into(), String::from(charset).into());
+                }
+            }
+            None if is_utf8_or_ascii(charset.as_bytes())
+                && core::str::from_utf8(&bytes).is_ok() =>
+            {
+                return (decode_text(bytes, None), String::from(charset).into());
+            }
+            None => (),
+        }
+    }
+
+    let detected = detect_charset(&bytes);
+    (
+        decode_text(bytes, charset_decoder(detected.as_bytes())),
+        detected.into(),
+    )
+}
+
 #[cfg(test)]
 mod tests {
     use std::{fs, path::PathBuf};
 
-    use crate::MessageParser;
+    use alloc::borrow::Cow;
+
+    use crate::{MessageBuffers, MessageParser, ParseError, PartType};
 
     #[test]
     fn parse_full_messages() {
@@ -610,7 +1125,7 @@ mod tests {
 
             for file_name in fs::read_dir(&test_dir).unwrap() {
                 let mut file_name = file_name.unwrap().path();
-                if file_name.extension().map_or(false, |e| e == "eml") {
+                if file_name.extension().is_some_and(|e| e == "eml") {
                     let raw_original = fs::read(&file_name).unwrap();
                     tests_run += 1;
 
@@ -658,6 +1173,294 @@ mod tests {
         }
     }
 
+    #[test]
+    fn parse_from_reader() {
+        struct ChunkedReader<'x> {
+            data: &'x [u8],
+            chunk_size: usize,
+        }
+
+        impl std::io::Read for ChunkedReader<'_> {
+            fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+                let len = self.chunk_size.min(buf.len()).min(self.data.len());
+                buf[..len].copy_from_slice(&self.data[..len]);
+                self.data = &self.data[len..];
+                Ok(len)
+            }
+        }
+
+        let input = concat!(
+            "From: Art Vandelay <art@vandelay.com>\n",
+            "Subject: Boundaries\n",
+            "Content-Type: multipart/mixed; boundary=\"festivus\"\n\n",
+            "--festivus\n",
+            "Content-Type: text/plain\n\n",
+            "Part one\n",
+            "--festivus\n",
+            "Content-Type: text/html\n\n",
+            "<p>Part two</p>\n",
+            "--festivus--\n"
+        );
+        let expected = MessageParser::default().parse(input).unwrap();
+
+        for chunk_size in [1, 3, 7, 64, 4096] {
+            let message = MessageParser::default()
+                .parse_reader(ChunkedReader {
+                    data: input.as_bytes(),
+                    chunk_size,
+                })
+                .unwrap()
+                .unwrap();
+            assert_eq!(message, expected, "failed for chunk size {chunk_size}");
+        }
+
+        assert!(MessageParser::default()
+            .parse_reader(ChunkedReader {
+                data: b"",
+                chunk_size: 1,
+            })
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn parse_max_size() {
+        let input = b"Subject: Hello\nX-Header: 1\n\nBody\n";
+        let parser = MessageParser::default()
+            .with_max_headers(1)
+            .with_max_size(input.len());
+        let message = parser.try_parse(&input[..]).unwrap();
+        assert_eq!(message.subject(), Some("Hello"));
+        assert_eq!(message.headers().len(), 1);
+        assert!(parser.parse_headers(&input[..]).is_some());
+
+        let parser = MessageParser::default().with_max_size(input.len() - 1);
+        assert_eq!(parser.try_parse(&input[..]), Err(ParseError::TooLarge));
+        assert_eq!(parser.parse(&input[..]), None);
+        assert_eq!(parser.parse_headers(&input[..]), None);
+        for (input, expected) in [
+            (&b""[..], ParseError::Empty),
+            (&b" \t "[..], ParseError::Empty),
+            (&b"no header\nfields here\n"[..], ParseError::NoHeaders),
+        ] {
+            assert_eq!(
+                MessageParser::default().try_parse(input),
+                Err(expected),
+                "failed for {input:?}"
+            );
+        }
+
+        // Truncated messages are parsed on a best-effort basis
+        let message = MessageParser::default()
+            .try_parse(&b"Subject: Truncated\nFrom: a@"[..])
+            .unwrap();
+        assert_eq!(message.subject(), Some("Truncated"));
+        assert!(message.parts[0].is_encoding_problem);
+        assert!(MessageParser::default()
+            .try_parse(&b"\nBody without headers"[..])
+            .is_ok());
+
+        // The reader is not consumed past the limit
+        let mut reader = &[&input[..], &[b'x'; 1024][..]].concat()[..];
+        let err = parser.parse_reader(&mut reader).unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+        assert_eq!(reader.len(), 1024);
+        assert!(MessageParser::default()
+            .with_max_size(input.len())
+            .parse_reader(&input[..])
+            .unwrap()
+            .is_some());
+    }
+
+    #[test]
+    fn parse_borrowed_text() {
+        for (charset, encoding, body, is_borrowed) in [
+            ("utf-8", "7bit", "Hello world\n", true),
+            ("UTF-8", "8bit", "Héllo wörld\n", true),
+            ("us-ascii", "binary", "Hello world\n", true),
+            ("utf-8", "8bit", "H\u{fffd}llo\n", false),
+            ("utf-8", "base64", "SGVsbG8K", false),
+        ] {
+            let mut input = format!(
+                "Content-Type: text/plain; charset={charset}\nContent-Transfer-Encoding: {encoding}\n\n{body}"
+            )
+            .into_bytes();
+            if let Some(pos) = input.windows(3).position(|w| w == "\u{fffd}".as_bytes()) {
+                input.splice(pos..pos + 3, [0xe9]);
+            }
+            let message = MessageParser::default().parse(&input).unwrap();
+            assert_eq!(
+                matches!(&message.parts[0].body, PartType::Text(Cow::Borrowed(_))),
+                is_borrowed,
+                "failed for {charset} {encoding}"
+            );
+            assert!(message.body_text(0).unwrap().starts_with('H'));
+        }
+    }
+
+    #[test]
+    fn parse_deeply_nested() {
+        // Nested multiparts
+        let mut input = String::new();
+        for level in 0..5000 {
+            input.push_str(&format!(
+                "Content-Type: multipart/mixed; boundary=\"b{level:05}\"\n\n--b{level:05}\n"
+            ));
+        }
+        input.push_str("Content-Type: text/plain\n\nDeep\n");
+        for level in (0..5000).rev() {
+            input.push_str(&format!("--b{level:05}--\n"));
+        }
+
+        let message = MessageParser::default()
+            .with_max_multipart_depth(10)
+            .parse(&input)
+            .unwrap();
+        assert_eq!(message.parts.len(), 11);
+        assert!(message.parts[10].is_binary());
+        assert!(message.parts[10].is_encoding_problem);
+
+        // Nested messages
+        let mut input = String::new();
+        for _ in 0..5000 {
+            input.push_str("Subject: Nested\nContent-Type: message/rfc822\n\n");
+        }
+        input.push_str("Subject: Deep\n\nDeep\n");
+
+        let message = MessageParser::default()
+            .with_max_nested_messages(10)
+            .parse(&input)
+            .unwrap();
+        let mut part = message.attachment(0).unwrap();
+        let mut depth = 0;
+        while let Some(nested) = part.message() {
+            part = nested.attachment(0).unwrap();
+            depth += 1;
+        }
+        assert_eq!(depth, 10);
+        assert!(part.is_binary());
+        assert!(part.is_encoding_problem);
+
+        // Nested encoded messages
+        let mut input = "Subject: Deep\n\nDeep\n".to_string();
+        for _ in 0..500 {
+            input = format!(
+                "Content-Type: message/rfc822\nContent-Transfer-Encoding: quoted-printable\n\n{input}"
+            );
+        }
+        let message = MessageParser::default().parse(&input).unwrap();
+        let mut part = message.attachment(0).unwrap();
+        let mut depth = 0;
+        while let Some(nested) = part.message() {
+            part = nested.attachment(0).unwrap();
+            depth += 1;
+        }
+        assert_eq!(depth, 3);
+        assert!(part.is_binary());
+        assert!(part.is_encoding_problem);
+    }
+
+    #[test]
+    fn parse_into_reused_buffers() {
+        let parser = MessageParser::default();
+        let mut buffers = MessageBuffers::new();
+
+        for test_suite in ["rfc", "legacy", "thirdparty", "malformed"] {
+            let test_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+                .join("resources")
+                .join("eml")
+                .join(test_suite);
+
+            for file_name in fs::read_dir(&test_dir).unwrap() {
+                let file_name = file_name.unwrap().path();
+                if file_name.extension().is_some_and(|e| e == "eml") {
+                    let raw_message = fs::read(&file_name).unwrap();
+                    let message = parser.parse_into(&raw_message, &mut buffers).unwrap();
+                    assert_eq!(
+                        Some(&message),
+                        parser.parse(&raw_message).as_ref(),
+                        "failed for {}",
+                        file_name.display()
+                    );
+                    buffers.recycle(message);
+                }
+            }
+        }
+
+        assert!(!buffers.parts.is_empty());
+        assert!(buffers.parts.iter().all(|parts| parts.is_empty()));
+        assert!(buffers.headers.iter().all(|headers| headers.is_empty()));
+        assert_eq!(parser.parse_into("", &mut buffers), None);
+    }
+
+    #[test]
+    fn parse_bare_cr_line_endings() {
+        let lf = concat!(
+            "From: Jane <jane@example.com>\n",
+            "Subject: Line\n endings\n",
+            "Content-Type: multipart/mixed; boundary=b\n\n",
+            "--b\n",
+            "Content-Type: text/plain\n\n",
+            "First line\nSecond line\n",
+            "--b\n",
+            "Content-Type: application/octet-stream\n",
+            "Content-Transfer-Encoding: base64\n\n",
+            "SGVs\nbG8=\n",
+            "--b--\n"
+        );
+        let cr = lf.replace('\n', "\r");
+        let crlf = lf.replace('\n', "\r\n");
+        let mixed = lf
+            .split_inclusive('\n')
+            .enumerate()
+            .map(|(pos, line)| match pos % 3 {
+                0 => line.replace('\n', "\r"),
+                1 => line.replace('\n', "\r\n"),
+                _ => line.to_string(),
+            })
+            .collect::<String>();
+        let mixed_lf_first = lf
+            .split_inclusive('\n')
+            .enumerate()
+            .map(|(pos, line)| match pos % 3 {
+                0 => line.to_string(),
+                1 => line.replace('\n', "\r"),
+                _ => line.replace('\n', "\r\n"),
+            })
+            .collect::<String>();
+
+        let expected = MessageParser::default().parse(lf).unwrap();
+        assert_eq!(
+            MessageParser::default().parse(&cr).as_ref(),
+            Some(&expected)
+        );
+
+        for raw in [lf, &crlf, &mixed, &mixed_lf_first] {
+            let message = MessageParser::default().parse(raw).unwrap();
+            assert_eq!(
+                message.from().unwrap().first().unwrap().address(),
+                Some("jane@example.com"),
+                "failed for {raw:?}"
+            );
+            assert_eq!(
+                message.subject(),
+                Some("Line endings"),
+                "failed for {raw:?}"
+            );
+            assert_eq!(message.parts.len(), 3, "failed for {raw:?}");
+            assert_eq!(
+                message.body_text(0).unwrap().replace('\r', ""),
+                "First line\nSecond line",
+                "failed for {raw:?}"
+            );
+            assert_eq!(
+                message.attachment(0).unwrap().contents(),
+                b"Hello",
+                "failed for {raw:?}"
+            );
+        }
+    }
+
     fn add_crlf(bytes: &[u8]) -> Vec<u8> {
         let mut result = Vec::with_capacity(bytes.len());
         let mut last_ch = 0;
@@ -9,7 +9,7 @@
  * except according to those terms.
  */
 
-use std::borrow::Cow;
+use alloc::borrow::Cow;
 
 use super::MessageStream;
 
@@ -9,19 +9,33 @@
  * except according to those terms.
  */
 
-use std::{iter::Peekable, ops::Range, slice::Iter};
+use alloc::vec::Vec;
+use core::{iter::Peekable, ops::Range, slice::Iter};
 
+use crate::{decoders::charsets::DecoderFnc, DiagnosticReason};
+
+pub mod diagnostics;
 pub mod fields;
 pub mod header;
 pub mod message;
 pub mod mime;
 pub mod preview;
+pub mod urls;
 
 pub struct MessageStream<'x> {
     data: &'x [u8],
     iter: Peekable<Iter<'x, u8>>,
     pos: usize,
     restore_pos: usize,
+    pub(crate) default_charset: Option<DecoderFnc>,
+    /// Set by body decoders when invalid bytes were skipped
+    pub(crate) has_decode_errors: bool,
+    /// Set by the header parser when the header limits were exceeded
+    pub(crate) has_header_overflow: bool,
+    /// Set by the message parser to keep the whitespace of unstructured values
+    pub(crate) preserve_whitespace: bool,
+    /// Problems found by the field parsers, only recorded when set
+    pub(crate) diagnostics: Option<Vec<(DiagnosticReason, usize)>>,
 }
 
 impl<'x> MessageStream<'x> {
@@ -31,6 +45,19 @@ impl<'x> MessageStream<'x> {
             iter: data.iter().peekable(),
             pos: 0,
             restore_pos: 0,
+            default_charset: None,
+            has_decode_errors: false,
+            has_header_overflow: false,
+            preserve_whitespace: false,
+            diagnostics: None,
+        }
+    }
+
+    /// Records a problem found at `offset` when diagnostics are enabled
+    #[inline(always)]
+    pub(crate) fn add_diagnostic(&mut self, reason: DiagnosticReason, offset: usize) {
+        if let Some(diagnostics) = &mut self.diagnostics {
+            diagnostics.push((reason, offset));
         }
     }
 
@@ -41,7 +68,7 @@ impl<'x> MessageStream<'x> {
 
     #[inline(always)]
     pub fn offset(&self) -> usize {
-        std::cmp::min(self.pos, self.data.len())
+        core::cmp::min(self.pos, self.data.len())
     }
 
     #[inline(always)]
@@ -0,0 +1,184 @@
+/*
+ * Copyright Stalwart Labs Ltd. See the COPYING
+ * file at the top-level directory of this distribution.
+ *
+ * Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+ * https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+ * <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+ * option. This file may not be copied, modified, or distributed
+ * except according to those terms.
+ */
+
+use alloc::{borrow::Cow, vec::Vec};
+
+use crate::decoders::html::{decode_html_entities, TagAttributes};
+
+static URL_PREFIXES: &[&str] = &["http://", "https://", "ftp://", "mailto:", "www."];
+static URL_ATTRIBUTES: &[&str] = &["href", "src"];
+
+/// Adds the bare URLs found in a plain text body to `urls`, skipping duplicates.
+pub fn text_urls<'x>(text: &'x str, urls: &mut Vec<Cow<'x, str>>) {
+    let bytes = text.as_bytes();
+    let mut pos = 0;
+
+    while pos < bytes.len() {
+        let is_boundary = pos == 0 || !bytes[pos - 1].is_ascii_alphanumeric();
+        let prefix = URL_PREFIXES.iter().find(|prefix| {
+            bytes
+                .get(pos..pos + prefix.len())
+                .is_some_and(|bytes| bytes.eq_ignore_ascii_case(prefix.as_bytes()))
+        });
+        let prefix_len = match prefix {
+            Some(prefix) if is_boundary => prefix.len(),
+            _ => {
+                pos += 1;
+                continue;
+            }
+        };
+
+        let start = pos;
+        let end = text[start..]
+            .find(|ch: char| {
+                ch.is_whitespace()
+                    || ch.is_control()
+                    || matches!(ch, '<' | '>' | '"' | '`' | '{' | '}' | '|' | '\\' | '^')
+            })
+            .map_or(text.len(), |end| start + end);
+        let url = trim_url(&text[start..end]);
+        if url.len() > prefix_len {
+            add_url(urls, url.into());
+        }
+        pos = end;
+    }
+}
+
+/// Adds the values of the `href` and `src` attributes found in an HTML body
+/// to `urls`, skipping duplicates and fragment-only links.
+pub fn html_urls<'x>(html: &'x str, urls: &mut Vec<Cow<'x, str>>) {
+    let bytes = html.as_bytes();
+    let mut pos = 0;
+
+    while let Some(tag_start) = bytes[pos..].iter().position(|&ch| ch == b'<') {
+        pos += tag_start + 1;
+        if bytes[pos..].starts_with(b"!--") {
+            pos = html[pos..]
+                .find("-->")
+                .map_or(bytes.len(), |end| pos + end + 3);
+            continue;
+        }
+
+        let mut attributes = TagAttributes::new(bytes, pos);
+        for (name, value) in attributes.by_ref() {
+            // Attributes are delimited by ASCII characters
+            let value = core::str::from_utf8(value).unwrap_or_default().trim();
+            if URL_ATTRIBUTES
+                .iter()
+                .any(|attr| name.eq_ignore_ascii_case(attr.as_bytes()))
+                && !value.is_empty()
+                && !value.starts_with('#')
+            {
+                add_url(urls, decode_html_entities(value));
+            }
+        }
+        pos = attributes.pos;
+    }
+}
+
+/// Returns `true` if the URL is a `cid:` reference to a body part (RFC2392).
+pub fn is_cid_url(url: &str) -> bool {
+    url.get(..4)
+        .is_some_and(|scheme| scheme.eq_ignore_ascii_case("cid:"))
+}
+
+fn add_url<'x>(urls: &mut Vec<Cow<'x, str>>, url: Cow<'x, str>) {
+    if !urls.contains(&url) {
+        urls.push(url);
+    }
+}
+
+/// Removes trailing punctuation and unbalanced closing brackets.
+fn trim_url(url: &str) -> &str {
+    let mut url = url;
+    loop {
+        let trimmed = url.trim_end_matches(['.', ',', ';', ':', '!', '?', '\'', '*']);
+        let trimmed = match trimmed.chars().last() {
+            Some(close @ (')' | ']')) => {
+                let open = if close == ')' { '(' } else { '[' };
+                if trimmed.matches(open).count() < trimmed.matches(close).count() {
+                    &trimmed[..trimmed.len() - 1]
+                } else {
+                    trimmed
+                }
+            }
+            _ => trimmed,
+        };
+        if trimmed.len() == url.len() {
+            return url;
+        }
+        url = trimmed;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec::Vec;
+
+    use super::{html_urls, text_urls};
+
+    #[test]
+    fn extract_text_urls() {
+        for (input, expected) in [
+            (
+                "Visit https://example.com/path?q=1&r=2. Thanks!",
+                &["https://example.com/path?q=1&r=2"][..],
+            ),
+            (
+                "See <http://example.com/a%20b> or (www.example.org/wiki/Foo_(bar)).",
+                &["http://example.com/a%20b", "www.example.org/wiki/Foo_(bar)"][..],
+            ),
+            (
+                "Write to mailto:john@example.com, HTTPS://EXAMPLE.COM and https://example.com/",
+                &[
+                    "mailto:john@example.com",
+                    "HTTPS://EXAMPLE.COM",
+                    "https://example.com/",
+                ][..],
+            ),
+            (
+                "Twice http://a.example http://a.example, none in xhttp://b.example or http://",
+                &["http://a.example"][..],
+            ),
+            ("No links here.", &[][..]),
+        ] {
+            let mut urls = Vec::new();
+            text_urls(input, &mut urls);
+            assert_eq!(urls, expected, "failed for {input:?}");
+        }
+    }
+
+    #[test]
+    fn extract_html_urls() {
+        for (input, expected) in [
+            (
+                concat!(
+                    "<a href=\"https://example.com/?a=1&amp;b=2\">link</a>",
+                    "<IMG SRC='cid:logo@example.com' alt=\"x > y\">",
+                    "<a href=#top>top</a><a href=http://unquoted.example/>u</a>",
+                    "<!-- <a href=\"http://commented.example\"> -->",
+                    "<a title=\"href=http://fake.example\" href = \"https://example.com/?a=1&amp;b=2\">"
+                ),
+                &[
+                    "https://example.com/?a=1&b=2",
+                    "cid:logo@example.com",
+                    "http://unquoted.example/",
+                ][..],
+            ),
+            ("<p>http://bare.example</p>", &[][..]),
+            ("<a href=\"http://truncated.example", &["http://truncated.example"][..]),
+        ] {
+            let mut urls = Vec::new();
+            html_urls(input, &mut urls);
+            assert_eq!(urls, expected, "failed for {input:?}");
+        }
+    }
+}
@@ -0,0 +1,101 @@
+/*
+ * Copyright Stalwart Labs Ltd. See the COPYING
+ * file at the top-level directory of this distribution.
+ *
+ * Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+ * https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+ * <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+ * option. This file may not be copied, modified, or distributed
+ * except according to those terms.
+ */
+
+// Kept in its own test binary, the allocator below counts every allocation
+// made by the process.
+
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use mail_parser::*;
+
+struct CountingAllocator;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAllocator = CountingAllocator;
+
+const MESSAGE: &str = concat!(
+    "From: Art Vandelay <art@vandelay.com>\n",
+    "To: Colleagues <colleagues@vandelay.com>\n",
+    "Subject: Importing and exporting\n",
+    "Content-Type: multipart/mixed; boundary=\"festivus\"\n",
+    "\n",
+    "--festivus\n",
+    "Content-Type: multipart/alternative; boundary=\"giddyup\"\n",
+    "\n",
+    "--giddyup\n",
+    "Content-Type: text/plain\n",
+    "\n",
+    "Why not both?\n",
+    "--giddyup\n",
+    "Content-Type: text/html\n",
+    "\n",
+    "<p>Why not both?</p>\n",
+    "--giddyup--\n",
+    "--festivus\n",
+    "Content-Type: message/rfc822\n",
+    "\n",
+    "From: Cosmo Kramer <kramer@kramerica.com>\n",
+    "Subject: Coffee tables\n",
+    "\n",
+    "A book about coffee tables.\n",
+    "--festivus--\n",
+);
+
+fn count_allocations(f: impl FnOnce()) -> usize {
+    let start = ALLOCATIONS.load(Ordering::Relaxed);
+    f();
+    ALLOCATIONS.load(Ordering::Relaxed) - start
+}
+
+#[test]
+fn parse_into_allocations() {
+    const ITERATIONS: usize = 100;
+    let parser = MessageParser::default();
+
+    let parse = count_allocations(|| {
+        for _ in 0..ITERATIONS {
+            let message = parser.parse(MESSAGE).unwrap();
+            assert_eq!(message.parts.len(), 5);
+        }
+    });
+
+    let mut buffers = MessageBuffers::new();
+    let parse_into = count_allocations(|| {
+        for _ in 0..ITERATIONS {
+            let message = parser.parse_into(MESSAGE, &mut buffers).unwrap();
+            assert_eq!(message.parts.len(), 5);
+            buffers.recycle(message);
+        }
+    });
+
+    assert!(parse_into < parse);
+}
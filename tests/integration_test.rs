@@ -77,13 +77,10 @@ R0lGODlhAQABAIAAAAAAAP///yH5BAEAAAAALAAAAAABAAEAAAIBRAA7
         message.parts[0].headers
     );
 
-    assert_eq!(
-        message.from().unwrap().first().unwrap(),
-        &Addr::new(
-            "Art Vandelay (Vandelay Industries)".into(),
-            "art@vandelay.com"
-        )
-    );
+    let from = message.from().unwrap().first().unwrap();
+    assert_eq!(from.name(), Some("Art Vandelay"));
+    assert_eq!(from.address(), Some("art@vandelay.com"));
+    assert_eq!(from.comment(), Some("Vandelay Industries"));
 
     assert_eq!(
         message.to().unwrap().as_group().unwrap(),
@@ -155,3 +152,2998 @@ R0lGODlhAQABAIAAAAAAAP///yH5BAEAAAAALAAAAAABAAEAAAIBRAA7
         "Book about ☕ tables.gif"
     );
 }
+
+#[test]
+fn test_into_owned() {
+    let message = {
+        let input = concat!(
+            "From: Art Vandelay <art@vandelay.com>\n",
+            "Subject: Owned =?utf-8?b?4pi6?=\n",
+            "Content-Type: multipart/mixed; boundary=\"festivus\"\n\n",
+            "--festivus\n",
+            "Content-Type: text/plain\n\n",
+            "I'm the owner now.\n",
+            "--festivus\n",
+            "Content-Type: message/rfc822\n\n",
+            "Subject: Nested\n\n",
+            "Nested body.\n",
+            "--festivus--\n"
+        )
+        .as_bytes()
+        .to_vec();
+
+        MessageParser::default().parse(&input).unwrap().into_owned()
+    };
+
+    assert_eq!(
+        message.from().unwrap().first().unwrap(),
+        &Addr::new("Art Vandelay".into(), "art@vandelay.com")
+    );
+    assert_eq!(message.subject().unwrap(), "Owned ☺");
+    assert_eq!(message.body_text(0).unwrap(), "I'm the owner now.");
+    assert_eq!(
+        message
+            .attachment(0)
+            .unwrap()
+            .message()
+            .unwrap()
+            .subject()
+            .unwrap(),
+        "Nested"
+    );
+}
+
+#[test]
+fn test_serde_roundtrip() {
+    let input = concat!(
+        "From: Art Vandelay <art@vandelay.com>\n",
+        "To: \"Colleagues\": \"James Smythe\" <james@vandelay.com>;\n",
+        "Date: Sat, 20 Nov 2021 14:22:01 -0800\n",
+        "Subject: Serde =?utf-8?b?4pi6?=\n",
+        "Content-Type: multipart/mixed; boundary=\"festivus\"\n\n",
+        "--festivus\n",
+        "Content-Type: text/plain; charset=utf-8\n\n",
+        "Hello, world.\n",
+        "--festivus\n",
+        "Content-Type: application/octet-stream\n",
+        "Content-Transfer-Encoding: base64\n\n",
+        "AAEC/w==\n",
+        "--festivus--\n"
+    );
+    let message = MessageParser::default().parse(input).unwrap();
+
+    let json = serde_json::to_string(&message).unwrap();
+    let json_message: Message = serde_json::from_str(&json).unwrap();
+    let bin = bincode::serialize(&message).unwrap();
+    let bin_message: Message = bincode::deserialize(&bin).unwrap();
+
+    for deserialized in [json_message, bin_message] {
+        assert_eq!(deserialized.parts.len(), message.parts.len());
+        for (deserialized, part) in deserialized.parts.iter().zip(message.parts.iter()) {
+            assert_eq!(deserialized.headers, part.headers);
+            assert_eq!(deserialized.body, part.body);
+            assert_eq!(deserialized.raw_body_offset(), part.raw_body_offset());
+        }
+        assert_eq!(deserialized.from(), message.from());
+        assert_eq!(deserialized.to(), message.to());
+        assert_eq!(deserialized.date(), message.date());
+        assert_eq!(deserialized.subject(), message.subject());
+        assert_eq!(deserialized.body_text(0), message.body_text(0));
+        assert_eq!(
+            deserialized.attachment(0).unwrap().contents(),
+            &[0x00, 0x01, 0x02, 0xff]
+        );
+    }
+}
+
+#[test]
+fn test_headers_iter() {
+    let input = concat!(
+        "Received: from b.example.com\n",
+        "Received: from a.example.com\n",
+        "X-Custom: first\n",
+        "Subject: Ordered\n",
+        "Content-Type: multipart/mixed; boundary=\"bound\"\n\n",
+        "--bound\n",
+        "X-Part: one\n",
+        "Content-Type: text/plain\n\n",
+        "First part.\n",
+        "--bound\n",
+        "Content-Type: text/html\n",
+        "X-Custom: second\n\n",
+        "<p>Second part.</p>\n",
+        "--bound--\n"
+    );
+    let message = MessageParser::default().parse(input).unwrap();
+
+    let headers = message
+        .headers_iter()
+        .map(|(name, _, offset_start, offset_end)| {
+            (name.as_str(), input[offset_start..offset_end].trim())
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        headers,
+        [
+            ("Received", "from b.example.com"),
+            ("Received", "from a.example.com"),
+            ("X-Custom", "first"),
+            ("Subject", "Ordered"),
+            ("Content-Type", "multipart/mixed; boundary=\"bound\""),
+            ("X-Part", "one"),
+            ("Content-Type", "text/plain"),
+            ("Content-Type", "text/html"),
+            ("X-Custom", "second"),
+        ]
+    );
+
+    let (name, value, _, _) = message.parts[2].headers_raw().nth(1).unwrap();
+    assert_eq!(name, &HeaderName::Other("X-Custom".into()));
+    assert_eq!(value.as_text(), Some("second"));
+}
+
+#[test]
+fn test_raw_header_bytes() {
+    let input = concat!(
+        "DKIM-Signature: v=1; a=rsa-sha256;\r\n",
+        "\td=example.com;  s=sel\r\n",
+        "Subject:  =?utf-8?q?Caf=C3=A9?= \r\n",
+        "Content-Type: multipart/mixed; boundary=\"b\"\r\n",
+        "\r\n",
+        "--b\r\n",
+        "X-Part:   One\r\n",
+        "\r\n",
+        "..dotted\r\n",
+        "--b--\r\n",
+    );
+    let message = MessageParser::default().parse(input).unwrap();
+
+    assert_eq!(
+        message.part_header_raw(0, "DKIM-Signature").unwrap(),
+        b" v=1; a=rsa-sha256;\r\n\td=example.com;  s=sel"
+    );
+    assert_eq!(
+        message.part_header_raw(0, HeaderName::Subject).unwrap(),
+        b"  =?utf-8?q?Caf=C3=A9?= "
+    );
+    assert_eq!(message.subject(), Some("Café"));
+    assert_eq!(message.part_header_raw(1, "X-Part").unwrap(), b"   One");
+    assert_eq!(message.part_header_raw(1, HeaderName::Subject), None);
+    assert_eq!(message.part_header_raw(5, HeaderName::Subject), None);
+
+    let body_offset = input.find("\r\n\r\n").unwrap() + 4;
+    assert_eq!(message.raw_body_offset(), body_offset);
+    assert_eq!(message.raw_body(), &input.as_bytes()[body_offset..]);
+}
+
+#[test]
+fn test_flowed_text() {
+    let input = concat!(
+        "Content-Type: text/plain; format=flowed; delsp=yes\n\n",
+        "> Quoted para \n",
+        "> graph.\n",
+        " From the top \n",
+        "of the text.\n",
+    );
+
+    assert_eq!(
+        MessageParser::default()
+            .parse(input)
+            .unwrap()
+            .body_text(0)
+            .unwrap(),
+        "> Quoted paragraph.\nFrom the topof the text.\n"
+    );
+    assert_eq!(
+        MessageParser::default()
+            .with_flowed_decoding(false)
+            .parse(input)
+            .unwrap()
+            .body_text(0)
+            .unwrap(),
+        input.split_once("\n\n").unwrap().1
+    );
+}
+
+#[test]
+fn test_flowed_quoted_reply() {
+    let input = concat!(
+        "Content-Type: text/plain; format=flowed\n\n",
+        "On Monday, Jane wrote: \n",
+        ">> Can we move the meeting \n",
+        ">> to Friday? \n",
+        "> Friday works \n",
+        "> for me. \n",
+        "> -- \n",
+        "> Jane \n",
+        "\n",
+        "Friday it \n",
+        "is.\n",
+        "-- \n",
+        "John\n",
+    );
+
+    assert_eq!(
+        MessageParser::default()
+            .parse(input)
+            .unwrap()
+            .body_text(0)
+            .unwrap(),
+        concat!(
+            "On Monday, Jane wrote: \n",
+            ">> Can we move the meeting to Friday? \n",
+            "> Friday works for me. \n",
+            "> -- \n",
+            "> Jane \n",
+            "\n",
+            "Friday it is.\n",
+            "-- \n",
+            "John\n",
+        )
+    );
+}
+
+#[test]
+fn test_list_unsubscribe() {
+    let input = concat!(
+        "List-Unsubscribe: <mailto:unsub@example.com?subject=unsubscribe>,\n",
+        "  <https://example.com/unsub?list=a,b&id=1>\n",
+        "List-Unsubscribe-Post: List-Unsubscribe=One-Click\n",
+        "Subject: Newsletter\n\n",
+        "Body\n",
+    );
+    let message = MessageParser::default().parse(input).unwrap();
+    assert_eq!(
+        message.list_unsubscribe_uris(),
+        [
+            "mailto:unsub@example.com?subject=unsubscribe",
+            "https://example.com/unsub?list=a,b&id=1"
+        ]
+    );
+    assert!(message.is_list_unsubscribe_one_click());
+
+    let message = MessageParser::default()
+        .parse("List-Unsubscribe: <https://example.com/u,1>, <mailto:a@b.c\n\nBody\n")
+        .unwrap();
+    assert_eq!(
+        message.list_unsubscribe_uris(),
+        ["https://example.com/u,1", "mailto:a@b.c"]
+    );
+    assert!(!message.is_list_unsubscribe_one_click());
+
+    let message = MessageParser::default()
+        .parse("Subject: None\n\nBody\n")
+        .unwrap();
+    assert!(message.list_unsubscribe_uris().is_empty());
+}
+
+#[test]
+fn test_address_iter() {
+    let input = concat!(
+        "To: Art <art@vandelay.com>, Friends: jerry@seinfeld.com, ",
+        "\"Undisclosed\" <>;, Kramer: kramer@kramerica.com;\n",
+        "Cc: george@vandelay.com, Elaine <elaine@pendant.com>\n\n",
+        "Body\n",
+    );
+    let message = MessageParser::default().parse(input).unwrap();
+
+    let to = message.to().unwrap();
+    assert!(to.as_group().is_some());
+    assert_eq!(
+        to.addresses().collect::<Vec<_>>(),
+        [
+            "art@vandelay.com",
+            "jerry@seinfeld.com",
+            "kramer@kramerica.com"
+        ]
+    );
+    assert_eq!(to.iter().count(), to.into_iter().count());
+    assert!(to.contains("Kramer@Kramerica.com"));
+    assert!(!to.contains("george@vandelay.com"));
+    assert!(!to.contains(""));
+
+    let cc = message.cc().unwrap();
+    assert_eq!(
+        cc.addresses().rev().collect::<Vec<_>>(),
+        ["elaine@pendant.com", "george@vandelay.com"]
+    );
+    assert!(cc.contains("GEORGE@vandelay.com"));
+    assert!(!cc.contains("elaine"));
+    assert_eq!(
+        cc.clone()
+            .into_iter()
+            .filter_map(|addr| addr.name)
+            .collect::<Vec<_>>(),
+        ["Elaine"]
+    );
+}
+
+#[test]
+fn test_part_by_cid() {
+    let input = concat!(
+        "Content-Type: multipart/mixed; boundary=\"outer\"\n\n",
+        "--outer\n",
+        "Content-Type: multipart/related; boundary=\"inner\"\n\n",
+        "--inner\n",
+        "Content-Type: text/html\n\n",
+        "<img src=\"cid:logo@example.com\"><img src=\"cid:photo@example.com\">\n",
+        "--inner\n",
+        "Content-Type: image/png\n",
+        "Content-ID: <logo@example.com>\n",
+        "Content-Transfer-Encoding: base64\n\n",
+        "bG9nbw==\n",
+        "--inner\n",
+        "Content-Type: image/jpeg\n",
+        "Content-ID: <photo@example.com>\n",
+        "Content-Transfer-Encoding: base64\n\n",
+        "cGhvdG8=\n",
+        "--inner--\n",
+        "--outer--\n",
+    );
+    let message = MessageParser::default().parse(input).unwrap();
+
+    assert_eq!(
+        message.part_by_cid("logo@example.com").unwrap().contents(),
+        b"logo"
+    );
+    assert_eq!(
+        message
+            .part_by_cid("<photo@example.com>")
+            .unwrap()
+            .contents(),
+        b"photo"
+    );
+    assert!(message.part_by_cid("unknown@example.com").is_none());
+    assert_eq!(
+        message
+            .content_ids()
+            .map(|(cid, part)| (cid, part.contents()))
+            .collect::<Vec<_>>(),
+        [
+            ("logo@example.com", &b"logo"[..]),
+            ("photo@example.com", &b"photo"[..])
+        ]
+    );
+}
+
+#[test]
+fn test_inline_parts() {
+    let input = concat!(
+        "Content-Type: multipart/mixed; boundary=\"outer\"\n\n",
+        "--outer\n",
+        "Content-Type: multipart/related; boundary=\"inner\"\n\n",
+        "--inner\n",
+        "Content-Type: text/html\n\n",
+        "<img src=\"cid:logo@example.com\">\n",
+        "--inner\n",
+        "Content-Type: image/png\n",
+        "Content-ID: <logo@example.com>\n\n",
+        "logo\n",
+        "--inner--\n",
+        "--outer\n",
+        "Content-Type: image/png; name=\"logo.png\"\n",
+        "Content-Disposition: inline; filename=\"logo.png\"\n\n",
+        "inline image\n",
+        "--outer\n",
+        "Content-Type: text/plain; name=\"notes.txt\"\n\n",
+        "notes\n",
+        "--outer\n",
+        "Content-Type: application/pdf\n\n",
+        "pdf\n",
+        "--outer\n",
+        "Content-Type: image/png\n",
+        "Content-ID: <other@example.com>\n",
+        "Content-Disposition: attachment\n\n",
+        "attached image\n",
+        "--outer--\n",
+    );
+    let message = MessageParser::default().parse(input).unwrap();
+
+    assert!(message.root_part().is_multipart());
+    assert!(!message.root_part().is_inline() && !message.root_part().is_attachment());
+    assert!(message.html_part(0).unwrap().is_inline());
+    assert_eq!(
+        message
+            .inline_parts()
+            .map(|part| part.contents())
+            .collect::<Vec<_>>(),
+        [&b"logo"[..], &b"inline image"[..]]
+    );
+    assert_eq!(
+        message
+            .attachments()
+            .map(|part| part.contents())
+            .collect::<Vec<_>>(),
+        [&b"notes"[..], &b"pdf"[..], &b"attached image"[..]]
+    );
+
+    // Positional access also includes the inline parts
+    assert_eq!(message.attachment_count(), 5);
+    assert_eq!(message.attachment(0).unwrap().contents(), b"logo");
+    assert_eq!(message.attachment(1).unwrap().contents(), b"inline image");
+    assert_eq!(message.attachment(2).unwrap().contents(), b"notes");
+    assert_eq!(
+        message.attachments().count() + message.inline_parts().count(),
+        message.attachment_count()
+    );
+}
+
+#[test]
+fn test_default_charset() {
+    let mut input =
+        b"Subject: =?x-unknown?q?Caf=E9?= and =?iso-8859-2?q?=B1?= or =?utf-8?q?=C3=A9?=\n"
+            .to_vec();
+    input.extend_from_slice(b"Content-Type: multipart/mixed; boundary=\"b\"\n\n");
+    input.extend_from_slice(b"--b\nContent-Type: text/plain\n\nCaf\xe9 \x80\n");
+    input
+        .extend_from_slice(b"--b\nContent-Type: text/plain; charset=x-unknown\n\n\x93quoted\x94\n");
+    input.extend_from_slice(b"--b\nContent-Type: text/plain; charset=utf-8\n\nCaf\xc3\xa9\n");
+    input.extend_from_slice(b"--b\nContent-Type: text/plain; charset=utf8\n\nCaf\xc3\xa9\n");
+    input.extend_from_slice(
+        b"--b\nContent-Type: text/plain; charset=ANSI_X3.4-1968\n\nPlain text\n",
+    );
+    input.extend_from_slice(b"--b--\n");
+
+    let message = MessageParser::default()
+        .with_default_charset("windows-1252")
+        .parse(&input)
+        .unwrap();
+    assert_eq!(message.subject(), Some("Café and ą or é"));
+    assert_eq!(message.body_text(0).unwrap(), "Café €");
+    assert_eq!(message.body_text(1).unwrap(), "\u{201c}quoted\u{201d}");
+    assert_eq!(message.body_text(2).unwrap(), "Café");
+    assert_eq!(message.body_text(3).unwrap(), "Café");
+    assert_eq!(message.body_text(4).unwrap(), "Plain text");
+
+    let message = MessageParser::default().parse(&input).unwrap();
+    assert_eq!(message.subject(), Some("Caf\u{fffd} and ą or é"));
+    assert_eq!(message.body_text(0).unwrap(), "Caf\u{fffd} \u{fffd}");
+}
+
+#[test]
+fn test_attachment_name() {
+    for (headers, expected) in [
+        (
+            concat!(
+                "Content-Type: text/plain; name=\"type.txt\"\n",
+                "Content-Disposition: attachment; filename=\"plain.txt\";\n",
+                " filename*=utf-8''na%C3%AFve.txt\n"
+            ),
+            Some("naïve.txt"),
+        ),
+        (
+            concat!(
+                "Content-Type: text/plain; name=\"type.txt\"\n",
+                "Content-Disposition: attachment; filename=\"plain.txt\"\n"
+            ),
+            Some("plain.txt"),
+        ),
+        (
+            concat!(
+                "Content-Type: text/plain; name=\"type.txt\"; name*=iso-8859-1''%E9.txt\n",
+                "Content-Disposition: attachment\n"
+            ),
+            Some("é.txt"),
+        ),
+        (
+            "Content-Type: text/plain; name=\"=?utf-8?q?=C3=A9t=C3=A9.txt?=\"\n",
+            Some("été.txt"),
+        ),
+        (
+            concat!(
+                "Content-Disposition: attachment;\n",
+                " filename*1*=ve.txt; filename*0*=utf-8''na%C3%AF\n"
+            ),
+            Some("naïve.txt"),
+        ),
+        ("Content-Disposition: attachment\n", None),
+    ] {
+        let input = format!("{headers}\nbody\n");
+        let message = MessageParser::default().parse(&input).unwrap();
+        assert_eq!(
+            message.root_part().attachment_name(),
+            expected,
+            "failed for {headers:?}"
+        );
+    }
+}
+
+#[test]
+fn test_nested_part_offsets() {
+    let input = concat!(
+        "Content-Type: multipart/mixed; boundary=outer\n\n",
+        "--outer\n",
+        "Content-Type: text/plain\n\n",
+        "Outer body\n",
+        "--outer\n",
+        "Content-Type: message/rfc822\n\n",
+        "Subject: Inner\n",
+        "Content-Type: multipart/mixed; boundary=inner\n\n",
+        "--inner\n",
+        "Content-Type: text/plain\n\n",
+        "Inner body\n",
+        "--inner--\n",
+        "--outer\n",
+        "Content-Type: message/rfc822\n",
+        "Content-Transfer-Encoding: base64\n\n",
+        "U3ViamVjdDogRW5jb2RlZAoKRW5jb2RlZCBib2R5Cg==\n",
+        "--outer--\n"
+    );
+    let message = MessageParser::default().parse(input).unwrap();
+    let range = |part: &MessagePart| part.raw_body_offset()..part.raw_end_offset();
+
+    // BODY[1] and BODY[2.1] are ranges of the original input
+    assert_eq!(&input[range(message.part(1).unwrap())], "Outer body");
+    let nested = message.part(2).unwrap().message().unwrap();
+    assert_eq!(&input[range(nested.part(1).unwrap())], "Inner body");
+    assert_eq!(
+        &input[nested.root_part().raw_header_offset()..nested.root_part().raw_body_offset()],
+        "Subject: Inner\nContent-Type: multipart/mixed; boundary=inner\n\n"
+    );
+
+    // Encoded nested messages are relative to their decoded contents
+    let encoded = message.part(3).unwrap().message().unwrap();
+    assert_eq!(
+        &encoded.raw_message()[range(encoded.root_part())],
+        b"Encoded body\n"
+    );
+}
+
+#[test]
+fn test_raw_len() {
+    let input = concat!(
+        "Subject: Quota\n",
+        "Content-Type: multipart/mixed; boundary=b\n\n",
+        "Preamble\n",
+        "--b\n",
+        "Content-Type: text/plain\n\n",
+        "Text\n",
+        "--b\n",
+        "Content-Type: application/octet-stream\n",
+        "Content-Transfer-Encoding: base64\n\n",
+        "AAEC/w==\n",
+        "--b--\n",
+        "Epilogue\n"
+    );
+
+    for input in [input.to_string(), input.replace('\n', "\r\n")] {
+        let message = MessageParser::default().parse(&input).unwrap();
+        assert_eq!(message.raw_len(), input.len());
+
+        let part = message.part(1).unwrap();
+        assert_eq!(
+            part.raw_len(),
+            part.raw_end_offset() - part.raw_header_offset()
+        );
+        let binary = message.part(2).unwrap();
+        assert_eq!(
+            binary.raw_len(),
+            input[binary.raw_header_offset()..binary.raw_end_offset()].len()
+        );
+        assert!(binary.raw_len() > binary.len());
+
+        let message = MessageParser::default()
+            .parse_reader(input.as_bytes())
+            .unwrap()
+            .unwrap();
+        assert_eq!(message.raw_len(), input.len());
+    }
+
+    // Truncated nested message
+    let message = MessageParser::default()
+        .parse("Content-Type: multipart/mixed; boundary=x\n\n--x\nContent-Type: message/rfc822\n\n")
+        .unwrap();
+    let nested = message.part(1).and_then(|part| part.message()).unwrap();
+    assert_eq!(nested.raw_len(), 0);
+}
+
+#[test]
+fn test_part_sizes() {
+    let input = concat!(
+        "From: Art Vandelay <art@vandelay.com>\n",
+        "Content-Type: multipart/mixed; boundary=\"festivus\"\n\n",
+        "--festivus\n",
+        "Content-Type: text/plain\n",
+        "Content-Transfer-Encoding: quoted-printable\n\n",
+        "Caf=C3=A9 au lait\n",
+        "second line\n",
+        "--festivus\n",
+        "Content-Type: application/octet-stream\n",
+        "Content-Transfer-Encoding: base64\n\n",
+        "AAEC/w==\n",
+        "--festivus--\n"
+    );
+    let message = MessageParser::default().parse(input).unwrap();
+
+    let text = message.part(1).unwrap();
+    assert_eq!(text.len(), "Café au lait\nsecond line".len());
+    assert_eq!(text.raw_body_len(), "Caf=C3=A9 au lait\nsecond line".len());
+    assert_eq!(text.line_count(), 2);
+    assert_eq!(
+        &input.as_bytes()[text.raw_body_offset()..text.raw_end_offset()],
+        b"Caf=C3=A9 au lait\nsecond line"
+    );
+
+    let binary = message.part(2).unwrap();
+    assert_eq!(binary.len(), 4);
+    assert_eq!(binary.raw_body_len(), "AAEC/w==".len());
+    assert_eq!(binary.line_count(), 0);
+
+    let input = input.replace('\n', "\r\n");
+    let message = MessageParser::default().parse(&input).unwrap();
+    let text = message.part(1).unwrap();
+    assert_eq!(text.line_count(), 2);
+    assert_eq!(
+        text.raw_body_len(),
+        "Caf=C3=A9 au lait\r\nsecond line".len()
+    );
+}
+
+#[test]
+fn test_body_structure() {
+    let input = concat!(
+        "From: Art Vandelay <art@vandelay.com>\n",
+        "To: \"Colleagues\": james@vandelay.com;\n",
+        "Subject: Importing\n",
+        "Date: Sat, 20 Nov 2021 14:22:01 -0800\n",
+        "Message-ID: <1234@vandelay.com>\n",
+        "Content-Type: multipart/mixed; boundary=\"festivus\"\n\n",
+        "--festivus\n",
+        "Content-Type: text/plain; charset=utf-8\n",
+        "Content-Transfer-Encoding: quoted-printable\n\n",
+        "Caf=C3=A9\n",
+        "au lait\n",
+        "--festivus\n",
+        "Content-Type: image/png; name=\"logo.png\"\n",
+        "Content-Transfer-Encoding: base64\n",
+        "Content-ID: <logo@vandelay.com>\n",
+        "Content-Disposition: inline; filename=\"logo.png\"\n\n",
+        "AAEC/w==\n",
+        "--festivus\n",
+        "Content-Type: message/rfc822\n\n",
+        "From: jane@example.com\n",
+        "Subject: Re: Importing\n\n",
+        "Hello\n",
+        "--festivus--\n"
+    );
+    let message = MessageParser::default().parse(input).unwrap();
+
+    let jane = "((NIL NIL \"jane\" \"example.com\"))";
+    assert_eq!(
+        message.body_structure(false),
+        format!(
+            concat!(
+                "((\"text\" \"plain\" (\"charset\" \"utf-8\") NIL NIL \"quoted-printable\" 17 2)",
+                "(\"image\" \"png\" (\"name\" \"logo.png\") \"<logo@vandelay.com>\" NIL \"base64\" 8)",
+                "(\"message\" \"rfc822\" NIL NIL NIL \"7bit\" 52 ",
+                "(NIL \"Re: Importing\" {jane} {jane} {jane} NIL NIL NIL NIL NIL) ",
+                "(\"text\" \"plain\" (\"charset\" \"us-ascii\") NIL NIL \"7bit\" 5 1) 4) ",
+                "\"mixed\")"
+            ),
+            jane = jane
+        )
+    );
+    assert_eq!(
+        message.body_structure(true),
+        format!(
+            concat!(
+                "((\"text\" \"plain\" (\"charset\" \"utf-8\") NIL NIL \"quoted-printable\" 17 2 ",
+                "NIL NIL NIL NIL)",
+                "(\"image\" \"png\" (\"name\" \"logo.png\") \"<logo@vandelay.com>\" NIL \"base64\" 8 ",
+                "NIL (\"inline\" (\"filename\" \"logo.png\")) NIL NIL)",
+                "(\"message\" \"rfc822\" NIL NIL NIL \"7bit\" 52 ",
+                "(NIL \"Re: Importing\" {jane} {jane} {jane} NIL NIL NIL NIL NIL) ",
+                "(\"text\" \"plain\" (\"charset\" \"us-ascii\") NIL NIL \"7bit\" 5 1 NIL NIL NIL NIL) 4 ",
+                "NIL NIL NIL NIL) ",
+                "\"mixed\" (\"boundary\" \"festivus\") NIL NIL NIL)"
+            ),
+            jane = jane
+        )
+    );
+
+    // Non-ASCII strings are sent as literals
+    let message = MessageParser::default()
+        .parse("Content-Type: text/plain; name=\"caf\u{e9}.txt\"\n\nhello\n")
+        .unwrap();
+    assert_eq!(
+        message.body_structure(false),
+        "(\"text\" \"plain\" (\"name\" {9}\r\ncaf\u{e9}.txt) NIL NIL \"7bit\" 6 1)"
+    );
+
+    // Empty nested message at the end of the input
+    let message = MessageParser::default()
+        .parse(concat!(
+            "Content-Type: multipart/mixed; boundary=\"x\"\n\n",
+            "--x\n",
+            "Content-Type: message/rfc822\n\n",
+        ))
+        .unwrap();
+    assert_eq!(
+        message.body_structure(false),
+        concat!(
+            "((\"message\" \"rfc822\" NIL NIL NIL \"7bit\" 0 NIL ",
+            "(\"text\" \"plain\" (\"charset\" \"us-ascii\") NIL NIL \"7bit\" 0 0) 0) ",
+            "\"mixed\")"
+        )
+    );
+    assert_eq!(
+        message.body_structure(true),
+        concat!(
+            "((\"message\" \"rfc822\" NIL NIL NIL \"7bit\" 0 NIL ",
+            "(\"text\" \"plain\" (\"charset\" \"us-ascii\") NIL NIL \"7bit\" 0 0 NIL NIL NIL NIL) 0 ",
+            "NIL NIL NIL NIL) ",
+            "\"mixed\" (\"boundary\" \"x\") NIL NIL NIL)"
+        )
+    );
+}
+
+#[test]
+fn test_envelope() {
+    let input = concat!(
+        "From: Art Vandelay <art@vandelay.com>\n",
+        "To: \"Colleagues\": james@vandelay.com;\n",
+        "Subject: Importing\n",
+        "Date: Sat, 20 Nov 2021 14:22:01 -0800\n",
+        "Message-ID: <1234@vandelay.com>\n\n",
+        "Hello\n"
+    );
+    let message = MessageParser::default().parse(input).unwrap();
+
+    assert_eq!(
+        message.envelope(),
+        concat!(
+            "(\"Sat, 20 Nov 2021 14:22:01 -0800\" \"Importing\" ",
+            "((\"Art Vandelay\" NIL \"art\" \"vandelay.com\")) ",
+            "((\"Art Vandelay\" NIL \"art\" \"vandelay.com\")) ",
+            "((\"Art Vandelay\" NIL \"art\" \"vandelay.com\")) ",
+            "((NIL NIL \"Colleagues\" NIL)(NIL NIL \"james\" \"vandelay.com\")(NIL NIL NIL NIL)) ",
+            "NIL NIL NIL \"<1234@vandelay.com>\")"
+        )
+    );
+
+    let message = MessageParser::default()
+        .parse(concat!(
+            "From: =?utf-8?q?J=C3=BCrgen?= <jurgen@example.com>\n",
+            "To: undisclosed-recipients:;\n",
+            "In-Reply-To: <1234@vandelay.com>\n",
+            "Subject: =?utf-8?q?caf=C3=A9?=\n\n",
+            "Hello\n"
+        ))
+        .unwrap();
+    let jurgen = "((\"=?utf-8?b?SsO8cmdlbg==?=\" NIL \"jurgen\" \"example.com\"))";
+    assert_eq!(
+        message.envelope(),
+        format!(
+            concat!(
+                "(NIL \"=?utf-8?q?caf=C3=A9?=\" {jurgen} {jurgen} {jurgen} ",
+                "((NIL NIL \"undisclosed-recipients\" NIL)(NIL NIL NIL NIL)) ",
+                "NIL NIL \"<1234@vandelay.com>\" NIL)"
+            ),
+            jurgen = jurgen
+        )
+    );
+
+    let message = MessageParser::default()
+        .parse("Subject: Empty\n\nHello\n")
+        .unwrap();
+    assert_eq!(
+        message.envelope(),
+        "(NIL \"Empty\" NIL NIL NIL NIL NIL NIL NIL NIL)"
+    );
+}
+
+#[test]
+fn test_truncated_multipart() {
+    let input = concat!(
+        "Subject: Truncated\n",
+        "Content-Type: multipart/mixed; boundary=\"festivus\"\n\n",
+        "--festivus\n",
+        "Content-Type: text/plain\n\n",
+        "Part one\n",
+        "--festivus\n",
+        "Content-Type: application/pdf\n",
+        "Content-Transfer-Encoding: base64\n\n",
+        "JVBERi0xLjQK\n",
+        "JVBERi0xL"
+    );
+    let message = MessageParser::default().parse(input).unwrap();
+
+    assert_eq!(message.parts.len(), 3);
+    assert_eq!(message.root_part().sub_parts(), Some(&[1, 2][..]));
+    assert_eq!(message.root_part().raw_end_offset(), input.len());
+
+    let text = message.part(1).unwrap();
+    assert_eq!(text.text_contents(), Some("Part one"));
+    assert!(!text.is_encoding_problem);
+
+    let attachment = message.attachment(0).unwrap();
+    assert!(attachment.is_content_type("application", "pdf"));
+    assert!(attachment.is_binary());
+    assert!(attachment.is_encoding_problem);
+    assert_eq!(attachment.contents(), b"%PDF-1.4\n%PDF-1");
+    assert_eq!(attachment.raw_end_offset(), input.len());
+}
+
+#[test]
+fn test_preserve_raw_headers() {
+    let input = concat!(
+        "Subject: =?utf-8?q?Caf=C3=A9?=\n",
+        "  au lait\n",
+        "Content-Type: multipart/mixed; boundary=\"b\"\n\n",
+        "--b\n",
+        "Content-Type: message/rfc822\n",
+        "Content-Transfer-Encoding: base64\n\n",
+        "U3ViamVjdDogIE5lc3RlZAoKSGVsbG8K\n",
+        "--b--\n"
+    );
+
+    let message = MessageParser::default()
+        .with_preserve_raw_headers(true)
+        .parse(input)
+        .unwrap();
+    assert_eq!(message.subject(), Some("Café au lait"));
+    assert_eq!(
+        message.root_part().header_raw(HeaderName::Subject),
+        Some(&b" =?utf-8?q?Caf=C3=A9?=\n  au lait"[..])
+    );
+    assert_eq!(
+        message.root_part().header_raw(HeaderName::Subject),
+        message.part_header_raw(0, HeaderName::Subject)
+    );
+    assert_eq!(
+        message
+            .part(1)
+            .unwrap()
+            .header_raw("Content-Transfer-Encoding"),
+        Some(&b" base64"[..])
+    );
+    let nested = message.attachment(0).unwrap().message().unwrap();
+    assert_eq!(nested.subject(), Some("Nested"));
+    assert_eq!(
+        nested.root_part().header_raw(HeaderName::Subject),
+        Some(&b"  Nested"[..])
+    );
+
+    // Raw values are not kept by default
+    let message = MessageParser::default().parse(input).unwrap();
+    assert_eq!(message.subject(), Some("Café au lait"));
+    assert_eq!(message.root_part().header_raw(HeaderName::Subject), None);
+}
+
+#[test]
+fn test_content_languages() {
+    let message = MessageParser::default()
+        .parse(concat!(
+            "Content-Language: en-US,\n",
+            " fr, de,\n",
+            "Content-Type: multipart/mixed; boundary=\"b\"\n\n",
+            "--b\n",
+            "Content-Language: es\n\n",
+            "Hola\n",
+            "--b\n\n",
+            "Hello\n",
+            "--b--\n"
+        ))
+        .unwrap();
+
+    assert_eq!(message.content_languages(), ["en-US", "fr", "de"]);
+    assert_eq!(message.part(1).unwrap().content_languages(), ["es"]);
+    assert!(message.part(2).unwrap().content_languages().is_empty());
+}
+
+#[test]
+fn test_calendar_parts() {
+    let message = MessageParser::default()
+        .parse(concat!(
+            "Subject: Meeting\n",
+            "Content-Type: multipart/mixed; boundary=\"mixed\"\n\n",
+            "--mixed\n",
+            "Content-Type: multipart/alternative; boundary=\"alt\"\n\n",
+            "--alt\n",
+            "Content-Type: text/plain\n\n",
+            "You are invited\n",
+            "--alt\n",
+            "Content-Type: text/calendar; charset=utf-8; method=REQUEST\n\n",
+            "BEGIN:VCALENDAR\n",
+            "END:VCALENDAR\n",
+            "--alt--\n",
+            "--mixed\n",
+            "Content-Type: text/calendar; name=\"invite.ics\"\n",
+            "Content-Disposition: attachment\n\n",
+            "BEGIN:VCALENDAR\n",
+            "END:VCALENDAR\n",
+            "--mixed--\n"
+        ))
+        .unwrap();
+
+    let parts = message.calendar_parts().collect::<Vec<_>>();
+    assert_eq!(parts.len(), 2);
+    assert_eq!(parts[0].calendar_method(), Some("REQUEST"));
+    assert_eq!(
+        parts[0].text_contents(),
+        Some("BEGIN:VCALENDAR\nEND:VCALENDAR")
+    );
+    assert_eq!(parts[1].calendar_method(), None);
+    assert_eq!(message.part(2).unwrap().calendar_method(), None);
+}
+
+#[test]
+fn test_raw_signed_content() {
+    let input = concat!(
+        "Subject: Signed\r\n",
+        "Content-Type: multipart/signed; boundary=\"signed\";\r\n",
+        " protocol=\"application/pgp-signature\"; micalg=pgp-sha256\r\n\r\n",
+        "This is an OpenPGP/MIME signed message\r\n",
+        "--signed  \r\n",
+        "Content-Type: text/plain\r\n\r\n",
+        "Hello\r\n",
+        "\r\n",
+        "--signed\r\n",
+        "Content-Type: application/pgp-signature\r\n\r\n",
+        "-----BEGIN PGP SIGNATURE-----\r\n",
+        "-----END PGP SIGNATURE-----\r\n",
+        "--signed--\r\n"
+    );
+    let message = MessageParser::default().parse(input).unwrap();
+    let signed = message.root_part();
+
+    assert_eq!(
+        signed.raw_signed_content(message.raw_message()),
+        Some(&b"Content-Type: text/plain\r\n\r\nHello\r\n"[..])
+    );
+    assert_eq!(
+        signed.signature_protocol(),
+        Some("application/pgp-signature")
+    );
+    assert_eq!(signed.signature_micalg(), Some("pgp-sha256"));
+
+    let signature = message.part(signed.sub_parts().unwrap()[1]).unwrap();
+    assert!(signature.is_content_type("application", "pgp-signature"));
+
+    let text = message.part(1).unwrap();
+    assert_eq!(text.raw_signed_content(message.raw_message()), None);
+    assert_eq!(text.signature_protocol(), None);
+}
+
+#[test]
+fn test_authentication_results() {
+    let message = MessageParser::default()
+        .parse(concat!(
+            "Authentication-Results: mx.example.com;\n",
+            "  spf=pass (sender is authorized) smtp.mailfrom=example.net;\n",
+            "  dkim=fail reason=\"bad signature\" header.d=example.net\n",
+            "Received: from relay.example.net by mx.example.com\n",
+            "authentication-results: relay.example.net; dmarc=pass header.from=example.net\n",
+            "Subject: Verdicts\n\n",
+            "Body\n"
+        ))
+        .unwrap();
+
+    let results = message.authentication_results().collect::<Vec<_>>();
+    assert_eq!(results.len(), 2);
+
+    assert_eq!(results[0].authserv_id, "mx.example.com");
+    let spf = results[0].result("SPF").unwrap();
+    assert!(spf.is_pass());
+    assert_eq!(spf.property("smtp", "mailfrom"), Some("example.net"));
+    let dkim = results[0].result("dkim").unwrap();
+    assert!(!dkim.is_pass());
+    assert_eq!(dkim.reason.as_deref(), Some("bad signature"));
+    assert_eq!(dkim.property("header", "d"), Some("example.net"));
+
+    assert_eq!(results[1].authserv_id, "relay.example.net");
+    assert!(results[1].result("dmarc").unwrap().is_pass());
+    assert!(results[1].result("spf").is_none());
+}
+
+#[test]
+fn test_dkim_signatures() {
+    let message = MessageParser::default()
+        .parse(concat!(
+            "DKIM-Signature: v=1; a=rsa-sha256; c=relaxed/simple; d=example.com;\n",
+            " s=selector1; h=From:To:\n",
+            "  Subject; bh=2jUSOH9NhtVGCQWNr9BrIAPreKQ\n",
+            "  jO6Sn7XIkfJVOzv8=; b=AuUoFEfDx\n",
+            "\tTDkHlLXSZEpZj79LICEps6eda7W3deTVFOk4yAUoqOB\n",
+            "dkim-signature: v=1; a=ed25519-sha256; d=example.net; s=brisbane;\n",
+            " h=from; bh=YQ==; b=Yg==\n",
+            "From: jane@example.com\n",
+            "Subject: Signed\n\n",
+            "Body\n"
+        ))
+        .unwrap();
+
+    let signatures = message.dkim_signatures().collect::<Vec<_>>();
+    assert_eq!(signatures.len(), 2);
+
+    assert_eq!(signatures[0].algorithm(), Some("rsa-sha256"));
+    assert_eq!(signatures[0].canonicalization(), Some("relaxed/simple"));
+    assert_eq!(signatures[0].domain(), Some("example.com"));
+    assert_eq!(signatures[0].selector(), Some("selector1"));
+    assert_eq!(
+        signatures[0].body_hash(),
+        Some("2jUSOH9NhtVGCQWNr9BrIAPreKQjO6Sn7XIkfJVOzv8=")
+    );
+    assert_eq!(
+        signatures[0].signature(),
+        Some("AuUoFEfDxTDkHlLXSZEpZj79LICEps6eda7W3deTVFOk4yAUoqOB")
+    );
+    assert_eq!(signatures[0].signed_headers(), ["From", "To", "Subject"]);
+
+    assert_eq!(signatures[1].domain(), Some("example.net"));
+    assert_eq!(signatures[1].signed_headers(), ["from"]);
+    assert_eq!(signatures[1].tag("q"), None);
+}
+
+#[test]
+fn test_content_type_attributes() {
+    let mut ct = HeaderValue::parse_content_type(concat!(
+        "multipart/mixed; BOUNDARY=\"frontier\"; Name=first;\n",
+        " charset*=iso-8859-1''caf%E9; format=flowed"
+    ))
+    .into_content_type()
+    .unwrap();
+
+    assert_eq!(ct.boundary(), Some("frontier"));
+    assert_eq!(ct.charset(), Some("café"));
+    assert_eq!(ct.attribute("NAME"), Some("first"));
+    assert_eq!(ct.attribute("Charset"), Some("café"));
+    assert!(ct.has_attribute("Format"));
+    assert!(!ct.has_attribute("size"));
+    assert_eq!(
+        ct.attributes()
+            .unwrap()
+            .iter()
+            .map(|(name, _)| name.as_ref())
+            .collect::<Vec<_>>(),
+        ["boundary", "name", "charset", "format"]
+    );
+
+    assert_eq!(ct.remove_attribute("Name").as_deref(), Some("first"));
+    assert_eq!(
+        ct.attributes()
+            .unwrap()
+            .iter()
+            .map(|(name, _)| name.as_ref())
+            .collect::<Vec<_>>(),
+        ["boundary", "charset", "format"]
+    );
+}
+
+#[test]
+fn test_base64_garbage() {
+    let message = MessageParser::default()
+        .parse(concat!(
+            "Content-Type: multipart/mixed; boundary=x\r\n\r\n",
+            "--x\r\n",
+            "Content-Transfer-Encoding: base64\r\n\r\n",
+            "SGVs bG8s\r\nIHdv\u{a0}cmxk\r\n",
+            "--x\r\n",
+            "Content-Transfer-Encoding: base64\r\n\r\n",
+            "SGVsbG8s\r\nIHdvcmxk\r\n",
+            "--x--\r\n"
+        ))
+        .unwrap();
+
+    for part_id in [1, 2] {
+        assert_eq!(
+            message.body_text(part_id - 1).as_deref(),
+            Some("Hello, world")
+        );
+    }
+    assert!(message.parts[1].is_encoding_problem);
+    assert!(!message.parts[2].is_encoding_problem);
+}
+
+#[test]
+fn test_raw_bodies() {
+    let raw = concat!(
+        "Content-Type: multipart/mixed; boundary=x\r\n\r\n",
+        "--x\r\n",
+        "Content-Type: text/plain; charset=iso-8859-1; format=flowed\r\n",
+        "Content-Transfer-Encoding: quoted-printable\r\n\r\n",
+        "caf=E9 \r\nau lait\r\n",
+        "--x\r\n",
+        "Content-Type: image/png\r\n",
+        "Content-Transfer-Encoding: base64\r\n\r\n",
+        "iVBORw0KGgo=\r\n",
+        "--x\r\n",
+        "Content-Type: message/rfc822\r\n",
+        "Content-Transfer-Encoding: base64\r\n\r\n",
+        "U3ViamVjdDogaGkNCg0KYm9keQ0K\r\n",
+        "--x--\r\n"
+    );
+    let message = MessageParser::default()
+        .with_raw_bodies(true)
+        .parse(raw)
+        .unwrap();
+
+    let decoded = MessageParser::default().parse(raw).unwrap();
+    assert_eq!(message.text_body, decoded.text_body);
+    assert_eq!(message.html_body, decoded.html_body);
+    assert_eq!(message.attachments, decoded.attachments);
+    for (part_id, encoding, contents) in [
+        (1, Encoding::QuotedPrintable, "caf=E9 \r\nau lait"),
+        (2, Encoding::Base64, "iVBORw0KGgo="),
+        (3, Encoding::Base64, "U3ViamVjdDogaGkNCg0KYm9keQ0K"),
+    ] {
+        let part = &message.parts[part_id];
+        assert_eq!(part.encoding, encoding);
+        assert!(!part.is_encoding_problem);
+        assert_eq!(part.contents(), contents.as_bytes());
+        assert_eq!(
+            &raw.as_bytes()[part.offset_body..part.offset_end],
+            contents.as_bytes()
+        );
+    }
+    assert!(matches!(message.parts[1].body, PartType::Text(_)));
+    assert!(matches!(message.parts[2].body, PartType::InlineBinary(_)));
+    assert!(matches!(message.parts[3].body, PartType::Binary(_)));
+
+    // Decoded by default
+    assert_eq!(decoded.body_text(0).as_deref(), Some("café\r\nau lait"));
+    assert_eq!(decoded.parts[2].contents(), b"\x89PNG\r\n\x1a\n");
+    assert!(matches!(decoded.parts[3].body, PartType::Message(_)));
+
+    // Raw text that is not valid UTF-8 is kept as bytes
+    let raw = b"Content-Type: text/plain; charset=iso-8859-1\nContent-Transfer-Encoding: 8bit\n\nCaf\xe9\n";
+    let message = MessageParser::default()
+        .with_raw_bodies(true)
+        .parse(&raw[..])
+        .unwrap();
+    assert_eq!(message.text_body, [0]);
+    assert!(matches!(message.parts[0].body, PartType::Binary(_)));
+    assert_eq!(message.parts[0].contents(), [67, 97, 102, 233, 10]);
+}
+
+#[test]
+fn test_header_index() {
+    let raw = concat!(
+        "Received: from a.example.com\n",
+        "X-Custom: one\n",
+        "Subject: Hello\n",
+        "x-custom: two\n",
+        "Received: from b.example.com\n",
+        "Content-Type: multipart/mixed; boundary=x\n\n",
+        "--x\n",
+        "Content-Type: text/plain\n",
+        "X-Part: yes\n\n",
+        "Part\n",
+        "--x--\n"
+    );
+
+    for parser in [
+        MessageParser::default(),
+        MessageParser::default().with_header_index(true),
+    ] {
+        let mut message = parser.parse(raw).unwrap();
+
+        assert_eq!(message.subject(), Some("Hello"));
+        assert_eq!(
+            message.header("X-CUSTOM").and_then(|v| v.as_text()),
+            Some("two")
+        );
+        assert_eq!(
+            message
+                .header_values("x-custom")
+                .filter_map(|v| v.as_text())
+                .collect::<Vec<_>>(),
+            ["one", "two"]
+        );
+        assert_eq!(message.header_values(HeaderName::Received).count(), 2);
+        assert_eq!(message.header("X-Missing"), None);
+        assert_eq!(message.header_values("X-Missing").count(), 0);
+
+        let part = &message.parts[1];
+        assert_eq!(part.header("x-part").and_then(|v| v.as_text()), Some("yes"));
+        assert_eq!(part.content_type().map(|ct| ct.ctype()), Some("text"));
+
+        // Headers modified after parsing are still found
+        message.parts[0].headers[1].name = HeaderName::Other("X-Renamed".into());
+        assert_eq!(
+            message.header("X-Renamed").and_then(|v| v.as_text()),
+            Some("one")
+        );
+        assert_eq!(message.header_values("X-Custom").count(), 1);
+        message.parts[0].headers[1].name = HeaderName::Other("X-Custom".into());
+        message.parts[0].headers.push(Header {
+            name: HeaderName::Other("X-Added".into()),
+            value: HeaderValue::Text("added".into()),
+            offset_field: 0,
+            offset_start: 0,
+            offset_end: 0,
+            raw: None,
+        });
+        assert_eq!(
+            message.header("X-Added").and_then(|v| v.as_text()),
+            Some("added")
+        );
+        assert!(message.remove_header("X-Custom").is_some());
+        assert_eq!(message.header_values("X-Custom").count(), 1);
+        assert_eq!(message.subject(), Some("Hello"));
+    }
+}
+
+#[test]
+fn test_header_values() {
+    let message = MessageParser::default()
+        .parse(concat!(
+            "Received: from c.example.com by d.example.com; Sat, 20 Nov 2021 14:22:03 -0800\n",
+            "X-Trace: first\n",
+            "Received: from b.example.com by c.example.com; Sat, 20 Nov 2021 14:22:02 -0800\n",
+            "Subject: Hops\n",
+            "x-trace: second\n",
+            "Received: from a.example.com by b.example.com; Sat, 20 Nov 2021 14:22:01 -0800\n\n",
+            "Body\n"
+        ))
+        .unwrap();
+    let part = message.root_part();
+
+    assert_eq!(
+        part.header_values(&HeaderName::Received)
+            .filter_map(|v| v.as_received())
+            .filter_map(|r| r.from().map(|host| host.to_string()))
+            .collect::<Vec<_>>(),
+        ["c.example.com", "b.example.com", "a.example.com"]
+    );
+    assert_eq!(
+        part.header_values(&HeaderName::Other("X-Trace".into()))
+            .filter_map(|v| v.as_text())
+            .collect::<Vec<_>>(),
+        ["first", "second"]
+    );
+    assert_eq!(
+        part.header_values("X-TRACE")
+            .filter_map(|v| v.as_text())
+            .collect::<Vec<_>>(),
+        ["first", "second"]
+    );
+    assert_eq!(part.header_values(&HeaderName::Subject).count(), 1);
+    assert_eq!(part.header_values(&HeaderName::Cc).count(), 0);
+}
+
+#[test]
+fn test_charset_aliases() {
+    let raw = concat!(
+        "Subject: =?cp1251?B?z/Do4uXy?=\n",
+        "Content-Type: text/plain; charset=sjis\n",
+        "Content-Transfer-Encoding: base64\n\n",
+        "k/qWew==\n"
+    );
+    let message = MessageParser::default().parse(raw).unwrap();
+
+    assert_eq!(message.subject(), Some("Привет"));
+    assert_eq!(message.body_text(0).unwrap(), "日本");
+}
+
+#[test]
+fn test_charset_detection() {
+    let raw = [
+        &b"Content-Type: multipart/mixed; boundary=x\n\n"[..],
+        b"--x\nContent-Type: text/plain\n\ncaf\xe9 cr\xe8me\n",
+        b"--x\nContent-Type: text/plain; charset=utf-8\n\ncaf\xe9\n",
+        b"--x\nContent-Type: text/plain; charset=iso-8859-1\n\ncaf\xe9\n",
+        b"--x\nContent-Type: text/plain; charset=x-unknown\n\n\x83n\x83\x8D\x81[\n",
+        b"--x\nContent-Type: text/plain\n\nplain ascii\n",
+        b"--x\nContent-Type: text/plain\n\nMerci de r\xe9pondre\n",
+        b"--x\nContent-Type: text/plain; charset=utf8\n\ncaf\xc3\xa9\n",
+        b"--x--\n",
+    ]
+    .concat();
+
+    let message = MessageParser::default()
+        .with_charset_detection(true)
+        .parse(&raw)
+        .unwrap();
+    for (part_id, text, charset) in [
+        (1, "café crème", "windows-1252"),
+        (2, "café", "windows-1252"),
+        (3, "café", "iso-8859-1"),
+        (4, "ハロー", "shift_jis"),
+        (5, "plain ascii", "utf-8"),
+        (6, "Merci de répondre", "windows-1252"),
+        (7, "café", "utf8"),
+    ] {
+        let part = &message.parts[part_id];
+        assert_eq!(part.text_contents(), Some(text), "part {part_id}");
+        assert_eq!(part.decoded_charset(), Some(charset), "part {part_id}");
+    }
+
+    // Disabled by default
+    let message = MessageParser::default().parse(&raw).unwrap();
+    assert_eq!(
+        message.parts[1].text_contents(),
+        Some("caf\u{fffd} cr\u{fffd}me")
+    );
+    assert_eq!(message.parts[1].decoded_charset(), None);
+}
+
+#[test]
+fn test_list_identifier() {
+    for (header, expected) in [
+        (
+            "List-Id: Some List <list.example.com>",
+            Some((Some("Some List"), "list.example.com")),
+        ),
+        (
+            "List-Id: <list.example.com>",
+            Some((None, "list.example.com")),
+        ),
+        (
+            "List-Id: =?utf-8?q?Caf=C3=A9_List?= <cafe.example.com>",
+            Some((Some("Café List"), "cafe.example.com")),
+        ),
+        (
+            "List-Id: \"Quoted, List\" <q.example.com>",
+            Some((Some("Quoted, List"), "q.example.com")),
+        ),
+        (
+            "List-Id: bare.example.com",
+            Some((None, "bare.example.com")),
+        ),
+        ("List-Id: Just a description", None),
+        ("List-Id: ", None),
+        ("Subject: No list", None),
+    ] {
+        let raw = format!("{header}\nFrom: jdoe@example.com\n\nBody\n");
+        for parser in [
+            MessageParser::default(),
+            MessageParser::new().header_raw(HeaderName::ListId),
+        ] {
+            let message = parser.parse(&raw).unwrap();
+            assert_eq!(
+                message
+                    .list_identifier()
+                    .as_ref()
+                    .map(|list_id| (list_id.description(), list_id.id())),
+                expected,
+                "failed for {header:?}"
+            );
+        }
+    }
+}
+
+#[test]
+fn test_list_uris() {
+    let message = MessageParser::default()
+        .parse(concat!(
+            "List-Help: <mailto:list@example.com?subject=help> (List Instructions)\n",
+            "List-Subscribe: <mailto:list-request@example.com?subject=subscribe>,\n",
+            "  <https://example.com/subscribe?list=a,b>\n",
+            "List-Post: <mailto:list@example.com>\n",
+            "List-Owner: <mailto:owner@example.com> (Contact Person for Help)\n",
+            "List-Archive: <https://example.com/archive/>\n",
+            "List-Unsubscribe: <mailto:list-request@example.com?subject=unsubscribe>\n",
+            "Subject: List message\n\n",
+            "Body\n"
+        ))
+        .unwrap();
+
+    assert_eq!(
+        message.list_help_uris(),
+        ["mailto:list@example.com?subject=help"]
+    );
+    assert_eq!(
+        message.list_subscribe_uris(),
+        [
+            "mailto:list-request@example.com?subject=subscribe",
+            "https://example.com/subscribe?list=a,b"
+        ]
+    );
+    assert_eq!(message.list_post_uris(), ["mailto:list@example.com"]);
+    assert!(!message.is_list_post_disallowed());
+    assert_eq!(message.list_owner_uris(), ["mailto:owner@example.com"]);
+    assert_eq!(
+        message.list_archive_uris(),
+        ["https://example.com/archive/"]
+    );
+    assert_eq!(
+        message.list_unsubscribe_uris(),
+        ["mailto:list-request@example.com?subject=unsubscribe"]
+    );
+
+    for list_post in ["NO", "no (posting not allowed on this list)"] {
+        let raw = format!("List-Post: {list_post}\n\nBody\n");
+        let message = MessageParser::default().parse(&raw).unwrap();
+        assert!(message.list_post_uris().is_empty());
+        assert!(message.is_list_post_disallowed());
+    }
+
+    let message = MessageParser::default()
+        .parse("Subject: Not a list\n\nBody\n")
+        .unwrap();
+    assert!(message.list_post_uris().is_empty());
+    assert!(!message.is_list_post_disallowed());
+    assert!(message.list_help_uris().is_empty());
+}
+
+#[test]
+fn test_reassemble_partials() {
+    let fragment = |number: usize, total: Option<usize>, body: &str| {
+        let total = total
+            .map(|total| format!(" total={total};"))
+            .unwrap_or_default();
+        format!(
+            concat!(
+                "X-Weird-Header-1: Foo\n",
+                "From: Bill@host.com\n",
+                "To: joe@otherhost.com\n",
+                "Date: Fri, 26 Mar 1993 12:59:38 -0500 (EST)\n",
+                "Subject: Audio mail (part {number} of 2)\n",
+                "Message-ID: <id{number}@host.com>\n",
+                "MIME-Version: 1.0\n",
+                "Content-Type: message/partial; id=\"ABC@host.com\";{total}\n",
+                "  number={number}\n\n",
+                "{body}"
+            ),
+            number = number,
+            total = total,
+            body = body
+        )
+    };
+    let first = fragment(
+        1,
+        None,
+        concat!(
+            "X-Weird-Header-2: Bar\n",
+            "Subject: Audio mail\n",
+            "Message-ID: <id1@host.com>\n",
+            "MIME-Version: 1.0\n",
+            "Content-type: text/plain\n\n",
+            "Hello, "
+        ),
+    );
+    let second = fragment(2, Some(2), "world!\n");
+    let unrelated = "Subject: Unrelated\n\nBody\n";
+    let parser = MessageParser::default();
+    let first = parser.parse(&first).unwrap();
+    let second = parser.parse(&second).unwrap();
+    let unrelated = parser.parse(unrelated).unwrap();
+
+    let messages =
+        Message::reassemble_partials(&parser, &[second.clone(), unrelated.clone(), first.clone()]);
+    assert_eq!(messages.len(), 1);
+    let message = &messages[0];
+    assert_eq!(message.subject(), Some("Audio mail"));
+    assert_eq!(message.message_id(), Some("id1@host.com"));
+    assert_eq!(message.header_raw("X-Weird-Header-1"), Some(" Foo\n"));
+    assert_eq!(message.header("X-Weird-Header-2"), None);
+    assert_eq!(message.date().unwrap().year, 1993);
+    assert_eq!(message.content_type().unwrap().subtype(), Some("plain"));
+    assert_eq!(message.body_text(0).unwrap(), "Hello, world!\n");
+
+    // Fragments of several messages are grouped by id
+    let other = |number: usize, body: &str| {
+        parser
+            .parse(&format!(
+                concat!(
+                    "Subject: Other\n",
+                    "Content-Type: message/partial; id=\"XYZ@host.com\"; number={}; total=2\n\n",
+                    "{}"
+                ),
+                number, body
+            ))
+            .unwrap()
+            .into_owned()
+    };
+    let other_first = other(1, "Subject: Other\n\nGood");
+    let other_second = other(2, "bye!\n");
+    let messages = Message::reassemble_partials(
+        &parser,
+        &[
+            other_second.clone(),
+            first.clone(),
+            other_first.clone(),
+            second.clone(),
+        ],
+    );
+    assert_eq!(
+        messages
+            .iter()
+            .map(|message| message.body_text(0).unwrap())
+            .collect::<Vec<_>>(),
+        ["Goodbye!\n", "Hello, world!\n"]
+    );
+
+    // Missing, duplicate and untotaled fragments
+    assert!(Message::reassemble_partials(&parser, std::slice::from_ref(&first)).is_empty());
+    assert!(Message::reassemble_partials(&parser, std::slice::from_ref(&second)).is_empty());
+    assert_eq!(
+        Message::reassemble_partials(
+            &parser,
+            &[
+                first.clone(),
+                second.clone(),
+                second.clone(),
+                other_first,
+                other_second
+            ]
+        )
+        .len(),
+        1
+    );
+    assert!(Message::reassemble_partials(&parser, &[unrelated]).is_empty());
+}
+
+#[test]
+fn test_priority() {
+    for (headers, expected) in [
+        ("", Priority::Normal),
+        ("X-Priority: 1 (Highest)\n", Priority::High),
+        ("X-Priority: 2\n", Priority::High),
+        ("X-Priority: 3 (Normal)\n", Priority::Normal),
+        ("X-Priority: 5 (Lowest)\n", Priority::Low),
+        ("X-Priority: 9\n", Priority::Normal),
+        ("Importance: High\n", Priority::High),
+        ("importance: low\n", Priority::Low),
+        ("Priority: urgent\n", Priority::High),
+        ("Priority: non-urgent\n", Priority::Low),
+        ("Priority: normal\n", Priority::Normal),
+        ("Priority: urgent\nX-Priority: 5\n", Priority::Low),
+        ("X-Priority: 1\nImportance: low\n", Priority::Low),
+        ("X-Priority: 1\nImportance: whatever\n", Priority::High),
+        ("Priority: non-urgent\nX-Priority: 7\n", Priority::Low),
+    ] {
+        let raw = format!("{headers}Subject: Test\n\nBody\n");
+        assert_eq!(
+            MessageParser::default().parse(&raw).unwrap().priority(),
+            expected,
+            "failed for {headers:?}"
+        );
+    }
+}
+
+#[test]
+fn test_text_contents() {
+    let message = MessageParser::default()
+        .parse(concat!(
+            "Subject: Text contents\n",
+            "Content-Type: multipart/mixed; boundary=\"outer\"\n\n",
+            "--outer\n",
+            "Content-Type: multipart/alternative; boundary=\"inner\"\n\n",
+            "--inner\n",
+            "Content-Type: text/plain; charset=iso-8859-1\n",
+            "Content-Transfer-Encoding: quoted-printable\n\n",
+            "Caf=E9 plain\n",
+            "--inner\n",
+            "Content-Type: text/html\n\n",
+            "<p>Caf&eacute; <b>html</b></p>\n",
+            "--inner--\n",
+            "--outer\n",
+            "Content-Type: text/plain\n",
+            "Content-Disposition: attachment; filename=notes.txt\n\n",
+            "Attached notes\n",
+            "--outer\n",
+            "Content-Type: application/octet-stream\n\n",
+            "Binary data\n",
+            "--outer\n",
+            "Content-Type: message/rfc822\n\n",
+            "Subject: Nested\n",
+            "Content-Type: text/html\n\n",
+            "<p>Nested html</p>\n",
+            "--outer--\n",
+        ))
+        .unwrap();
+
+    assert_eq!(message.text_contents(false), "Café plain\nNested html");
+    assert_eq!(
+        message.text_contents(true),
+        "Café plain\nCafé html\nNested html"
+    );
+}
+
+#[test]
+fn test_urls() {
+    let message = MessageParser::default()
+        .with_flowed_decoding(false)
+        .parse(concat!(
+            "Subject: Links\n",
+            "Content-Type: multipart/mixed; boundary=\"outer\"\n\n",
+            "--outer\n",
+            "Content-Type: multipart/related; boundary=\"inner\"\n\n",
+            "--inner\n",
+            "Content-Type: text/html\n\n",
+            "<a href=\"https://example.com/?a=1&amp;b=2\">Click</a>",
+            "<img src=\"cid:logo%40example.com\"><img src=\"cid:missing@example.com\">\n",
+            "--inner\n",
+            "Content-Type: image/png\n",
+            "Content-ID: <logo@example.com>\n\n",
+            "PNG\n",
+            "--inner--\n",
+            "--outer\n",
+            "Content-Type: text/plain; format=flowed; delsp=yes\n\n",
+            "See https://example.com/a/very/long/ \n",
+            "path, or https://example.com/?a=1&b=2.\n",
+            "--outer\n",
+            "Content-Type: message/rfc822\n\n",
+            "Subject: Nested\n\n",
+            "Nested link: <http://nested.example/>\n",
+            "--outer--\n",
+        ))
+        .unwrap();
+
+    assert_eq!(
+        message.urls(),
+        [
+            "https://example.com/?a=1&b=2",
+            "https://example.com/a/very/long/path",
+            "http://nested.example/"
+        ]
+    );
+    let cids = message.cid_references();
+    assert_eq!(cids.len(), 2);
+    assert_eq!(cids[0].0, "cid:logo%40example.com");
+    assert_eq!(cids[0].1.unwrap().contents(), b"PNG");
+    assert_eq!(cids[1].0, "cid:missing@example.com");
+    assert!(cids[1].1.is_none());
+}
+
+#[test]
+fn test_part_to_text() {
+    let message = MessageParser::default()
+        .parse(concat!(
+            "Content-Type: text/html\n\n",
+            "<p>See <a href=\"https://example.com/\">our site</a></p>\n"
+        ))
+        .unwrap();
+    let part = message.root_part();
+
+    assert_eq!(
+        part.to_text(true).unwrap(),
+        "See our site (https://example.com/)\n"
+    );
+    assert_eq!(part.to_text(false).unwrap(), "See our site\n");
+    assert_eq!(message.body_text(0).unwrap(), part.to_text(false).unwrap());
+}
+
+#[test]
+fn test_content_description() {
+    let message = MessageParser::default()
+        .parse(concat!(
+            "Content-Type: multipart/mixed; boundary=\"b\"\n\n",
+            "--b\n",
+            "Content-Type: application/pdf\n",
+            "Content-Description: =?utf-8?q?Quarterly_r=C3=A9sum=C3=A9?=\n",
+            "  for the\n",
+            "\tboard\n\n",
+            "PDF\n",
+            "--b\n",
+            "Content-Type: text/plain\n\n",
+            "No description\n",
+            "--b--\n",
+        ))
+        .unwrap();
+
+    assert_eq!(
+        message.part(1).unwrap().content_description(),
+        Some("Quarterly résumé for the board")
+    );
+    assert_eq!(message.part(2).unwrap().content_description(), None);
+    assert_eq!(message.content_description(), None);
+}
+
+#[test]
+fn test_verify_content_md5() {
+    let message = MessageParser::default()
+        .parse(concat!(
+            "Content-Type: multipart/mixed; boundary=\"b\"\n\n",
+            "--b\n",
+            "Content-Type: text/plain\n",
+            "Content-MD5: IfkkBoSScJgXcsO4e1MOFA==\n\n",
+            "Hello\n",
+            "World\n\n",
+            "--b\n",
+            "Content-Type: application/octet-stream\n",
+            "Content-Transfer-Encoding: base64\n",
+            "Content-MD5: uV9n9h67A2GWIteY9F/C0w==\n\n",
+            "AAEC\n",
+            "--b\n",
+            "Content-Type: application/octet-stream\n",
+            "Content-Transfer-Encoding: base64\n",
+            "Content-MD5: uV9n9h67A2GWIteY9F/C0w==\n\n",
+            "AAED\n",
+            "--b\n",
+            "Content-Type: text/plain\n\n",
+            "No digest\n",
+            "--b\n",
+            "Content-Type: text/plain; charset=iso-8859-1\n",
+            "Content-Transfer-Encoding: quoted-printable\n",
+            "Content-MD5: occMhWR+YJkXFHUlSG9qaw==\n\n",
+            "Caf=E9\n",
+            "au lait\n",
+            "--b--\n",
+        ))
+        .unwrap();
+
+    for (part_id, expected) in [
+        (1, Some(true)),
+        (2, Some(true)),
+        (3, Some(false)),
+        (4, None),
+        (5, Some(true)),
+        (0, None),
+    ] {
+        assert_eq!(
+            message
+                .part(part_id)
+                .unwrap()
+                .verify_content_md5(message.raw_message()),
+            expected,
+            "part {part_id}"
+        );
+    }
+}
+
+#[test]
+fn test_header_limits() {
+    let raw = concat!(
+        "Subject: Hello\n",
+        "Content-Type: multipart/mixed; boundary=x\n",
+        "X-Spam: 1\n",
+        "X-Spam: 2\n",
+        " folded\n",
+        "X-Spam: 3\n\n",
+        "--x\n",
+        "Content-Type: text/plain\n",
+        "X-Spam: 4\n\n",
+        "Part\n",
+        "--x--\n"
+    );
+
+    let message = MessageParser::default().parse(raw).unwrap();
+    assert_eq!(message.headers().len(), 5);
+    assert!(message.parts.iter().all(|part| !part.is_header_overflow()));
+
+    let message = MessageParser::default()
+        .with_max_headers(2)
+        .parse(raw)
+        .unwrap();
+    assert_eq!(message.headers().len(), 2);
+    assert_eq!(message.subject(), Some("Hello"));
+    assert!(message.parts[0].is_header_overflow());
+    assert!(!message.parts[1].is_header_overflow());
+    assert_eq!(message.body_text(0).as_deref(), Some("Part"));
+
+    let message = MessageParser::default()
+        .with_max_headers(1)
+        .parse(raw)
+        .unwrap();
+    assert_eq!(message.headers().len(), 1);
+    assert!(message.parts[0].is_header_overflow());
+    assert_eq!(
+        message.body_text(0).as_deref(),
+        Some("--x\nContent-Type: text/plain\nX-Spam: 4\n\nPart\n--x--\n")
+    );
+
+    let message = MessageParser::default()
+        .with_max_header_bytes(68)
+        .parse(raw)
+        .unwrap();
+    assert_eq!(message.headers().len(), 3);
+    assert!(message.parts[0].is_header_overflow());
+    assert!(!message.parts[1].is_header_overflow());
+    assert_eq!(message.parts[1].headers.len(), 2);
+    assert_eq!(message.body_text(0).as_deref(), Some("Part"));
+
+    let message = MessageParser::default()
+        .with_max_headers(0)
+        .parse("Subject: Hello\nFrom: a@b.c\n\nBody")
+        .unwrap();
+    assert!(message.headers().is_empty());
+    assert!(message.parts[0].is_header_overflow());
+    assert_eq!(message.body_text(0).as_deref(), Some("Body"));
+}
+
+#[test]
+fn test_write_to() {
+    let raw = concat!(
+        "Received: from mx.example.com (mx.example.com [192.0.2.1])\n",
+        "\tby mail.example.org with ESMTP id abc123; Mon, 1 Jan 2024 10:00:00 +0000\n",
+        "From: =?iso-8859-1?q?Jos=E9?= <jose@example.com>\n",
+        "To: \"Doe, John\" <john@example.com>, ann@example.com\n",
+        "Subject: =?utf-8?B?UmVwb3J0IGR1IG1vaXMg4oCUIGphbnZpZXI=?=\n",
+        "Date: Mon, 1 Jan 2024 10:00:00 +0100\n",
+        "Message-ID: <1234@example.com>\n",
+        "References: <a@example.com> <b@example.com>\n",
+        "List-Unsubscribe: <mailto:unsub@example.com>, <https://example.com/unsub>\n",
+        "X-Custom: some value\n",
+        "MIME-Version: 1.0\n",
+        "Content-Type: multipart/mixed; boundary=\"outer boundary\"\n\n",
+        "Preamble\n",
+        "--outer boundary\n",
+        "Content-Type: text/plain; charset=iso-8859-1; format=flowed\n",
+        "Content-Transfer-Encoding: quoted-printable\n\n",
+        "Voil=E0 le rapport, avec une ligne assez longue pour qu'elle soit =\n",
+        "coup=E9e. \n",
+        "=3D Fin\n",
+        "--outer boundary\n",
+        "Content-Type: text/html; charset=utf-8\n",
+        "Content-Transfer-Encoding: base64\n\n",
+        "PHA+Vm9pbMOgPC9wPg==\n",
+        "--outer boundary\n",
+        "Content-Type: application/octet-stream; name*=utf-8''r%C3%A9sum%C3%A9.bin\n",
+        "Content-Disposition: attachment; filename=\"data file.bin\"\n",
+        "Content-Transfer-Encoding: base64\n\n",
+        "AAECAwQFBgcICQoLDA0ODxAREhMUFRYXGBkaGxwdHh8gISIjJCUmJygpKissLS4vMDEyMzQ1Njc4\n",
+        "OTo7PD0+P0BBQkNERUZH\n",
+        "--outer boundary\n",
+        "Content-Type: message/rfc822\n\n",
+        "Subject: Nested\n",
+        "Content-Type: text/plain\n\n",
+        "Nested body\n",
+        "--outer boundary\n",
+        "Content-Type: message/rfc822\n",
+        "Content-Transfer-Encoding: base64\n\n",
+        "U3ViamVjdDogaGkNCg0KYm9keQ0K\n",
+        "--outer boundary--\n",
+        "Epilogue\n"
+    )
+    .replace('\n', "\r\n");
+
+    for parser in [
+        MessageParser::default(),
+        MessageParser::default().with_raw_bodies(true),
+    ] {
+        let mut message = parser.parse(&raw).unwrap();
+        message.parts[0]
+            .headers
+            .retain(|header| header.name != HeaderName::Other("X-Custom".into()));
+        message.parts[0].headers.push(Header {
+            name: HeaderName::Comments,
+            value: HeaderValue::Text("Grüße aus Köln".into()),
+            offset_field: 0,
+            offset_start: 0,
+            offset_end: 0,
+            raw: None,
+        });
+
+        let mut output = Vec::new();
+        message.write_to(&mut output).unwrap();
+        let output = parser.parse(&output).unwrap();
+
+        assert_eq!(output.headers().len(), message.headers().len());
+        for header in ["Received", "From", "To", "Subject", "Date", "Message-ID"] {
+            assert_eq!(output.header(header), message.header(header), "{header}");
+        }
+        for header in ["References", "List-Unsubscribe", "Comments", "MIME-Version"] {
+            assert_eq!(output.header(header), message.header(header), "{header}");
+        }
+        assert_eq!(output.header("X-Custom"), None);
+        assert_eq!(output.parts.len(), message.parts.len());
+        for (part, expected) in output.parts.iter().zip(message.parts.iter()) {
+            match (&part.body, &expected.body) {
+                (PartType::Message(nested), PartType::Message(expected)) => {
+                    assert_eq!(
+                        nested
+                            .parts
+                            .iter()
+                            .map(|part| &part.body)
+                            .collect::<Vec<_>>(),
+                        expected
+                            .parts
+                            .iter()
+                            .map(|part| &part.body)
+                            .collect::<Vec<_>>()
+                    );
+                }
+                (body, expected) => assert_eq!(body, expected),
+            }
+            assert_eq!(part.content_disposition(), expected.content_disposition());
+            assert_eq!(part.attachment_name(), expected.attachment_name());
+            assert_eq!(part.encoding, expected.encoding);
+        }
+        assert_eq!(
+            output
+                .attachment(1)
+                .and_then(|part| part.message())
+                .and_then(|message| message.subject()),
+            Some("Nested")
+        );
+    }
+
+    // Encoded nested messages are written using their transfer encoding
+    let message = MessageParser::default().parse(&raw).unwrap();
+    let mut output = Vec::new();
+    message.write_to(&mut output).unwrap();
+    assert!(output
+        .windows(28)
+        .any(|window| window == b"U3ViamVjdDogaGkNCg0KYm9keQ0K"));
+    let output = MessageParser::default().parse(&output).unwrap();
+    let nested = output.attachment(2).unwrap();
+    assert_eq!(nested.encoding, Encoding::Base64);
+    assert_eq!(nested.message().and_then(|m| m.subject()), Some("hi"));
+    assert_eq!(
+        nested.message().and_then(|m| m.body_text(0)).as_deref(),
+        Some("body\r\n")
+    );
+
+    // Modified bodies are encoded using the declared transfer encoding
+    let mut message = MessageParser::default().parse(&raw).unwrap();
+    message.parts[1].body = PartType::Text("Texte modifié\nsur deux lignes".into());
+    message.parts[3].body = PartType::Binary(vec![0xff; 100].into());
+    let mut output = Vec::new();
+    message.write_to(&mut output).unwrap();
+    let output = MessageParser::default().parse(&output).unwrap();
+    assert_eq!(
+        output.body_text(0).as_deref(),
+        Some("Texte modifié\r\nsur deux lignes")
+    );
+    assert_eq!(
+        output.parts[1].content_type().unwrap().attribute("charset"),
+        Some("utf-8")
+    );
+    assert_eq!(
+        output.parts[1].content_type().unwrap().attribute("format"),
+        None
+    );
+    assert_eq!(output.parts[3].contents(), &[0xff; 100][..]);
+    assert_eq!(output.parts[3].body, message.parts[3].body);
+}
+
+#[test]
+fn test_boundary() {
+    let message = MessageParser::default()
+        .parse(concat!(
+            "Content-Type: multipart/mixed; boundary=\"outer\"\n\n",
+            "--outer\n",
+            "Content-Type: multipart/alternative; boundary=inner\n\n",
+            "--inner\n",
+            "Content-Type: text/plain\n\n",
+            "Text\n",
+            "--inner--\n",
+            "--outer\n",
+            "Content-Type: text/plain; boundary=unused\n\n",
+            "Not a multipart\n",
+            "--outer--\n"
+        ))
+        .unwrap();
+
+    assert_eq!(message.parts[0].boundary(), Some("outer"));
+    assert_eq!(message.parts[1].boundary(), Some("inner"));
+    assert_eq!(message.parts[2].boundary(), None);
+    assert_eq!(message.parts[3].boundary(), None);
+}
+
+#[test]
+fn test_thread_references() {
+    let mut raw = String::from("Subject: Re: Thread\nReferences:");
+    for id in 0..40 {
+        raw.push_str(&format!("\n <{id}@example.com>"));
+        if id % 10 == 0 {
+            raw.push_str(" <0@example.com>");
+        }
+    }
+    raw.push_str(" <no-at-sign> <@example.com>\n");
+    raw.push_str("In-Reply-To: <39@example.com> <40@example.com>\n\nBody\n");
+
+    let message = MessageParser::default().parse(&raw).unwrap();
+    let expected = (0..=40)
+        .map(|id| format!("{id}@example.com"))
+        .collect::<Vec<_>>();
+    assert_eq!(message.references().as_text_list().unwrap().len(), 46);
+    assert_eq!(message.thread_references(), expected);
+
+    let message = MessageParser::default()
+        .parse("In-Reply-To: <a@example.com>\n\nBody\n")
+        .unwrap();
+    assert_eq!(message.thread_references(), ["a@example.com"]);
+
+    let message = MessageParser::default()
+        .parse("Subject: No references\n\nBody\n")
+        .unwrap();
+    assert!(message.thread_references().is_empty());
+}
+
+#[test]
+fn test_malformed_message_ids() {
+    let raw = concat!(
+        "Message-ID: < abc.123 @\n mail.example.com >\n",
+        "References: <1@example.com><2@exam\n",
+        " ple.com>   <3@example.com\n",
+        "\t<4@example.com>< 5@example.com\n",
+        "In-Reply-To: 5@example.com\n\n",
+        "Body\n"
+    );
+    let message = MessageParser::default().parse(raw).unwrap();
+
+    assert_eq!(message.message_id(), Some("abc.123@mail.example.com"));
+    assert_eq!(message.message_id_domain(), Some("mail.example.com"));
+    assert_eq!(
+        message.references().as_text_list().unwrap(),
+        [
+            "1@example.com",
+            "2@example.com",
+            "3@example.com",
+            "4@example.com",
+            "5@example.com"
+        ]
+    );
+    assert_eq!(message.in_reply_to().as_text(), Some("5@example.com"));
+
+    for (raw, expected) in [
+        ("Message-ID: <local@>\n\nBody\n", None),
+        ("Message-ID: no-domain\n\nBody\n", None),
+        ("Subject: No id\n\nBody\n", None),
+    ] {
+        let message = MessageParser::default().parse(raw).unwrap();
+        assert_eq!(message.message_id_domain(), expected, "failed for {raw:?}");
+    }
+}
+
+#[test]
+fn test_content_disposition() {
+    let message = MessageParser::default()
+        .parse(concat!(
+            "Content-Type: multipart/mixed; boundary=b\n\n",
+            "--b\n",
+            "Content-Disposition: attachment; filename=genome.jpeg;\n",
+            " creation-date=\"Wed, 12 Feb 1997 16:29:51 -0500\";\n",
+            " modification-date=\"not a date\"; size=\" 2048\";\n",
+            " read-date=\"Mon, 31 Feb 1997 99:00:00 -0500\"\n\n",
+            "Data\n",
+            "--b\n",
+            "Content-Disposition: INLINE\n\n",
+            "Text\n",
+            "--b\n",
+            "Content-Disposition: x-custom; read-date=\"Thu, 13 Feb 1997 08:00:00 +0100\"; size=big\n\n",
+            "Other\n",
+            "--b\n\n",
+            "None\n",
+            "--b--\n"
+        ))
+        .unwrap();
+
+    assert_eq!(
+        message.parts[1].disposition(),
+        Some(ContentDisposition {
+            disposition_type: DispositionType::Attachment,
+            filename: Some("genome.jpeg"),
+            creation_date: DateTime::parse_rfc822("Wed, 12 Feb 1997 16:29:51 -0500"),
+            modification_date: None,
+            read_date: None,
+            size: Some(2048),
+        })
+    );
+    assert_eq!(
+        message.parts[2].disposition().unwrap().disposition_type,
+        DispositionType::Inline
+    );
+    let disposition = message.parts[3].disposition().unwrap();
+    assert_eq!(
+        disposition.disposition_type,
+        DispositionType::Other("x-custom")
+    );
+    assert_eq!(
+        disposition.read_date.unwrap().to_rfc3339(),
+        "1997-02-13T08:00:00+01:00"
+    );
+    assert_eq!(disposition.size, None);
+    assert_eq!(message.parts[4].disposition(), None);
+
+    let disposition = message.parts[1].disposition().unwrap();
+    let json = serde_json::to_string(&disposition).unwrap();
+    assert_eq!(
+        serde_json::from_str::<ContentDisposition>(&json).unwrap(),
+        disposition
+    );
+}
+
+#[test]
+fn test_autocrypt() {
+    let header = "Autocrypt: addr=alice@example.org; prefer-encrypt=mutual; keydata=\n mDMEXEcE6RYJKwYBBAHaRw8BAQdArjWwk3FAqyiFbFBKT4TzX\n cVBqPTB3gmzlC/Ub7O1u120\n";
+    let raw = format!("From: alice@example.org\n{header}\nBody\n");
+    let message = MessageParser::default().parse(&raw).unwrap();
+    let autocrypt = message.autocrypt().unwrap();
+    assert_eq!(autocrypt.addr, "alice@example.org");
+    assert_eq!(autocrypt.prefer_encrypt.as_deref(), Some("mutual"));
+    assert_eq!(
+        autocrypt.keydata,
+        "mDMEXEcE6RYJKwYBBAHaRw8BAQdArjWwk3FAqyiFbFBKT4TzXcVBqPTB3gmzlC/Ub7O1u120"
+    );
+
+    // More than one valid header
+    let raw = format!("{header}{header}\nBody\n");
+    let message = MessageParser::default().parse(&raw).unwrap();
+    assert_eq!(message.autocrypt(), None);
+    assert_eq!(
+        MessageParser::default()
+            .parse("Subject: No key\n\nBody\n")
+            .unwrap()
+            .autocrypt(),
+        None
+    );
+}
+
+#[test]
+fn test_parse_str() {
+    let raw = concat!(
+        "Subject: Caf\u{e9}\n",
+        "Content-Type: multipart/mixed; boundary=b\n\n",
+        "--b\n",
+        "Content-Type: text/plain; charset=utf-8\n\n",
+        "Cr\u{e8}me br\u{fb}l\u{e9}e\n",
+        "--b--\n"
+    );
+    let parser = MessageParser::default();
+    let message = parser.parse_str(raw).unwrap();
+    assert_eq!(message, parser.parse(raw.as_bytes()).unwrap());
+    assert_eq!(
+        message.body_text(0).as_deref(),
+        Some("Cr\u{e8}me br\u{fb}l\u{e9}e")
+    );
+
+    let owned: Message<'static> = raw.parse().unwrap();
+    assert_eq!(owned, message);
+    assert_eq!("".parse::<Message>(), Err(ParseError::Empty));
+}
+
+#[test]
+fn test_resent_blocks() {
+    let message = MessageParser::default()
+        .parse(concat!(
+            "Resent-From: Second Relay <second@example.org>\n",
+            "Resent-Date: Fri, 21 Nov 1997 11:00:00 -0600\n",
+            "Resent-To: Final <final@example.net>\n",
+            "Resent-Message-ID: <78910@example.org>\n",
+            "Received: from relay.example.org by mx.example.net\n",
+            "Resent-From: First Relay <first@example.com>\n",
+            "Resent-Date: Fri, 21 Nov 1997 10:00:00 -0600\n",
+            "Resent-To: Second Relay <second@example.org>\n",
+            "Resent-Cc: cc@example.com\n",
+            "Resent-Message-ID: <123456@example.com>\n",
+            "From: Author <author@example.com>\n",
+            "Date: Fri, 21 Nov 1997 09:00:00 -0600\n\n",
+            "Body\n"
+        ))
+        .unwrap();
+
+    assert_eq!(
+        message.resent_from().unwrap().first().unwrap().address(),
+        Some("second@example.org")
+    );
+    assert_eq!(
+        message.resent_to().unwrap().first().unwrap().address(),
+        Some("final@example.net")
+    );
+    assert_eq!(
+        message.resent_date().unwrap().to_rfc3339(),
+        "1997-11-21T11:00:00-06:00"
+    );
+    assert_eq!(message.resent_message_id(), Some("78910@example.org"));
+    assert_eq!(message.resent_cc(), None);
+    assert_eq!(message.resent_sender(), None);
+
+    let blocks = message.resent_blocks().collect::<Vec<_>>();
+    assert_eq!(blocks.len(), 2);
+    assert_eq!(blocks[1].message_id, Some("123456@example.com"));
+    assert_eq!(
+        blocks[1].cc.unwrap().first().unwrap().address(),
+        Some("cc@example.com")
+    );
+    assert_eq!(
+        blocks[1].date.unwrap().to_rfc3339(),
+        "1997-11-21T10:00:00-06:00"
+    );
+
+    assert!(MessageParser::default()
+        .parse("Subject: Not resent\n\nBody\n")
+        .unwrap()
+        .resent_blocks()
+        .next()
+        .is_none());
+}
+
+#[test]
+fn test_return_path() {
+    for (raw, expected) in [
+        (
+            "Return-Path: <bounce@example.com>\nReturn-Path: <older@example.com>\n\nBody\n",
+            Some("bounce@example.com"),
+        ),
+        ("Return-Path: <>\n\nBody\n", Some("")),
+        ("Return-Path: < >\nFrom: a@example.com\n\nBody\n", Some("")),
+        (
+            "Return-Path: user@example.org\n\nBody\n",
+            Some("user@example.org"),
+        ),
+        ("Return-Path: \n\nBody\n", None),
+        ("From: a@example.com\n\nBody\n", None),
+    ] {
+        let message = MessageParser::default().parse(raw).unwrap();
+        assert_eq!(
+            message
+                .return_path()
+                .as_ref()
+                .and_then(|addr| addr.address()),
+            expected,
+            "failed for {raw:?}"
+        );
+    }
+}
+
+#[test]
+fn test_uudecode() {
+    let raw = concat!(
+        "Subject: Legacy attachment\n",
+        "Content-Type: multipart/mixed; boundary=b\n\n",
+        "--b\n",
+        "Content-Type: text/plain\n\n",
+        "See attached.\n",
+        "begin 644 cat.txt\n",
+        "#0V%T\n",
+        "`\n",
+        "end\n",
+        "Bye.\n",
+        "--b\n",
+        "Content-Type: text/plain\n\n",
+        "Second part\n",
+        "--b--\n"
+    );
+    let message = MessageParser::default()
+        .with_uudecode(true)
+        .parse(raw)
+        .unwrap();
+
+    assert_eq!(message.body_text(0).unwrap(), "See attached.\nBye.");
+    assert_eq!(message.parts.len(), 4);
+    // The extracted part is detached from the MIME tree
+    assert_eq!(message.parts[0].sub_parts(), Some(&[1, 3][..]));
+    assert!(message.parts[2].is_uuencoded());
+    assert_eq!(message.attachment_count(), 1);
+    let attachment = message.attachment(0).unwrap();
+    assert_eq!(attachment.contents(), b"Cat");
+    assert_eq!(attachment.attachment_name(), Some("cat.txt"));
+    assert!(attachment.is_content_type("application", "octet-stream"));
+    assert_eq!(attachment.uuencode_mode(), Some(0o644));
+    assert!(attachment.is_uuencoded());
+    assert!(!message.parts[1].is_uuencoded());
+    assert_eq!(message.body_text(1).unwrap(), "Second part");
+
+    // Single part message, disabled by default
+    let raw = "Subject: Hi\n\nbegin 600 a.bin\n!80\nend\n";
+    let message = MessageParser::default()
+        .with_uudecode(true)
+        .parse(raw)
+        .unwrap();
+    assert_eq!(message.body_text(0).unwrap(), "");
+    assert_eq!(message.attachment(0).unwrap().contents(), b"a");
+    assert_eq!(message.parts.len(), 2);
+    assert_eq!(message.parts[0].sub_parts(), None);
+    assert!(message.parts[1].is_uuencoded());
+    assert!(message
+        .body_structure(false)
+        .starts_with("(\"text\" \"plain\""));
+    let message = MessageParser::default().parse(raw).unwrap();
+    assert_eq!(message.attachment_count(), 0);
+    assert_eq!(message.body_text(0).unwrap(), "begin 600 a.bin\n!80\nend\n");
+}
+
+#[test]
+fn test_delivery_status() {
+    let message = MessageParser::default()
+        .parse(concat!(
+            "From: MAILER-DAEMON@mx.example.com\n",
+            "Content-Type: multipart/report; report-type=delivery-status; boundary=b\n\n",
+            "--b\n",
+            "Content-Type: text/plain\n\n",
+            "Delivery failed.\n",
+            "--b\n",
+            "Content-Type: message/delivery-status\n\n",
+            "Reporting-MTA: dns; mx.example.com\n",
+            "Original-Envelope-Id: 0123456789\n",
+            "Arrival-Date: Mon, 7 Oct 2024 10:00:00 +0000\n",
+            "\n",
+            "Original-Recipient: rfc822;jdoe@example.org\n",
+            "Final-Recipient: rfc822; jdoe@example.org\n",
+            "Action: failed\n",
+            "Status: 5.1.1\n",
+            "Remote-MTA: dns; mail.example.org\n",
+            "Diagnostic-Code: smtp; 550 5.1.1 <jdoe@example.org>:\n",
+            "    Recipient address rejected: User unknown\n",
+            "\n\n",
+            "Final-Recipient: rfc822; other@example.net\n",
+            "Action: delayed\n",
+            "Status: 4.4.7\n",
+            "--b--\n"
+        ))
+        .unwrap();
+
+    assert_eq!(message.parts[1].delivery_status(), None);
+    let status = message.parts[2].delivery_status().unwrap();
+    assert_eq!(
+        status,
+        DeliveryStatus {
+            reporting_mta: Some("dns; mx.example.com".into()),
+            original_envelope_id: Some("0123456789".into()),
+            recipients: vec![
+                RecipientStatus {
+                    original_recipient: Some("rfc822;jdoe@example.org".into()),
+                    final_recipient: Some("rfc822; jdoe@example.org".into()),
+                    action: Some("failed".into()),
+                    status: Some("5.1.1".into()),
+                    remote_mta: Some("dns; mail.example.org".into()),
+                    diagnostic_code: Some(
+                        "smtp; 550 5.1.1 <jdoe@example.org>: Recipient address rejected: User unknown"
+                            .into()
+                    ),
+                },
+                RecipientStatus {
+                    final_recipient: Some("rfc822; other@example.net".into()),
+                    action: Some("delayed".into()),
+                    status: Some("4.4.7".into()),
+                    ..Default::default()
+                },
+            ],
+        }
+    );
+}
+
+#[test]
+fn test_preserve_whitespace() {
+    let raw = "Subject:  Re:   Signed\n\t value \nComments: =?utf-8?Q?a?=  =?utf-8?Q?b?=\n\nBody\n";
+
+    let message = MessageParser::default()
+        .with_preserve_whitespace(true)
+        .parse(raw)
+        .unwrap();
+    assert_eq!(message.subject(), Some("  Re:   Signed\t value "));
+    assert_eq!(message.comments().as_text(), Some(" ab"));
+
+    let message = MessageParser::default().parse(raw).unwrap();
+    assert_eq!(message.subject(), Some("Re:   Signed value"));
+}
+
+#[test]
+fn test_raw_header_iter() {
+    let raw = concat!(
+        "Received: from a\n",
+        "Received: from b\n",
+        "Subject: =?utf-8?Q?caf=C3=A9?=\n",
+        "X-Folded : first\n",
+        " second\n",
+        "Content-Type: multipart/mixed; boundary=b\n\n",
+        "--b\n",
+        "Content-Type: text/plain\n\n",
+        "Text\n",
+        "--b--\n"
+    );
+    let message = MessageParser::default().parse(raw).unwrap();
+
+    assert_eq!(
+        message.parts[0]
+            .raw_header_iter(message.raw_message())
+            .collect::<Vec<_>>(),
+        [
+            (&b"Received"[..], &b" from a\n"[..]),
+            (b"Received", b" from b\n"),
+            (b"Subject", b" =?utf-8?Q?caf=C3=A9?=\n"),
+            (b"X-Folded", b" first\n second\n"),
+            (b"Content-Type", b" multipart/mixed; boundary=b\n"),
+        ]
+    );
+    assert_eq!(
+        message.parts[1]
+            .raw_header_iter(message.raw_message())
+            .collect::<Vec<_>>(),
+        [(&b"Content-Type"[..], &b" text/plain\n"[..])]
+    );
+}
+
+#[test]
+fn test_default_content_type() {
+    let message = MessageParser::default()
+        .parse(concat!(
+            "Content-Type: multipart/digest; boundary=d\n\n",
+            "--d\n\n",
+            "Subject: First digest entry\n\n",
+            "Entry body\n",
+            "--d\n",
+            "Content-Type: text\n\n",
+            "Text without subtype\n",
+            "--d\n",
+            "Content-Type: multipart; boundary=m\n\n",
+            "--m\n\n",
+            "Nested text\n",
+            "--m--\n",
+            "--d--\n"
+        ))
+        .unwrap();
+
+    let content_type = message.parts[1].effective_content_type();
+    assert_eq!(
+        (content_type.ctype(), content_type.subtype()),
+        ("message", Some("rfc822"))
+    );
+    assert!(message.parts[1].content_type().is_none());
+    assert_eq!(
+        message.parts[1].message().unwrap().parts[0]
+            .effective_content_type()
+            .subtype(),
+        Some("plain")
+    );
+
+    let content_type = message.parts[2].content_type().unwrap();
+    assert_eq!(
+        (content_type.ctype(), content_type.subtype()),
+        ("text", Some("plain"))
+    );
+    assert_eq!(
+        message.parts[2].effective_content_type(),
+        message.parts[2].content_type().unwrap()
+    );
+    assert!(message.text_body.contains(&2));
+
+    assert_eq!(
+        message.parts[3].content_type().unwrap().subtype(),
+        Some("mixed")
+    );
+    assert_eq!(message.parts[3].sub_parts(), Some(&[4][..]));
+    assert_eq!(message.body_text(1).as_deref(), Some("Nested text"));
+}
+
+#[test]
+fn test_encoding_problems() {
+    let message = MessageParser::default()
+        .parse(concat!(
+            "Content-Type: multipart/mixed; boundary=b\n\n",
+            "--b\n",
+            "Content-Type: text/plain\n\n",
+            "Fine\n",
+            "--b\n",
+            "Content-Type: message/rfc822\n\n",
+            "Subject: Nested\n",
+            "Content-Type: text/plain\n",
+            "Content-Transfer-Encoding: quoted-printable\n\n",
+            "Bad =ZZ escape\n",
+            "--b--\n"
+        ))
+        .unwrap();
+
+    assert!(message.has_encoding_problems());
+    assert_eq!(message.problem_parts().collect::<Vec<_>>(), [2]);
+    assert!(!message.parts[2].is_encoding_problem);
+    let nested = message.parts[2].message().unwrap();
+    assert_eq!(nested.problem_parts().collect::<Vec<_>>(), [0]);
+
+    assert!(!MessageParser::default()
+        .parse("Subject: Fine\n\nBody\n")
+        .unwrap()
+        .has_encoding_problems());
+}
+
+#[test]
+fn test_read_receipt() {
+    let raw = concat!(
+        "From: Jane <jane@example.com>\n",
+        "Disposition-Notification-To: Jane <jane@example.com>,\n",
+        " receipts@example.com\n",
+        "Return-Receipt-To: <legacy@example.com>\n",
+        "Original-Message-ID: <original@example.com>\n\n",
+        "Body\n"
+    );
+    for parser in [
+        MessageParser::default(),
+        MessageParser::default()
+            .header_address("Disposition-Notification-To")
+            .header_address("Return-Receipt-To"),
+    ] {
+        let message = parser.parse(raw).unwrap();
+        let read_receipt_to = message.read_receipt_to().unwrap();
+        assert_eq!(
+            read_receipt_to
+                .iter()
+                .map(|addr| addr.address().unwrap())
+                .collect::<Vec<_>>(),
+            ["jane@example.com", "receipts@example.com"]
+        );
+        assert_eq!(read_receipt_to.first().unwrap().name(), Some("Jane"));
+        assert_eq!(
+            message
+                .return_receipt_to()
+                .unwrap()
+                .first()
+                .unwrap()
+                .address(),
+            Some("legacy@example.com")
+        );
+        assert_eq!(
+            message.original_message_id().as_deref(),
+            Some("original@example.com")
+        );
+    }
+
+    let message = MessageParser::default()
+        .parse("Subject: No receipt\n\nBody\n")
+        .unwrap();
+    assert_eq!(message.read_receipt_to(), None);
+    assert_eq!(message.return_receipt_to(), None);
+    assert_eq!(message.original_message_id(), None);
+}
+
+#[test]
+fn test_content_duration() {
+    for (header, expected) in [
+        ("Content-Duration: 33\n", Some(33)),
+        ("Content-Duration:  (voicemail) 33 (seconds)\n", Some(33)),
+        ("Content-Duration:\n 4294967295\n", Some(u32::MAX)),
+        ("Content-Duration: 4294967296\n", None),
+        ("Content-Duration: +33\n", None),
+        ("Content-Duration: 3 3\n", None),
+        ("Content-Duration: 33s\n", None),
+        ("Content-Duration: (none)\n", None),
+        ("X-Duration: 33\n", None),
+    ] {
+        let raw = format!(
+            concat!(
+                "Content-Type: multipart/mixed; boundary=b\n\n",
+                "--b\n",
+                "Content-Type: audio/basic\n",
+                "{}\n",
+                "audio\n",
+                "--b--\n"
+            ),
+            header
+        );
+        let message = MessageParser::default().parse(&raw).unwrap();
+        assert_eq!(
+            message.attachment(0).unwrap().content_duration(),
+            expected,
+            "failed for {header:?}"
+        );
+    }
+}
+
+#[test]
+fn test_mime_version() {
+    for (header, expected) in [
+        ("MIME-Version: 1.0\n", Some("1.0")),
+        (
+            "MIME-Version: 1.0 (produced by MetaSend Vx.x)\n",
+            Some("1.0"),
+        ),
+        (
+            "MIME-Version: (produced by \\(Meta\\)Send) 1.(x)0\n",
+            Some("1.0"),
+        ),
+        ("MIME-Version:\n 1.0\n (folded)\n", Some("1.0")),
+        ("MIME-Version: (only a comment)\n", None),
+        ("MIME-Version:\n", None),
+        ("X-Other: 1.0\n", None),
+    ] {
+        let raw = format!("{header}Subject: Version\n\nBody\n");
+        let message = MessageParser::default().parse(&raw).unwrap();
+        assert_eq!(
+            message.mime_version().as_deref(),
+            expected,
+            "failed for {header:?}"
+        );
+    }
+
+    let message = MessageParser::default()
+        .parse("MIME-Version: 1.0\n\nBody\n")
+        .unwrap();
+    assert!(matches!(
+        message.mime_version(),
+        Some(std::borrow::Cow::Borrowed("1.0"))
+    ));
+}
+
+#[test]
+fn test_binhex() {
+    let raw = concat!(
+        "Subject: Mac attachment\n",
+        "Content-Type: multipart/mixed; boundary=b\n\n",
+        "--b\n",
+        "Content-Type: text/plain\n\n",
+        "See attached.\n",
+        "--b\n",
+        "Content-Type: application/mac-binhex40; name=\"Read Me.hqx\"\n\n",
+        "(This file must be converted with BinHex 4.0)\n\n",
+        ":\"e*PB@3J6@8!9%9B9(4dH(3\"!*!%&`#3!`5AK%KPE'a[,#\"0B@-K$3#3#T!!Z\"Y5\n",
+        "8e*$MlF:\n",
+        "--b\n",
+        "Content-Type: application/mac-binhex40\n\n",
+        ":\"e*PB@3J6@8!9%9B9(4dH(3\"!*!%&`#3!`5AK%KPE'a[:\n",
+        "--b--\n"
+    );
+    let message = MessageParser::default()
+        .with_binhex(true)
+        .parse(raw)
+        .unwrap();
+
+    let attachment = message.attachment(0).unwrap();
+    assert_eq!(
+        attachment.contents(),
+        b"Hello, Mac!\r\0\0\0\0\0\0\0\0\0\0\x90"
+    );
+    assert_eq!(
+        attachment.binhex_header(),
+        Some(&BinHexHeader {
+            filename: "Read Me".into(),
+            file_type: *b"TEXT",
+            creator: *b"ttxt",
+            flags: 0x0100,
+        })
+    );
+    assert!(!attachment.is_encoding_problem);
+
+    // Malformed streams are kept as is
+    let attachment = message.attachment(1).unwrap();
+    assert_eq!(attachment.binhex_header(), None);
+    assert!(attachment.is_encoding_problem);
+    assert!(attachment
+        .contents()
+        .starts_with(b":\"e*PB@3J6@8!9%9B9(4dH(3"));
+    assert!(message.parts[1].binhex_header().is_none());
+
+    // Disabled by default
+    let message = MessageParser::default().parse(raw).unwrap();
+    let attachment = message.attachment(0).unwrap();
+    assert_eq!(attachment.binhex_header(), None);
+    assert!(attachment.contents().starts_with(b"(This file"));
+    assert!(!message.attachment(1).unwrap().is_encoding_problem);
+}
+
+#[test]
+fn test_thread_index() {
+    let message = MessageParser::default()
+        .parse(concat!(
+            "Subject: RE: Quarterly report\n",
+            "Thread-Topic: =?utf-8?q?Quarterly_report_=E2=80=93_Q1?=\n",
+            "Thread-Index: AdpHnct5EBESExQVFhcYGRobHB0e\n",
+            " HwAAEjRW\n\n",
+            "Body\n"
+        ))
+        .unwrap();
+    assert_eq!(
+        message.thread_topic().as_deref(),
+        Some("Quarterly report – Q1")
+    );
+    let thread_index = message.thread_index().unwrap();
+    assert_eq!(thread_index.date.to_rfc3339(), "2024-01-15T10:30:00Z");
+    assert_eq!(thread_index.guid[0], 0x10);
+    assert_eq!(thread_index.child_blocks, 1);
+
+    let message = MessageParser::default()
+        .parse("Subject: Hi\nThread-Index: AdpH\n\nBody\n")
+        .unwrap();
+    assert_eq!(message.thread_index(), None);
+    assert_eq!(message.thread_topic(), None);
+}
+
+#[test]
+fn test_header_name_whitespace() {
+    let raw = concat!(
+        "Subject : Hello\n",
+        "From\t: John Doe <john@example.com>\n",
+        "X-Foo  :bar\n\n",
+        "Body\n"
+    );
+    let message = MessageParser::default().parse(raw).unwrap();
+
+    assert_eq!(message.subject(), Some("Hello"));
+    assert_eq!(
+        message.from().unwrap().first().unwrap().address(),
+        Some("john@example.com")
+    );
+    assert_eq!(message.header_raw("X-Foo"), Some("bar\n"));
+    assert_eq!(
+        message
+            .headers()
+            .iter()
+            .map(|header| header.name())
+            .collect::<Vec<_>>(),
+        ["Subject", "From", "X-Foo"]
+    );
+}
+
+#[test]
+fn test_header_parser() {
+    let raw = concat!(
+        "From: Jane <jane@example.com>\n",
+        "X-Original-Sender: John <john@example.com>\n",
+        "X-Received-Date: Sat, 20 Nov 2021 14:22:01 -0800\n",
+        "X-Tags: red, green\n",
+        "X-Note: =?utf-8?q?caf=C3=A9?=\n",
+        "X-Secret: hidden\n\n",
+        "Body\n"
+    );
+    let message = MessageParser::new()
+        .with_minimal_headers()
+        .with_header_parser("X-Original-Sender", HeaderParser::Address)
+        .with_header_parser("X-Received-Date", HeaderParser::Date)
+        .with_header_parser("X-Tags", HeaderParser::CommaSeparated)
+        .with_header_parser("X-Note", HeaderParser::Text)
+        .with_header_parser("X-Secret", HeaderParser::Ignore)
+        .parse(raw)
+        .unwrap();
+
+    assert_eq!(
+        message.from().unwrap().first().unwrap().address(),
+        Some("jane@example.com")
+    );
+    assert_eq!(
+        message
+            .header("X-Original-Sender")
+            .and_then(|value| value.as_address())
+            .and_then(|address| address.first())
+            .and_then(|addr| addr.address()),
+        Some("john@example.com")
+    );
+    assert_eq!(
+        message
+            .header("X-Received-Date")
+            .and_then(|value| value.as_datetime())
+            .map(|date| date.to_rfc3339()),
+        Some("2021-11-20T14:22:01-08:00".to_string())
+    );
+    assert_eq!(
+        message
+            .header("X-Tags")
+            .and_then(|value| value.as_text_list()),
+        Some(vec!["red", "green"])
+    );
+    assert_eq!(
+        message.header("X-Note").and_then(|value| value.as_text()),
+        Some("café")
+    );
+    assert_eq!(message.header("X-Secret"), Some(&HeaderValue::Empty));
+
+    // Other headers keep their built-in parser
+    let raw = concat!(
+        "Received: from mx.example.com by mail.example.org; Sat, 20 Nov 2021 14:22:01 -0800\n",
+        "From: Jane <jane@example.com>\n",
+        "To: John <john@example.com>, ann@example.com\n",
+        "Subject: =?utf-8?q?caf=C3=A9?=\n",
+        "Date: Sat, 20 Nov 2021 14:22:01 -0800\n",
+        "Message-ID: <1234@example.com>\n",
+        "References: <a@example.com> <b@example.com>\n",
+        "Keywords: red, green\n",
+        "List-Id: Example list <list.example.com>\n",
+        "Disposition-Notification-To: jane@example.com\n",
+        "MIME-Version: 1.0\n",
+        "X-Original-Sender: John <john@example.com>\n",
+        "Content-Type: text/plain; charset=utf-8\n",
+        "Content-Transfer-Encoding: 8bit\n\n",
+        "Body\n"
+    );
+    let message = MessageParser::default()
+        .with_header_parser("X-Original-Sender", HeaderParser::Address)
+        .parse(raw)
+        .unwrap();
+    let expected = MessageParser::default().parse(raw).unwrap();
+    assert_eq!(
+        message.from().unwrap().first().unwrap().address(),
+        Some("jane@example.com")
+    );
+    assert_eq!(message.subject(), Some("café"));
+    for (header, expected) in message.headers().iter().zip(expected.headers()) {
+        if header.name() != "X-Original-Sender" {
+            assert_eq!(header, expected);
+        } else {
+            assert!(header.value().as_address().is_some());
+        }
+    }
+    assert_eq!(message.body_text(0), expected.body_text(0));
+
+    // Regardless of the order in which the parsers are added
+    let message = MessageParser::default()
+        .header_text("X-Note")
+        .with_header_parser("X-Original-Sender", HeaderParser::Address)
+        .parse(raw)
+        .unwrap();
+    assert_eq!(message.headers()[..11], expected.headers()[..11]);
+    assert_eq!(message.subject(), Some("café"));
+}
+
+#[test]
+fn test_nested_messages() {
+    let raw = concat!(
+        "From: outer@example.com\n",
+        "Subject: Outer\n",
+        "Content-Type: multipart/mixed; boundary=outer\n\n",
+        "--outer\n",
+        "Content-Type: text/plain\n\n",
+        "Forwarding two messages.\n",
+        "--outer\n",
+        "Content-Type: message/rfc822\n\n",
+        "From: first@example.com\n",
+        "Subject: First\n",
+        "Content-Type: multipart/mixed; boundary=inner\n\n",
+        "--inner\n",
+        "Content-Type: text/plain\n\n",
+        "First body\n",
+        "--inner\n",
+        "Content-Type: message/rfc822\n\n",
+        "From: deepest@example.com\n",
+        "Subject: Deepest\n\n",
+        "Deepest body\n",
+        "--inner--\n",
+        "--outer\n",
+        "Content-Type: message/rfc822\n\n",
+        "From: second@example.com\n",
+        "Subject: Second\n\n",
+        "Second body\n",
+        "--outer--\n"
+    );
+    let message = MessageParser::default().parse(raw).unwrap();
+
+    assert_eq!(
+        message
+            .nested_messages()
+            .map(|nested| nested.subject().unwrap())
+            .collect::<Vec<_>>(),
+        ["First", "Deepest", "Second"]
+    );
+
+    let first = message.attachment(0).unwrap().message().unwrap();
+    assert_eq!(
+        first.from().unwrap().first().unwrap().address(),
+        Some("first@example.com")
+    );
+    assert_eq!(first.body_text(0).unwrap(), "First body");
+    assert_eq!(
+        first
+            .nested_messages()
+            .map(|nested| nested.subject().unwrap())
+            .collect::<Vec<_>>(),
+        ["Deepest"]
+    );
+
+    let message = MessageParser::default()
+        .parse("Subject: Flat\n\nBody\n")
+        .unwrap();
+    assert_eq!(message.nested_messages().count(), 0);
+
+    // Truncated nested message
+    let message = MessageParser::default()
+        .parse("Content-Type: multipart/mixed; boundary=x\n\n--x\nContent-Type: message/rfc822\n\n")
+        .unwrap();
+    assert!(message
+        .parts
+        .iter()
+        .any(|part| matches!(&part.body, PartType::Message(nested) if nested.parts.is_empty())));
+    assert_eq!(message.nested_messages().count(), 0);
+}
+
+#[test]
+fn test_best_alternative() {
+    let raw = concat!(
+        "Subject: Alternatives\n",
+        "Content-Type: multipart/alternative; boundary=b\n\n",
+        "--b\n",
+        "Content-Type: text/plain\n\n",
+        "Plain\n",
+        "--b\n",
+        "Content-Type: text/plain\n\n",
+        "Better plain\n",
+        "--b\n",
+        "Content-Type: multipart/related; boundary=r\n\n",
+        "--r\n",
+        "Content-Type: text/html\n\n",
+        "<p>Related <b>HTML</b></p>\n",
+        "--r\n",
+        "Content-Type: image/png\n\n",
+        "PNG\n",
+        "--r--\n",
+        "--b--\n"
+    );
+    let message = MessageParser::default().parse(raw).unwrap();
+    assert_eq!(message.best_text().unwrap(), "Better plain");
+    assert_eq!(message.best_html().unwrap(), "<p>Related <b>HTML</b></p>");
+
+    // Only HTML alternatives
+    let message = MessageParser::default()
+        .parse(concat!(
+            "Content-Type: multipart/mixed; boundary=m\n\n",
+            "--m\n",
+            "Content-Type: multipart/alternative; boundary=b\n\n",
+            "--b\n",
+            "Content-Type: text/html\n\n",
+            "<p>First</p>\n",
+            "--b\n",
+            "Content-Type: text/html\n\n",
+            "<p>Second</p>\n",
+            "--b--\n",
+            "--m\n",
+            "Content-Type: application/pdf\n\n",
+            "PDF\n",
+            "--m--\n"
+        ))
+        .unwrap();
+    assert_eq!(message.best_text().unwrap(), "Second\n");
+    assert_eq!(message.best_html().unwrap(), "<p>Second</p>");
+
+    // Only plain text alternatives and no alternatives at all
+    for raw in [
+        concat!(
+            "Content-Type: multipart/alternative; boundary=b\n\n",
+            "--b\n",
+            "Content-Type: text/plain\n\n",
+            "Hello\n",
+            "--b--\n"
+        ),
+        "Subject: Plain\n\nHello",
+    ] {
+        let message = MessageParser::default().parse(raw).unwrap();
+        assert_eq!(message.best_text().unwrap(), "Hello");
+        assert_eq!(
+            message.best_html().unwrap(),
+            "<html><body>Hello</body></html>"
+        );
+    }
+}
+
+#[test]
+fn test_attachments_filter() {
+    let raw = concat!(
+        "From: outer@example.com\n",
+        "Content-Type: multipart/mixed; boundary=outer\n\n",
+        "--outer\n",
+        "Content-Type: multipart/related; boundary=related\n\n",
+        "--related\n",
+        "Content-Type: text/html\n\n",
+        "<img src=\"cid:logo\">\n",
+        "--related\n",
+        "Content-Type: image/png\n",
+        "Content-ID: <logo>\n",
+        "Content-Disposition: inline\n\n",
+        "logo\n",
+        "--related--\n",
+        "--outer\n",
+        "Content-Type: image/jpeg; name=\"photo.JPG\"\n",
+        "Content-Disposition: attachment\n\n",
+        "photo\n",
+        "--outer\n",
+        "Content-Type: application/pdf\n",
+        "Content-Disposition: attachment; filename=\"report.pdf\"\n\n",
+        "report\n",
+        "--outer\n",
+        "Content-Type: message/rfc822\n\n",
+        "From: inner@example.com\n",
+        "Content-Type: multipart/mixed; boundary=inner\n\n",
+        "--inner\n",
+        "Content-Type: text/plain\n\n",
+        "Inner body\n",
+        "--inner\n",
+        "Content-Type: image/gif\n",
+        "Content-Disposition: attachment; filename=\"anim.gif\"\n\n",
+        "anim\n",
+        "--inner\n",
+        "Content-Type: application/pdf\n",
+        "Content-Disposition: attachment; filename=\"invoice.PDF\"\n\n",
+        "invoice\n",
+        "--inner--\n",
+        "--outer--\n"
+    );
+    let message = MessageParser::default().parse(raw).unwrap();
+    let names = |parts: Vec<&MessagePart>| {
+        parts
+            .into_iter()
+            .map(|part| part.attachment_name().unwrap_or_default().to_string())
+            .collect::<Vec<_>>()
+    };
+
+    // The inline logo is excluded, nested attachments are included
+    assert_eq!(
+        names(message.image_attachments().collect()),
+        ["photo.JPG", "anim.gif"]
+    );
+    assert_eq!(
+        names(message.attachments_with_extension("pdf").collect()),
+        ["report.pdf", "invoice.PDF"]
+    );
+    assert_eq!(
+        names(message.attachments_with_extension(".jpg").collect()),
+        ["photo.JPG"]
+    );
+    assert_eq!(message.attachments_with_extension("png").count(), 0);
+    assert_eq!(
+        message
+            .attachments_filter(|part| part.is_message())
+            .filter_map(|part| part.message())
+            .map(|nested| nested.body_text(0).unwrap().into_owned())
+            .collect::<Vec<_>>(),
+        ["Inner body"]
+    );
+}